@@ -0,0 +1,511 @@
+use std::ops::RangeInclusive;
+
+use crate::{Access, ConditionError, Cpu, IoBus, MemoryBus, Reg8, RegPair, Registers, StepError, Variant};
+
+/// What makes a breakpoint stop the processor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Execution reaches the address.
+    Address(u16),
+    /// An instruction reads memory in the range.
+    Read(RangeInclusive<u16>),
+    /// An instruction writes memory in the range.
+    Write(RangeInclusive<u16>),
+    /// An instruction reads from a port in the range.
+    In(RangeInclusive<u8>),
+    /// An instruction writes to a port in the range.
+    Out(RangeInclusive<u8>),
+    /// The condition holds before an instruction is executed.
+    Condition(Condition),
+}
+
+impl Trigger {
+    fn accessed(&self, access: Access) -> bool {
+        match (self, access) {
+            (Trigger::Read(range), Access::Read(adr)) => range.contains(&adr),
+            (Trigger::Write(range), Access::Write(adr)) => range.contains(&adr),
+            (Trigger::In(range), Access::In(port)) => range.contains(&port),
+            (Trigger::Out(range), Access::Out(port)) => range.contains(&port),
+            _ => false,
+        }
+    }
+}
+
+/// A condition over the registers, flags and memory, such as
+/// `A == 0x24 && HL > 0x2000`.
+///
+/// Operands are the registers `A`, `B`, `C`, `D`, `E`, `H` and `L`, `M`
+/// for the byte addressed by HL, the pairs `BC`, `DE`, `HL`, `SP`, `PC`
+/// and `PSW` (or `AF`), the flags `Z`, `S`, `P`, `CY` and `AC`, the byte
+/// in memory at an address written as `[expr]`, and numbers, written in
+/// decimal, with a `0x` prefix or with an `H` suffix. Names are not case
+/// sensitive. Operands combine with `==`, `!=`, `<`, `<=`, `>`, `>=`,
+/// `&`, `|`, `!`, `&&`, `||` and parentheses, and any non-zero
+/// value counts as true.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Condition {
+    expr: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Expr {
+    Value(u32),
+    Reg(Reg8),
+    Pair(RegPair),
+    Pc,
+    Flag(u8),
+    Memory(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    BitOr,
+    BitAnd,
+}
+
+impl Condition {
+    pub fn parse(text: &str) -> Result<Condition, ConditionError> {
+        let mut parser = Parser { text, pos: 0 };
+        let expr = parser.binary(0)?;
+        parser.skip_space();
+        if parser.pos < text.len() {
+            return Err(ConditionError::Unexpected { pos: parser.pos });
+        }
+        Ok(Condition { expr })
+    }
+
+    /// Evaluates the condition. The byte addressed by `M` and by `[expr]`
+    /// operands is peeked from `mem`, so that evaluating it does not count
+    /// as an access, and `PSW` is packed as on `variant`.
+    pub fn holds<M: MemoryBus>(&self, reg: &Registers, variant: Variant, mem: &mut M) -> bool {
+        eval(&self.expr, reg, variant, mem) != 0
+    }
+}
+
+impl std::str::FromStr for Condition {
+    type Err = ConditionError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Condition::parse(text)
+    }
+}
+
+fn eval<M: MemoryBus>(expr: &Expr, reg: &Registers, variant: Variant, mem: &mut M) -> u32 {
+    match expr {
+        Expr::Value(value) => *value,
+        Expr::Reg(r) => reg.get8(*r).unwrap_or_else(|| mem.peek(reg.m())) as u32,
+        Expr::Pair(pair) => reg.get16(*pair, variant) as u32,
+        Expr::Pc => reg.pc as u32,
+        Expr::Flag(mask) => (u8::from(reg.flags) & mask != 0) as u32,
+        Expr::Memory(adr) => {
+            let adr = eval(adr, reg, variant, mem) as u16;
+            mem.peek(adr) as u32
+        },
+        Expr::Not(expr) => (eval(expr, reg, variant, mem) == 0) as u32,
+        Expr::Binary(BinOp::Or, lhs, rhs) => {
+            (eval(lhs, reg, variant, mem) != 0 || eval(rhs, reg, variant, mem) != 0) as u32
+        },
+        Expr::Binary(BinOp::And, lhs, rhs) => {
+            (eval(lhs, reg, variant, mem) != 0 && eval(rhs, reg, variant, mem) != 0) as u32
+        },
+        Expr::Binary(op, lhs, rhs) => {
+            let (lhs, rhs) = (eval(lhs, reg, variant, mem), eval(rhs, reg, variant, mem));
+            match op {
+                BinOp::Eq => (lhs == rhs) as u32,
+                BinOp::Ne => (lhs != rhs) as u32,
+                BinOp::Lt => (lhs < rhs) as u32,
+                BinOp::Le => (lhs <= rhs) as u32,
+                BinOp::Gt => (lhs > rhs) as u32,
+                BinOp::Ge => (lhs >= rhs) as u32,
+                BinOp::BitOr => lhs | rhs,
+                BinOp::BitAnd => lhs & rhs,
+                BinOp::Or | BinOp::And => unreachable!(),
+            }
+        },
+    }
+}
+
+/// Binary operators from the loosest to the tightest binding.
+const LEVELS: [&[(&str, BinOp)]; 5] = [
+    &[("||", BinOp::Or)],
+    &[("&&", BinOp::And)],
+    &[
+        ("==", BinOp::Eq),
+        ("!=", BinOp::Ne),
+        ("<=", BinOp::Le),
+        (">=", BinOp::Ge),
+        ("<", BinOp::Lt),
+        (">", BinOp::Gt),
+    ],
+    &[("|", BinOp::BitOr)],
+    &[("&", BinOp::BitAnd)],
+];
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_space(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_space();
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ConditionError> {
+        match self.eat(token) {
+            true => Ok(()),
+            false if self.pos == self.text.len() => Err(ConditionError::End),
+            false => Err(ConditionError::Unexpected { pos: self.pos }),
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, ConditionError> {
+        let Some(ops) = LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut lhs = self.binary(level + 1)?;
+        'operators: loop {
+            for &(token, op) in ops.iter() {
+                // Keep `||` and `&&` from being taken for `|` and `&`.
+                let doubled = matches!(op, BinOp::BitOr | BinOp::BitAnd)
+                    && self.rest().trim_start().starts_with(&token.repeat(2));
+                if !doubled && self.eat(token) {
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+                    continue 'operators;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ConditionError> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let expr = self.binary(0)?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        if self.eat("[") {
+            let expr = self.binary(0)?;
+            self.expect("]")?;
+            return Ok(Expr::Memory(Box::new(expr)));
+        }
+
+        self.skip_space();
+        let start = self.pos;
+        let len = self.rest().find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(self.rest().len());
+        if len == 0 {
+            return match start == self.text.len() {
+                true => Err(ConditionError::End),
+                false => Err(ConditionError::Unexpected { pos: start }),
+            };
+        }
+        let word = self.rest()[..len].to_ascii_uppercase();
+        self.pos += len;
+
+        if word.as_bytes()[0].is_ascii_digit() {
+            let value = if let Some(hex) = word.strip_prefix("0X") {
+                u32::from_str_radix(hex, 16)
+            } else if let Some(hex) = word.strip_suffix('H') {
+                u32::from_str_radix(hex, 16)
+            } else {
+                word.parse()
+            };
+            return value.map(Expr::Value).map_err(|_| ConditionError::Number { pos: start });
+        }
+
+        let expr = match word.as_str() {
+            "A" => Expr::Reg(Reg8::A),
+            "B" => Expr::Reg(Reg8::B),
+            "C" => Expr::Reg(Reg8::C),
+            "D" => Expr::Reg(Reg8::D),
+            "E" => Expr::Reg(Reg8::E),
+            "H" => Expr::Reg(Reg8::H),
+            "L" => Expr::Reg(Reg8::L),
+            "M" => Expr::Reg(Reg8::M),
+            "BC" => Expr::Pair(RegPair::BC),
+            "DE" => Expr::Pair(RegPair::DE),
+            "HL" => Expr::Pair(RegPair::HL),
+            "SP" => Expr::Pair(RegPair::SP),
+            "PSW" | "AF" => Expr::Pair(RegPair::PSW),
+            "PC" => Expr::Pc,
+            "Z" => Expr::Flag(crate::Flags::ZERO),
+            "S" => Expr::Flag(crate::Flags::SIGN),
+            "P" => Expr::Flag(crate::Flags::PARITY),
+            "CY" => Expr::Flag(crate::Flags::CARRY),
+            "AC" => Expr::Flag(crate::Flags::CARRY_AUX),
+            _ => return Err(ConditionError::Name { pos: start }),
+        };
+        Ok(expr)
+    }
+}
+
+/// The breakpoints set on a processor.
+#[derive(Default)]
+pub(crate) struct Breakpoints {
+    list: Vec<(usize, Trigger, Option<Condition>)>,
+    next_id: usize,
+    /// Whether any breakpoint watches memory or port accesses.
+    watches: bool,
+    /// Address of the breakpoint the processor stopped at,
+    /// which is not hit again when execution resumes.
+    resume: Option<u16>,
+    /// The first watched access made by the current instruction.
+    pub(crate) hit: Option<(usize, Access)>,
+}
+
+impl Breakpoints {
+    fn update(&mut self) {
+        self.watches = self.list.iter().any(|(_, trigger, _)| {
+            matches!(trigger, Trigger::Read(_) | Trigger::Write(_) | Trigger::In(_) | Trigger::Out(_))
+        });
+    }
+}
+
+impl<M: MemoryBus, I: IoBus> Cpu<M, I> {
+    /// Adds a breakpoint, which makes [`Cpu::step`] fail with
+    /// [`StepError::Breakpoint`] when triggered. Returns the identifier
+    /// reported when it is hit.
+    ///
+    /// Breakpoints on an address or a condition are hit before the
+    /// instruction is executed; executing again resumes past them.
+    /// Breakpoints on memory or port accesses are hit after the
+    /// instruction that made the access was executed.
+    pub fn add_breakpoint(&mut self, trigger: Trigger) -> usize {
+        self.insert_breakpoint(trigger, None)
+    }
+
+    /// Adds a breakpoint that is only hit when the condition also holds.
+    /// For memory and port accesses, the condition is evaluated when the
+    /// access is made.
+    pub fn add_conditional_breakpoint(&mut self, trigger: Trigger, condition: Condition) -> usize {
+        self.insert_breakpoint(trigger, Some(condition))
+    }
+
+    /// Removes the breakpoint with the given identifier.
+    /// Returns `false` if there is no such breakpoint.
+    pub fn remove_breakpoint(&mut self, id: usize) -> bool {
+        let len = self.breakpoints.list.len();
+        self.breakpoints.list.retain(|(other, _, _)| *other != id);
+        self.breakpoints.update();
+        self.breakpoints.list.len() != len
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.list.clear();
+        self.breakpoints.update();
+    }
+
+    fn insert_breakpoint(&mut self, trigger: Trigger, condition: Option<Condition>) -> usize {
+        let id = self.breakpoints.next_id;
+        self.breakpoints.next_id += 1;
+        self.breakpoints.list.push((id, trigger, condition));
+        self.breakpoints.update();
+        id
+    }
+
+    /// Checks the breakpoints on addresses and conditions
+    /// before the next instruction is executed.
+    pub(crate) fn check_breakpoints(&mut self) -> Result<(), StepError> {
+        if self.breakpoints.list.is_empty() || self.halted {
+            return Ok(());
+        }
+        let pc = self.reg.pc;
+        if self.breakpoints.resume.take() == Some(pc) {
+            return Ok(());
+        }
+        let mut holds = |condition: &Condition| condition.holds(&self.reg, self.variant, &mut self.mem);
+        for (id, trigger, condition) in &self.breakpoints.list {
+            let hit = match trigger {
+                Trigger::Address(adr) => *adr == pc,
+                Trigger::Condition(condition) => holds(condition),
+                _ => false,
+            };
+            if hit && condition.as_ref().is_none_or(&mut holds) {
+                self.breakpoints.resume = Some(pc);
+                return Err(StepError::Breakpoint { pc, id: *id, access: None });
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the access if it is the first one made
+    /// by the current instruction to hit a breakpoint.
+    pub(crate) fn watch(&mut self, access: Access) {
+        if !self.breakpoints.watches || self.breakpoints.hit.is_some() {
+            return;
+        }
+        let mut holds = |condition: &Condition| condition.holds(&self.reg, self.variant, &mut self.mem);
+        for (id, trigger, condition) in &self.breakpoints.list {
+            if trigger.accessed(access) && condition.as_ref().is_none_or(&mut holds) {
+                self.breakpoints.hit = Some((*id, access));
+                return;
+            }
+        }
+    }
+
+    /// Reports the access recorded by [`Cpu::watch`], if any.
+    pub(crate) fn take_watch(&mut self) -> Result<(), StepError> {
+        match self.breakpoints.hit.take() {
+            Some((id, access)) => {
+                Err(StepError::Breakpoint { pc: self.reg.pc, id, access: Some(access) })
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::Memory;
+
+    // LXI H,0100H; MVI M,7; MOV A,M; OUT 3; INR A; JMP 0008H
+    const PROGRAM: [u8; 12] = [0x21, 0x00, 0x01, 0x36, 0x07, 0x7e, 0xd3, 0x03, 0x3c, 0xc3, 0x08, 0x00];
+
+    fn cpu() -> Cpu {
+        Cpu::new(Memory::from_reader(&mut BufReader::new(&PROGRAM[..])).unwrap())
+    }
+
+    fn holds(text: &str, reg: &Registers, mem: &mut Memory) -> bool {
+        Condition::parse(text).unwrap().holds(reg, Variant::I8080, mem)
+    }
+
+    #[test]
+    fn address_breakpoints_stop_before_the_instruction() {
+        let mut cpu = cpu();
+        let id = cpu.add_breakpoint(Trigger::Address(5));
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.step().unwrap_err(), StepError::Breakpoint { pc: 5, id, access: None });
+        assert_eq!((cpu.pc(), cpu.reg().a), (5, 0));
+        cpu.step().unwrap();
+        assert_eq!((cpu.pc(), cpu.reg().a), (6, 7));
+
+        assert!(cpu.remove_breakpoint(id));
+        assert!(!cpu.remove_breakpoint(id));
+    }
+
+    #[test]
+    fn watchpoints_stop_after_the_access() {
+        let mut cpu = cpu();
+        let write = cpu.add_breakpoint(Trigger::Write(0x100..=0x1ff));
+        let out = cpu.add_breakpoint(Trigger::Out(3..=3));
+        cpu.step().unwrap();
+        let access = Some(Access::Write(0x100));
+        assert_eq!(cpu.step().unwrap_err(), StepError::Breakpoint { pc: 5, id: write, access });
+        assert_eq!(cpu.mem()[0x100], 7);
+        cpu.step().unwrap();
+        let access = Some(Access::Out(3));
+        assert_eq!(cpu.step().unwrap_err(), StepError::Breakpoint { pc: 8, id: out, access });
+    }
+
+    #[test]
+    fn conditions_are_evaluated_when_the_access_is_made() {
+        let mut cpu = cpu();
+        cpu.add_conditional_breakpoint(Trigger::Read(0x100..=0x100), "A == 7".parse().unwrap());
+        let id = cpu.add_conditional_breakpoint(Trigger::Read(0x100..=0x100), "A == 0".parse().unwrap());
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        let access = Some(Access::Read(0x100));
+        assert_eq!(cpu.step().unwrap_err(), StepError::Breakpoint { pc: 6, id, access });
+    }
+
+    #[test]
+    fn condition_breakpoints_stop_when_the_condition_holds() {
+        let mut cpu = cpu();
+        let id = cpu.add_breakpoint(Trigger::Condition("a == 0AH && !(pc < 9)".parse().unwrap()));
+        let err = loop {
+            if let Err(err) = cpu.step() {
+                break err;
+            }
+        };
+        assert_eq!(err, StepError::Breakpoint { pc: 9, id, access: None });
+        assert_eq!(cpu.reg().a, 10);
+        cpu.clear_breakpoints();
+        cpu.step().unwrap();
+    }
+
+    #[test]
+    fn conditions_read_registers_flags_and_memory() {
+        let mut mem = Memory::from_reader(&mut BufReader::new(&PROGRAM[..])).unwrap();
+        let mut reg = Registers::default();
+        reg.set_hl(3);
+        reg.set_psw(Variant::I8080, 0x4201);
+        assert!(holds("M == 0x36 && [HL] == 0x36 && [1] == 0", &reg, &mut mem));
+        assert!(holds("PSW == 0x4203 && AF == PSW && CY && !Z", &reg, &mut mem));
+        assert!(holds("A & 3 == 2 || B", &reg, &mut mem));
+        assert!(holds("H | L == 3 && SP == 0 && PC <= 0", &reg, &mut mem));
+    }
+
+    #[test]
+    fn malformed_conditions_are_rejected() {
+        assert_eq!(Condition::parse("A =="), Err(ConditionError::End));
+        assert_eq!(Condition::parse("(A"), Err(ConditionError::End));
+        assert_eq!(Condition::parse("A $ 1"), Err(ConditionError::Unexpected { pos: 2 }));
+        assert_eq!(Condition::parse("A 1"), Err(ConditionError::Unexpected { pos: 2 }));
+        assert_eq!(Condition::parse("A == 0xZZ"), Err(ConditionError::Number { pos: 5 }));
+        assert_eq!(Condition::parse("IX == 1"), Err(ConditionError::Name { pos: 0 }));
+    }
+
+    /// Counts the reads made by the processor, but not the ones peeked.
+    struct Counted {
+        bytes: Vec<u8>,
+        reads: usize,
+    }
+
+    impl MemoryBus for Counted {
+        fn read(&mut self, adr: u16) -> u8 {
+            self.reads += 1;
+            self.bytes[adr as usize]
+        }
+
+        fn write(&mut self, adr: u16, value: u8) {
+            self.bytes[adr as usize] = value;
+        }
+
+        fn peek(&mut self, adr: u16) -> u8 {
+            self.bytes[adr as usize]
+        }
+    }
+
+    #[test]
+    fn conditions_peek_at_memory() {
+        let mut cpu = Cpu::new(Counted { bytes: vec![0; 0x10000], reads: 0 });
+        cpu.add_breakpoint(Trigger::Condition("M == 1 || [0x100] == 1".parse().unwrap()));
+        cpu.add_conditional_breakpoint(Trigger::Address(0x1234), "[0x200] == 1".parse().unwrap());
+        for _ in 0..4 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.mem().reads, 4);
+    }
+}
@@ -0,0 +1,778 @@
+use crate::breakpoint::Breakpoints;
+use crate::i8085::{self, InterruptLine};
+use crate::{
+    emulate, z80, Access, Flags, IoBus, Journal, Memory, MemoryBus, Observer, Opcode, Ports, Reg8, RegPair,
+    Registers, StepError,
+};
+
+/// Record of a single executed instruction, as returned by [`Cpu::step`].
+#[derive(Clone, Copy, Debug)]
+pub struct Step {
+    /// Address the instruction was fetched from.
+    pub address: u16,
+    /// The decoded instruction.
+    pub opcode: Opcode,
+    /// Number of states (clock periods) the instruction took.
+    pub states: u32,
+    /// Whether the instruction was placed on the data bus by an interrupting
+    /// device, rather than fetched from memory.
+    pub interrupt: bool,
+    bytes: [u8; 4],
+    len: usize,
+}
+
+impl Step {
+    /// The raw bytes of the instruction, including its operands.
+    /// Empty if the instruction was not fetched from memory.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// The processor being emulated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    #[default]
+    I8080,
+    /// The 8085, which adds RIM and SIM, the TRAP and RST 5.5, 6.5 and 7.5
+    /// interrupt lines, serial I/O and its own instruction timings.
+    /// It also executes the 8085 instructions Intel left undocumented.
+    I8085,
+    /// The Z80, which adds an alternate register set, the IX and IY index
+    /// registers, the CB, DD, ED and FD prefixed instructions, interrupt
+    /// modes 0, 1 and 2 and its own flag semantics and timings.
+    Z80,
+}
+
+/// How the processor executes the undocumented opcodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Undocumented {
+    /// Execute them like the 8080 silicon does, as aliases of
+    /// NOP, JMP, RET and CALL.
+    #[default]
+    Hardware,
+    /// Execute them as single-byte NOPs, without consuming operands.
+    Nop,
+    /// Refuse to execute them and fail with [`StepError::Undocumented`].
+    Trap,
+}
+
+pub struct Cpu<M: MemoryBus = Memory, I: IoBus = Ports> {
+    pub(crate) reg: Registers,
+    pub(crate) mem: M,
+    pub(crate) io: I,
+    /// Interrupt enable flip-flop, set by EI and reset by DI.
+    pub(crate) inte: bool,
+    /// Set by EI to hold off interrupts until after the next instruction.
+    pub(crate) ei_delay: bool,
+    /// Instruction supplied by a device requesting an interrupt.
+    pub(crate) pending: Option<Opcode>,
+    pub(crate) halted: bool,
+    /// Total number of states elapsed since power-on.
+    pub(crate) cycles: u64,
+    pub(crate) variant: Variant,
+    pub(crate) undocumented: Undocumented,
+    pub(crate) i8085: i8085::State,
+    pub(crate) z80: z80::State,
+    pub(crate) journal: Option<Box<Journal>>,
+    pub(crate) observer: Option<Box<dyn Observer>>,
+    pub(crate) breakpoints: Breakpoints,
+}
+
+impl<M: MemoryBus> Cpu<M> {
+    /// Creates a processor connected to the given memory and
+    /// to an I/O bus without any devices.
+    pub fn new(mem: M) -> Self {
+        Cpu::with_io(mem, Ports::default())
+    }
+}
+
+impl<M: MemoryBus, I: IoBus> Cpu<M, I> {
+    pub fn with_io(mem: M, io: I) -> Self {
+        Cpu {
+            reg: Registers::default(),
+            mem,
+            io,
+            inte: false,
+            ei_delay: false,
+            pending: None,
+            halted: false,
+            cycles: 0,
+            variant: Variant::default(),
+            undocumented: Undocumented::default(),
+            i8085: i8085::State::default(),
+            z80: z80::State::default(),
+            journal: None,
+            observer: None,
+            breakpoints: Breakpoints::default(),
+        }
+    }
+
+    /// Puts the processor back into its power-on state.
+    /// The content of memory and the cycle counter are left untouched.
+    pub fn reset(&mut self) {
+        self.reg = Registers::default();
+        self.inte = false;
+        self.ei_delay = false;
+        self.pending = None;
+        self.halted = false;
+        self.i8085.reset();
+        self.z80.reset();
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+    }
+
+    /// Requests an interrupt. The given instruction, usually one of the
+    /// RST instructions, is executed as soon as interrupts are enabled,
+    /// without advancing the program counter. Acknowledging the interrupt
+    /// disables further interrupts and brings the processor out of a halt.
+    ///
+    /// The request stays pending until it is acknowledged; a new request
+    /// replaces one that has not been acknowledged yet.
+    pub fn interrupt(&mut self, opcode: Opcode) {
+        self.pending = Some(opcode);
+    }
+
+    /// Drives one of the additional interrupt lines of the 8085. TRAP and
+    /// RST 7.5 trigger on a rising edge, RST 6.5 and RST 5.5 are requested
+    /// for as long as the line is held high. Ignored on other processors.
+    ///
+    /// An acknowledged interrupt calls the vector of its line; TRAP
+    /// is acknowledged even when interrupts are disabled.
+    pub fn set_line(&mut self, line: InterruptLine, level: bool) {
+        self.i8085.set_line(line, level);
+    }
+
+    /// Sets the level of the serial input data line of the 8085, read by RIM.
+    pub fn set_sid(&mut self, level: bool) {
+        self.i8085.sid = level;
+    }
+
+    /// The level of the serial output data line of the 8085, set by SIM.
+    pub fn sod(&self) -> bool {
+        self.i8085.sod
+    }
+
+    /// Requests a non-maskable interrupt on the Z80, which calls 0x66 even
+    /// when interrupts are disabled. Ignored on other processors.
+    pub fn nmi(&mut self) {
+        self.z80.nmi = true;
+    }
+
+    /// Sets the byte an interrupting device places on the data bus when
+    /// the Z80 acknowledges an interrupt in mode 2.
+    pub fn set_interrupt_vector(&mut self, vector: u8) {
+        self.z80.vector = vector;
+    }
+
+    /// Fetches, decodes and executes a single instruction, or acknowledges
+    /// a pending interrupt. Fails with [`StepError::Halted`] without doing
+    /// anything while the processor is halted.
+    ///
+    /// Fails with [`StepError::Breakpoint`] when a breakpoint is hit,
+    /// see [`Cpu::add_breakpoint`].
+    pub fn step(&mut self) -> Result<Step, StepError> {
+        self.check_breakpoints()?;
+        let step = match self.journal.is_some() {
+            true => self.step_recorded(),
+            false => self.advance(),
+        };
+        let watch = self.take_watch();
+        let step = step?;
+        watch.map(|_| step)
+    }
+
+    pub(crate) fn advance(&mut self) -> Result<Step, StepError> {
+        match self.variant {
+            Variant::I8080 => {},
+            Variant::I8085 => {
+                if let Some(line) = self.i8085.take(self.inte && !self.ei_delay) {
+                    return self.acknowledge(Opcode::CALL(line.vector()));
+                }
+            },
+            Variant::Z80 => {
+                if std::mem::take(&mut self.z80.nmi) {
+                    let iff2 = self.inte;
+                    let step = self.acknowledge(Opcode::CALL(0x66));
+                    self.z80.iff2 = iff2;
+                    return step;
+                }
+            },
+        }
+        if self.inte && !self.ei_delay {
+            if let Some(opcode) = self.pending.take() {
+                let opcode = match (self.variant, self.z80.im) {
+                    (Variant::Z80, z80::InterruptMode::Mode1) => Opcode::RST_7,
+                    (Variant::Z80, z80::InterruptMode::Mode2) => {
+                        let adr = ((self.z80.i as u16) << 8) | self.z80.vector as u16;
+                        let rl = self.mem.read(adr);
+                        let rh = self.mem.read(adr.wrapping_add(1));
+                        Opcode::CALL(((rh as u16) << 8) | rl as u16)
+                    },
+                    _ => opcode,
+                };
+                return self.acknowledge(opcode);
+            }
+        }
+        self.ei_delay = false;
+
+        if self.halted {
+            return Err(StepError::Halted { pc: self.reg.pc });
+        }
+
+        // Undocumented opcodes executed as NOPs do not fetch their operands, so
+        // the instruction is only peeked at until its length is known.
+        let peek = self.undocumented == Undocumented::Nop;
+        let address = self.reg.pc;
+        let mut bytes = [0; 4];
+        let mut len = 0;
+        let opcode = Opcode::read(self.variant, || {
+            let byte = if peek { self.mem.peek(self.reg.pc) } else { self.mem.read(self.reg.pc) };
+            self.reg.pc = self.reg.pc.wrapping_add(1);
+            bytes[len] = byte;
+            len += 1;
+            byte
+        });
+
+        // A prefix followed by another prefix is executed on its own.
+        if let Opcode::Z80(z80::Opcode::NONI(_)) = opcode {
+            self.reg.pc = address.wrapping_add(1);
+            len = 1;
+        }
+
+        let opcode = match self.undocumented {
+            _ if !opcode.is_undocumented() => opcode,
+            Undocumented::Hardware => opcode,
+            Undocumented::Nop => {
+                self.reg.pc = address.wrapping_add(1);
+                len = 1;
+                Opcode::NOP
+            },
+            Undocumented::Trap => {
+                self.reg.pc = address;
+                return Err(StepError::Undocumented { pc: address, opcode });
+            },
+        };
+        if peek {
+            for (offset, byte) in bytes[..len].iter_mut().enumerate() {
+                *byte = self.mem.read(address.wrapping_add(offset as u16));
+            }
+        }
+
+        let states = self.execute(address, opcode)?;
+        Ok(self.executed(Step { address, opcode, states, interrupt: false, bytes, len }))
+    }
+
+    fn acknowledge(&mut self, opcode: Opcode) -> Result<Step, StepError> {
+        self.inte = false;
+        self.halted = false;
+        self.z80.iff2 = false;
+        if let Some(observer) = &mut self.observer {
+            observer.interrupt(opcode);
+        }
+
+        let address = self.reg.pc;
+        let states = self.execute(address, opcode)?;
+        Ok(self.executed(Step { address, opcode, states, interrupt: true, bytes: [0; 4], len: 0 }))
+    }
+
+    fn executed(&mut self, step: Step) -> Step {
+        if let Some(observer) = &mut self.observer {
+            observer.after_instruction(&step, &self.reg);
+            if self.halted {
+                observer.halt(self.reg.pc);
+            }
+        }
+        step
+    }
+
+    fn execute(&mut self, address: u16, opcode: Opcode) -> Result<u32, StepError> {
+        if let Some(observer) = &mut self.observer {
+            if self.variant == Variant::Z80 {
+                observer.before_z80_instruction(&self.z80, self.inte);
+            }
+            observer.before_instruction(address, opcode, &self.reg);
+        }
+        let states = match self.variant {
+            Variant::Z80 => z80::emulate(self, opcode),
+            _ => emulate(self, opcode),
+        };
+        let Some(states) = states else {
+            self.reg.pc = address;
+            return Err(StepError::Unimplemented { pc: address, opcode });
+        };
+
+        if let Some(access) = self.mem.take_fault().or_else(|| self.io.take_fault()) {
+            self.reg.pc = address;
+            return Err(StepError::IllegalAccess { pc: address, opcode, access });
+        }
+
+        self.cycles += states as u64;
+        Ok(states)
+    }
+
+    /// Executes instructions until at least the given number of states
+    /// have elapsed. Returns the number of states that were actually run,
+    /// which may overshoot by the length of the last instruction.
+    ///
+    /// When the processor halts, the remaining states are spent waiting
+    /// for an interrupt. Any other error stops execution and is returned.
+    pub fn run_for(&mut self, states: u64) -> Result<u64, StepError> {
+        let mut elapsed = 0;
+        while elapsed < states {
+            match self.step() {
+                Ok(step) => elapsed += step.states as u64,
+                Err(StepError::Halted { .. }) => {
+                    self.cycles += states - elapsed;
+                    elapsed = states;
+                },
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(elapsed)
+    }
+
+    /// Total number of states elapsed since power-on.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+    }
+
+    /// Registers and interrupt state that only exist on the Z80.
+    pub fn z80(&self) -> &z80::State {
+        &self.z80
+    }
+
+    pub fn z80_mut(&mut self) -> &mut z80::State {
+        &mut self.z80
+    }
+
+    pub fn undocumented(&self) -> Undocumented {
+        self.undocumented
+    }
+
+    pub fn set_undocumented(&mut self, undocumented: Undocumented) {
+        self.undocumented = undocumented;
+    }
+
+    pub fn interrupts_enabled(&self) -> bool {
+        self.inte
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn reg(&self) -> &Registers {
+        &self.reg
+    }
+
+    pub fn reg_mut(&mut self) -> &mut Registers {
+        &mut self.reg
+    }
+
+    /// Reads an 8-bit register, or the memory location
+    /// addressed by the H,L pair for [`Reg8::M`].
+    pub fn get8(&mut self, reg: Reg8) -> u8 {
+        match self.reg.get8(reg) {
+            Some(value) => value,
+            None => self.read(self.reg.m()),
+        }
+    }
+
+    /// Writes an 8-bit register, or the memory location
+    /// addressed by the H,L pair for [`Reg8::M`].
+    pub fn set8(&mut self, reg: Reg8, value: u8) {
+        match self.reg.get8_mut(reg) {
+            Some(r) => *r = value,
+            None => self.write(self.reg.m(), value),
+        }
+    }
+
+    /// Reads a register pair, with the flags in [`RegPair::PSW`]
+    /// packed as PUSH PSW does on the current processor.
+    pub fn get16(&self, pair: RegPair) -> u16 {
+        self.reg.get16(pair, self.variant)
+    }
+
+    /// Writes a register pair, with the flags in [`RegPair::PSW`]
+    /// unpacked as POP PSW does on the current processor.
+    pub fn set16(&mut self, pair: RegPair, value: u16) {
+        self.reg.set16(pair, self.variant, value);
+    }
+
+    pub fn flags(&self) -> &Flags {
+        &self.reg.flags
+    }
+
+    pub fn flags_mut(&mut self) -> &mut Flags {
+        &mut self.reg.flags
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.reg.pc
+    }
+
+    pub fn set_pc(&mut self, pc: u16) {
+        self.reg.pc = pc;
+    }
+
+    pub fn sp(&self) -> u16 {
+        self.reg.sp
+    }
+
+    pub fn set_sp(&mut self, sp: u16) {
+        self.reg.sp = sp;
+    }
+
+    /// Registers an observer that is notified of every instruction, memory
+    /// and port access, interrupt and halt. Replaces any previous observer.
+    pub fn set_observer<O: Observer + 'static>(&mut self, observer: O) {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Unregisters the observer and returns it.
+    pub fn take_observer(&mut self) -> Option<Box<dyn Observer>> {
+        self.observer.take()
+    }
+
+    pub fn mem(&self) -> &M {
+        &self.mem
+    }
+
+    pub fn mem_mut(&mut self) -> &mut M {
+        &mut self.mem
+    }
+
+    pub fn io(&self) -> &I {
+        &self.io
+    }
+
+    pub fn io_mut(&mut self) -> &mut I {
+        &mut self.io
+    }
+
+    pub(crate) fn load_sp(&mut self, rh: u8, rl: u8) {
+        self.reg.sp = ((rh as u16) << 8) | (rl as u16)
+    }
+
+    pub(crate) fn load_pc(&mut self, rh: u8, rl: u8) {
+        self.reg.pc = ((rh as u16) << 8) | (rl as u16)
+    }
+
+    pub(crate) fn read(&mut self, adr: u16) -> u8 {
+        let value = self.mem.read(adr);
+        if let Some(observer) = &mut self.observer {
+            observer.memory_read(adr, value);
+        }
+        self.watch(Access::Read(adr));
+        value
+    }
+
+    pub(crate) fn write(&mut self, adr: u16, value: u8) {
+        if let Some(journal) = &mut self.journal {
+            journal.record_write(adr, self.mem.peek(adr));
+        }
+        self.mem.write(adr, value);
+        if let Some(observer) = &mut self.observer {
+            observer.memory_write(adr, value);
+        }
+        self.watch(Access::Write(adr));
+    }
+
+    pub(crate) fn input(&mut self, port: u8) -> u8 {
+        let value = self.io.read_port(port);
+        if let Some(observer) = &mut self.observer {
+            observer.input(port, value);
+        }
+        self.watch(Access::In(port));
+        value
+    }
+
+    pub(crate) fn output(&mut self, port: u8, value: u8) {
+        self.io.write_port(port, value);
+        if let Some(observer) = &mut self.observer {
+            observer.output(port, value);
+        }
+        self.watch(Access::Out(port));
+    }
+
+    pub(crate) fn pop8(&mut self) -> u8 {
+        let res = self.read(self.reg.sp);
+        self.reg.sp = self.reg.sp.wrapping_add(1);
+        res
+    }
+
+    pub(crate) fn jump(&mut self, adr: u16, cond: bool) -> bool {
+        if cond {
+            self.reg.pc = adr;
+        }
+        cond
+    }
+
+    pub(crate) fn call(&mut self, adr: u16, cond: bool) -> bool {
+        if cond {
+            self.push((self.reg.pc >> 8) as u8, self.reg.pc as u8);
+            self.reg.pc = adr;
+        }
+        cond
+    }
+
+    pub(crate) fn ret(&mut self, cond: bool) -> bool {
+        if cond {
+            let rl = self.read(self.reg.sp);
+            let rh = self.read(self.reg.sp.wrapping_add(1));
+            let adr = ((rh as u16) << 8) | (rl as u16);
+            self.reg.pc = adr;
+            self.reg.sp = self.reg.sp.wrapping_add(2);
+        }
+        cond
+    }
+
+    pub(crate) fn push(&mut self, rh: u8, rl: u8) {
+        self.write(self.reg.sp.wrapping_sub(1), rh);
+        self.write(self.reg.sp.wrapping_sub(2), rl);
+        self.reg.sp = self.reg.sp.wrapping_sub(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    fn cpu(program: &[u8]) -> Cpu {
+        Cpu::new(Memory::from_reader(&mut BufReader::new(program)).unwrap())
+    }
+
+    #[test]
+    fn step_reports_the_executed_instruction() {
+        // LXI B,1234H; MVI A,56H
+        let mut cpu = cpu(&[0x01, 0x34, 0x12, 0x3e, 0x56]);
+
+        let step = cpu.step().unwrap();
+        assert_eq!(step.address, 0);
+        assert!(step.opcode == Opcode::LXI_BC(0x34, 0x12));
+        assert_eq!(step.bytes(), [0x01, 0x34, 0x12]);
+        assert_eq!(step.states, 10);
+        assert_eq!(cpu.reg().bc(), 0x1234);
+        assert_eq!(cpu.pc(), 3);
+
+        let step = cpu.step().unwrap();
+        assert_eq!(step.address, 3);
+        assert_eq!(step.bytes(), [0x3e, 0x56]);
+        assert_eq!(cpu.reg().a, 0x56);
+        assert_eq!(cpu.pc(), 5);
+    }
+
+    #[test]
+    fn run_for_stops_after_the_instruction_that_reaches_the_budget() {
+        // NOPs take 4 states each.
+        let mut cpu = cpu(&[]);
+        assert_eq!(cpu.run_for(10).unwrap(), 12);
+        assert_eq!(cpu.pc(), 3);
+        assert_eq!(cpu.run_for(0).unwrap(), 0);
+        assert_eq!(cpu.pc(), 3);
+    }
+
+    #[test]
+    fn reset_clears_the_registers_but_not_memory() {
+        // MVI A,56H; LXI SP,2000H
+        let mut cpu = cpu(&[0x3e, 0x56, 0x31, 0x00, 0x20]);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        cpu.flags_mut().set_carry(true);
+
+        cpu.reset();
+        assert_eq!(cpu.reg().a, 0);
+        assert!(!cpu.flags().carry());
+        assert_eq!(cpu.pc(), 0);
+        assert_eq!(cpu.sp(), 0);
+        assert_eq!(cpu.mem()[0], 0x3e);
+    }
+
+    #[test]
+    fn conditional_calls_and_returns_take_longer_when_taken() {
+        // LXI SP,0100H; CZ 0010H; CNZ 0010H; ... 0010: RZ; RNZ
+        let mut program = vec![0x31, 0x00, 0x01, 0xcc, 0x10, 0x00, 0xc4, 0x10, 0x00];
+        program.resize(0x10, 0);
+        program.extend([0xc8, 0xc0]);
+        let mut cpu = cpu(&program);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.step().unwrap().states, 11);
+        assert_eq!(cpu.pc(), 6);
+        assert_eq!(cpu.step().unwrap().states, 17);
+        assert_eq!(cpu.pc(), 0x10);
+        assert_eq!(cpu.step().unwrap().states, 5);
+        assert_eq!(cpu.step().unwrap().states, 11);
+        assert_eq!(cpu.pc(), 9);
+    }
+
+    #[test]
+    fn cycles_accumulate_across_steps_and_resets() {
+        // MVI A,1; NOP
+        let mut cpu = cpu(&[0x3e, 0x01, 0x00]);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.cycles(), 11);
+        cpu.reset();
+        assert_eq!(cpu.cycles(), 11);
+        cpu.run_for(7).unwrap();
+        assert_eq!(cpu.cycles(), 18);
+    }
+
+    #[test]
+    fn interrupts_wait_for_the_instruction_after_ei() {
+        // LXI SP,0100H; EI; NOP; NOP
+        let mut cpu = cpu(&[0x31, 0x00, 0x01, 0xfb, 0x00, 0x00]);
+        cpu.interrupt(Opcode::RST_7);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert!(cpu.interrupts_enabled());
+        assert_eq!(cpu.step().unwrap().address, 4);
+
+        let step = cpu.step().unwrap();
+        assert!(step.interrupt);
+        assert!(step.opcode == Opcode::RST_7);
+        assert_eq!(step.bytes(), []);
+        assert_eq!(cpu.pc(), 0x38);
+        assert_eq!(cpu.sp(), 0xfe);
+        assert_eq!((cpu.mem()[0xfe], cpu.mem()[0xff]), (5, 0));
+        assert!(!cpu.interrupts_enabled());
+    }
+
+    #[test]
+    fn an_interrupt_wakes_the_processor_from_a_halt() {
+        // LXI SP,0100H; EI; HLT
+        let mut cpu = cpu(&[0x31, 0x00, 0x01, 0xfb, 0x76]);
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.step().unwrap_err(), StepError::Halted { pc: 5 });
+        assert_eq!(cpu.pc(), 5);
+
+        cpu.interrupt(Opcode::CALL(0x1234));
+        let step = cpu.step().unwrap();
+        assert!(step.interrupt);
+        assert_eq!(step.states, 17);
+        assert!(!cpu.is_halted());
+        assert_eq!(cpu.pc(), 0x1234);
+        assert_eq!((cpu.mem()[0xfe], cpu.mem()[0xff]), (5, 0));
+    }
+
+    #[test]
+    fn run_for_waits_out_a_halt() {
+        // MVI A,1; HLT
+        let mut cpu = cpu(&[0x3e, 0x01, 0x76]);
+        assert_eq!(cpu.run_for(100).unwrap(), 100);
+        assert_eq!(cpu.cycles(), 100);
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.pc(), 3);
+    }
+
+    #[test]
+    fn unimplemented_instructions_leave_pc_at_the_fault() {
+        // EI; NOP
+        let mut cpu = cpu(&[0xfb, 0x00]);
+        cpu.step().unwrap();
+        cpu.interrupt(Opcode::RIM);
+        cpu.step().unwrap();
+        let err = cpu.step().unwrap_err();
+        assert_eq!(err, StepError::Unimplemented { pc: 2, opcode: Opcode::RIM });
+        assert_eq!(cpu.pc(), 2);
+        assert_eq!(cpu.cycles(), 8);
+    }
+
+    #[test]
+    fn stack_operations_wrap_around_the_address_space() {
+        // LXI SP,0FFFFH; XTHL; SPHL; RET
+        let mut cpu = cpu(&[0x31, 0xff, 0xff, 0xe3, 0xf9, 0xc9]);
+        cpu.mem_mut()[0xffff] = 0x34;
+        cpu.mem_mut()[0x0000] = 0x31;
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().m(), 0x3134);
+        assert_eq!((cpu.mem()[0xffff], cpu.mem()[0]), (0, 0));
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.sp(), 0x3134);
+        cpu.set_sp(0xffff);
+        cpu.step().unwrap();
+        assert_eq!(cpu.pc(), 0);
+        assert_eq!(cpu.sp(), 1);
+    }
+
+    #[test]
+    fn undocumented_opcodes_follow_the_configured_handling() {
+        // 0CBH (JMP) 0010H
+        let program = [0xcb, 0x10, 0x00];
+
+        let mut cpu = cpu(&program);
+        let step = cpu.step().unwrap();
+        assert!(step.opcode == Opcode::JMP_CB(0x10));
+        assert!(step.opcode.is_undocumented());
+        assert_eq!(step.states, 10);
+        assert_eq!(cpu.pc(), 0x10);
+
+        let mut cpu = self::cpu(&program);
+        cpu.set_undocumented(Undocumented::Nop);
+        let step = cpu.step().unwrap();
+        assert!(step.opcode == Opcode::NOP);
+        assert_eq!(step.bytes(), [0xcb]);
+        assert_eq!(cpu.pc(), 1);
+
+        let mut cpu = self::cpu(&program);
+        cpu.set_undocumented(Undocumented::Trap);
+        let err = cpu.step().unwrap_err();
+        assert_eq!(err, StepError::Undocumented { pc: 0, opcode: Opcode::JMP_CB(0x10) });
+        assert_eq!(cpu.pc(), 0);
+    }
+
+    /// Remembers the addresses read by the processor, but not the ones peeked.
+    struct Fetches {
+        bytes: Vec<u8>,
+        reads: Vec<u16>,
+    }
+
+    impl MemoryBus for Fetches {
+        fn read(&mut self, adr: u16) -> u8 {
+            self.reads.push(adr);
+            self.bytes[adr as usize]
+        }
+
+        fn write(&mut self, adr: u16, value: u8) {
+            self.bytes[adr as usize] = value;
+        }
+
+        fn peek(&mut self, adr: u16) -> u8 {
+            self.bytes[adr as usize]
+        }
+    }
+
+    #[test]
+    fn undocumented_nops_do_not_read_their_operands() {
+        // 0CBH (JMP) 0000H; JMP 0010H
+        let mut bytes = vec![0; 0x10000];
+        bytes[..6].copy_from_slice(&[0xcb, 0x00, 0x00, 0xc3, 0x10, 0x00]);
+        let mut cpu = Cpu::new(Fetches { bytes, reads: Vec::new() });
+        cpu.set_undocumented(Undocumented::Nop);
+        assert!(cpu.step().unwrap().opcode == Opcode::NOP);
+        assert_eq!(cpu.mem().reads, [0]);
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.pc(), 0x10);
+        assert_eq!(cpu.mem().reads, [0, 1, 2, 3, 4, 5]);
+    }
+}
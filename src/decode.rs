@@ -0,0 +1,112 @@
+use crate::{z80, DecodeError, Opcode, Variant};
+
+/// Decodes the 8080 instruction at the start of `bytes`,
+/// and returns it together with its length in bytes.
+pub fn decode(bytes: &[u8]) -> Result<(Opcode, usize), DecodeError> {
+    decode_with(Variant::I8080, bytes)
+}
+
+/// Decodes the instruction at the start of `bytes` for the given processor,
+/// and returns it together with its length in bytes.
+pub fn decode_with(variant: Variant, bytes: &[u8]) -> Result<(Opcode, usize), DecodeError> {
+    if bytes.is_empty() {
+        return Err(DecodeError::Empty);
+    }
+    let mut len = 0;
+    let opcode = Opcode::read(variant, || {
+        let byte = bytes.get(len).copied().unwrap_or(0);
+        len += 1;
+        byte
+    });
+    if len > bytes.len() {
+        return Err(DecodeError::Truncated { len, available: bytes.len() });
+    }
+    // A prefix followed by another prefix is an instruction on its own.
+    if let Opcode::Z80(z80::Opcode::NONI(_)) = opcode {
+        len = 1;
+    }
+    Ok((opcode, len))
+}
+
+/// An iterator over the instructions in a byte slice, yielding the
+/// address, the decoded instruction and its bytes.
+///
+/// Iteration ends at the first instruction that does not fit in the
+/// remaining bytes, which are available from [`Instructions::remainder`].
+pub struct Instructions<'a> {
+    variant: Variant,
+    bytes: &'a [u8],
+    address: u16,
+}
+
+impl<'a> Instructions<'a> {
+    /// Walks 8080 instructions, with the first byte at address `origin`.
+    pub fn new(bytes: &'a [u8], origin: u16) -> Self {
+        Self::with_variant(Variant::I8080, bytes, origin)
+    }
+
+    /// Walks instructions for the given processor,
+    /// with the first byte at address `origin`.
+    pub fn with_variant(variant: Variant, bytes: &'a [u8], origin: u16) -> Self {
+        Instructions { variant, bytes, address: origin }
+    }
+
+    /// The bytes that have not been decoded yet.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The address of the next instruction.
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = (u16, Opcode, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (opcode, len) = decode_with(self.variant, self.bytes).ok()?;
+        let (bytes, rest) = self.bytes.split_at(len);
+        let address = self.address;
+        self.bytes = rest;
+        self.address = self.address.wrapping_add(len as u16);
+        Some((address, opcode, bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_reports_the_length_and_truncated_input() {
+        assert!(decode(&[0x3e, 0x12, 0x00]).unwrap() == (Opcode::MVI_A(0x12), 2));
+        assert!(decode(&[0xc3, 0x34, 0x12]).unwrap() == (Opcode::JMP(0x1234), 3));
+        assert_eq!(decode(&[]).unwrap_err(), DecodeError::Empty);
+        assert_eq!(decode(&[0xc3, 0x34]).unwrap_err(), DecodeError::Truncated { len: 3, available: 2 });
+
+        // 0x20 is RIM on the 8085, but an undocumented NOP on the 8080.
+        assert!(decode(&[0x20]).unwrap() == (Opcode::NOP_20, 1));
+        assert!(decode_with(Variant::I8085, &[0x20]).unwrap() == (Opcode::RIM, 1));
+    }
+
+    #[test]
+    fn instructions_walk_a_slice_until_it_runs_out() {
+        // NOP; LXI H,1234H; JMP (truncated)
+        let bytes = [0x00, 0x21, 0x34, 0x12, 0xc3, 0x00];
+        let mut instructions = Instructions::new(&bytes, 0xfffe);
+
+        let (address, opcode, bytes) = instructions.next().unwrap();
+        assert_eq!((address, bytes), (0xfffe, &[0x00][..]));
+        assert!(opcode == Opcode::NOP);
+
+        let (address, opcode, bytes) = instructions.next().unwrap();
+        assert_eq!((address, bytes), (0xffff, &[0x21, 0x34, 0x12][..]));
+        assert!(opcode == Opcode::LXI_HL(0x34, 0x12));
+
+        assert!(instructions.next().is_none());
+        assert_eq!(instructions.address(), 0x0002);
+        assert_eq!(instructions.remainder(), &[0xc3, 0x00]);
+    }
+}
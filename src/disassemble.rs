@@ -1,18 +1,27 @@
-use std::{fs::File, io::BufReader};
+use std::{fs::File, io::Read};
 
-use intel8080::Memory;
+use intel8080::{Hex, Instructions, Style};
 
 fn main() -> std::io::Result<()> {
-    let args: Vec<String> = std::env::args().collect();
-    let file = File::open(&args[1])?;
-    let mut reader = BufReader::new(file);
-
-    let mut mem = Memory::from_reader(&mut reader)?;
+    let mut style = Style::default();
+    let mut path = String::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--lowercase" => style.lowercase = true,
+            "--0x" => style.hex = Hex::Prefix,
+            _ => path = arg,
+        }
+    }
+    let mut bytes = Vec::new();
+    File::open(&path)?.read_to_end(&mut bytes)?;
 
-    while (mem.pc as usize) < mem.bytes_read {
-        let pc = mem.pc;
-        let opcode = mem.read_opcode();
-        println!("{:#010x}: {:?}", pc, opcode);
+    let mut instructions = Instructions::new(&bytes, 0);
+    for (adr, opcode, _) in instructions.by_ref() {
+        println!("{:#010x}: {}", adr, opcode.display(style));
+    }
+    for (i, byte) in instructions.remainder().iter().enumerate() {
+        let adr = instructions.address().wrapping_add(i as u16);
+        println!("{:#010x}: {:#04x} (truncated)", adr, byte);
     }
 
     Ok(())
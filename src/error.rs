@@ -0,0 +1,159 @@
+use crate::Opcode;
+
+/// A bus access that was rejected by the device it was addressed to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read(u16),
+    Write(u16),
+    In(u8),
+    Out(u8),
+}
+
+impl std::fmt::Display for Access {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Access::Read(adr) => write!(f, "read from {:#06x}", adr),
+            Access::Write(adr) => write!(f, "write to {:#06x}", adr),
+            Access::In(port) => write!(f, "input from port {:#04x}", port),
+            Access::Out(port) => write!(f, "output to port {:#04x}", port),
+        }
+    }
+}
+
+/// Reasons for [`Cpu::step`](crate::Cpu::step) to not execute an instruction.
+///
+/// Faults leave the program counter at the offending instruction,
+/// so that the host can inspect it and decide how to continue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepError {
+    /// The processor is halted and waits for an interrupt or a reset.
+    Halted { pc: u16 },
+    /// The instruction at `pc` is not supported by the emulator.
+    Unimplemented { pc: u16, opcode: Opcode },
+    /// The instruction at `pc` is undocumented and the processor
+    /// is configured to trap on undocumented instructions.
+    Undocumented { pc: u16, opcode: Opcode },
+    /// The instruction at `pc` performed an access that was rejected by the bus.
+    IllegalAccess { pc: u16, opcode: Opcode, access: Access },
+    /// Breakpoint `id` was hit, before executing the instruction at `pc`.
+    /// For breakpoints on memory and port accesses, `access` is the access
+    /// that hit it, made by the instruction that was just executed.
+    Breakpoint { pc: u16, id: usize, access: Option<Access> },
+}
+
+impl std::fmt::Display for StepError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StepError::Halted { pc } => write!(f, "halted at {:#06x}", pc),
+            StepError::Unimplemented { pc, opcode } => {
+                write!(f, "unimplemented instruction {:?} at {:#06x}", opcode, pc)
+            },
+            StepError::Undocumented { pc, opcode } => {
+                write!(f, "undocumented instruction {:?} at {:#06x}", opcode, pc)
+            },
+            StepError::IllegalAccess { pc, opcode, access } => {
+                write!(f, "illegal {} by {:?} at {:#06x}", access, opcode, pc)
+            },
+            StepError::Breakpoint { pc, id, access: None } => write!(f, "breakpoint {} at {:#06x}", id, pc),
+            StepError::Breakpoint { pc, id, access: Some(access) } => {
+                write!(f, "breakpoint {} hit by {} before {:#06x}", id, access, pc)
+            },
+        }
+    }
+}
+
+impl std::error::Error for StepError {}
+
+/// Reasons for [`decode`](crate::decode) to not return an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// There are no bytes to decode.
+    Empty,
+    /// The instruction is `len` bytes long, but only `available` bytes are left.
+    Truncated { len: usize, available: usize },
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "no bytes to decode"),
+            DecodeError::Truncated { len, available } => {
+                write!(f, "truncated instruction: {} of {} bytes available", available, len)
+            },
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Reasons for [`Condition::parse`](crate::Condition::parse) to reject a condition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConditionError {
+    /// The condition ends where an operand or a closing bracket is expected.
+    End,
+    /// Unexpected character at byte offset `pos`.
+    Unexpected { pos: usize },
+    /// Invalid number at byte offset `pos`.
+    Number { pos: usize },
+    /// Unknown register or flag name at byte offset `pos`.
+    Name { pos: usize },
+}
+
+impl std::fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConditionError::End => write!(f, "unexpected end of condition"),
+            ConditionError::Unexpected { pos } => write!(f, "unexpected character at offset {}", pos),
+            ConditionError::Number { pos } => write!(f, "invalid number at offset {}", pos),
+            ConditionError::Name { pos } => write!(f, "unknown register or flag at offset {}", pos),
+        }
+    }
+}
+
+impl std::error::Error for ConditionError {}
+
+/// Reasons for [`Cpu::restore`](crate::Cpu::restore) to reject a save state.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The save state could not be read.
+    Io(std::io::Error),
+    /// The data does not start with the save state header.
+    NotASnapshot,
+    /// The save state was written by another version of the format.
+    Version { found: u16 },
+    /// The checksum of the save state does not match its content.
+    Checksum,
+    /// The content of the save state does not match the machine,
+    /// such as when devices were mapped differently.
+    Corrupt,
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "cannot read save state: {}", err),
+            SnapshotError::NotASnapshot => write!(f, "not a save state"),
+            SnapshotError::Version { found } => {
+                let expected = crate::snapshot::VERSION;
+                write!(f, "save state version {} is not supported, expected {}", found, expected)
+            },
+            SnapshotError::Checksum => write!(f, "save state checksum mismatch"),
+            SnapshotError::Corrupt => write!(f, "save state does not match the machine"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(err: std::io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
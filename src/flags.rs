@@ -1,27 +1,113 @@
-#[derive(Default)]
+use crate::Variant;
+
+/// The last operation that affected the auxiliary carry and overflow flags,
+/// recorded so that the flags can be derived from its operands on demand.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Op {
+    /// The flags hold explicit values.
+    #[default]
+    None,
+    Add,
+    Sub,
+    Inr,
+    Dcr,
+    And,
+    /// XRA or ORA, which reset the auxiliary carry.
+    Or,
+}
+
+/// The condition flags.
+///
+/// Most instructions that affect the flags are followed by others that
+/// overwrite them before they are ever tested, so the processor only
+/// records the result and operands of the last operation and derives
+/// zero, sign, parity, auxiliary carry and overflow when they are read.
+#[derive(Clone, Copy, Default)]
 pub struct Flags {
+    zero: bool,
+    sign: bool,
+    parity: bool,
+    carry: bool,
+    carry_aux: bool,
+    overflow: bool,
+    underflow: bool,
+    subtract: bool,
+    /// Result that zero, sign and parity are derived from,
+    /// while `pending` is set.
+    result: u8,
+    pending: bool,
+    /// Operation and operands that auxiliary carry and overflow
+    /// are derived from, unless it is `Op::None`.
+    op: Op,
+    lhs: u8,
+    rhs: u8,
+    res: u8,
+}
+
+impl Flags {
+    /// Bit of the sign flag in the processor status word.
+    pub const SIGN: u8 = 0b1000_0000;
+    /// Bit of the zero flag in the processor status word.
+    pub const ZERO: u8 = 0b0100_0000;
+    /// Bit of the underflow indicator in the 8085 processor status word.
+    pub const UNDERFLOW: u8 = 0b0010_0000;
+    /// Bit of the auxiliary carry flag in the processor status word.
+    pub const CARRY_AUX: u8 = 0b0001_0000;
+    /// Bit of the parity flag in the processor status word.
+    pub const PARITY: u8 = 0b0000_0100;
+    /// Bit of the overflow flag in the 8085 processor status word.
+    pub const OVERFLOW: u8 = 0b0000_0010;
+    /// Bit of the subtract flag in the Z80 flag register.
+    pub const SUBTRACT: u8 = 0b0000_0010;
+    /// Bit of the carry flag in the processor status word.
+    pub const CARRY: u8 = 0b0000_0001;
+
     /// ### Zero
     ///
     /// If the result of an instruction has the value 0,
     /// this flag is set; otherwise it is reset.
-    pub zero: bool,
+    pub fn zero(&self) -> bool {
+        if self.pending {
+            self.result == 0
+        } else {
+            self.zero
+        }
+    }
+
     /// ### Sign
     ///
     /// If the most significant bit of the result of the operation
     /// has the value 1, this flag is set; otherwise it is reset.
-    pub sign: bool,
+    pub fn sign(&self) -> bool {
+        if self.pending {
+            (self.result & 0x80) != 0
+        } else {
+            self.sign
+        }
+    }
+
     /// ### Parity
     ///
     /// If the modulo 2 sum of the bits of the result of the operation is 0,
     /// (i.e., if the result has even parity), this flag is set;
     /// otherwise it is reset (i.e., if the result has odd parity).
-    pub parity: bool,
+    pub fn parity(&self) -> bool {
+        if self.pending {
+            (self.result.count_ones() & 0x1) == 0
+        } else {
+            self.parity
+        }
+    }
+
     /// ### Carry
     ///
     /// If the instruction resulted in a carry (from addition),
     /// or a borrow (from subtraction or a comparison) out of the
     /// high-order bit, the is flag is set; otherwise it is reset.
-    pub carry: bool,
+    pub fn carry(&self) -> bool {
+        self.carry
+    }
+
     /// ### Auxiliary Carry
     ///
     /// If the instruction caused a carry out of bit 3 and into bit 4 of the
@@ -30,18 +116,225 @@ pub struct Flags {
     /// increments, decrements, comparisons, and logical operations, but is
     /// principally used with additions and increments preceding a DAA
     /// (Decimal Adjust Accumulator) instruction.
-    pub carry_aux: bool,
-    /// Not actually a flag, but we keep it here for now.
-    pub interrupt_enabled: bool,
-}
+    pub fn carry_aux(&self) -> bool {
+        match self.op {
+            Op::None => self.carry_aux,
+            Op::Add => (self.lhs ^ self.rhs ^ self.res) & 0x10 != 0,
+            // The 8080 subtracts by adding the two's complement, so the auxiliary
+            // carry is the carry out of bit 3 of that addition rather than a borrow.
+            Op::Sub => (self.lhs ^ self.rhs ^ self.res) & 0x10 == 0,
+            Op::Inr => self.res & 0x0f == 0,
+            Op::Dcr => self.res & 0x0f != 0x0f,
+            // The 8080 sets the auxiliary carry of a logical AND
+            // to the OR of bit 3 of both operands.
+            Op::And => (self.lhs | self.rhs) & 0x08 != 0,
+            Op::Or => false,
+        }
+    }
 
-impl Flags {
+    /// ### Overflow
+    ///
+    /// If the signed result of an arithmetic instruction does not fit in
+    /// the destination, this flag is set; otherwise it is reset.
+    /// Only the 8085 exposes this flag, as bit 1 of the PSW, and tests it
+    /// with the RSTV instruction.
+    pub fn overflow(&self) -> bool {
+        match self.op {
+            Op::None | Op::And | Op::Or => self.overflow,
+            Op::Add => (self.lhs ^ self.res) & (self.rhs ^ self.res) & 0x80 != 0,
+            Op::Sub => (self.lhs ^ self.rhs) & (self.lhs ^ self.res) & 0x80 != 0,
+            Op::Inr => self.res == 0x80,
+            Op::Dcr => self.res == 0x7f,
+        }
+    }
+
+    /// ### Underflow Indicator
+    ///
+    /// If INX or DCX caused the register pair to wrap around,
+    /// this flag is set; otherwise it is reset. Only the 8085 exposes
+    /// this flag, as bit 5 of the PSW, and tests it with JK and JNK.
+    pub fn underflow(&self) -> bool {
+        self.underflow
+    }
+
+    /// ### Subtract
+    ///
+    /// If the last arithmetic instruction was a subtraction, decrement or
+    /// comparison, this flag is set; otherwise it is reset. Only the Z80
+    /// exposes this flag, as bit 1 of F, and uses it to correct DAA.
+    pub fn subtract(&self) -> bool {
+        self.subtract
+    }
+
+    pub fn set_zero(&mut self, value: bool) {
+        self.settle();
+        self.zero = value;
+    }
+
+    pub fn set_sign(&mut self, value: bool) {
+        self.settle();
+        self.sign = value;
+    }
+
+    pub fn set_parity(&mut self, value: bool) {
+        self.settle();
+        self.parity = value;
+    }
+
+    pub fn set_carry(&mut self, value: bool) {
+        self.carry = value;
+    }
+
+    pub fn set_carry_aux(&mut self, value: bool) {
+        self.settle_op();
+        self.carry_aux = value;
+    }
+
+    pub fn set_overflow(&mut self, value: bool) {
+        self.settle_op();
+        self.overflow = value;
+    }
+
+    pub fn set_underflow(&mut self, value: bool) {
+        self.underflow = value;
+    }
+
+    pub fn set_subtract(&mut self, value: bool) {
+        self.subtract = value;
+    }
+
+    /// Packs the flags into the low byte of the processor status word,
+    /// as PUSH PSW does on the given processor. The 8085 stores the
+    /// overflow in bit 1 and the underflow indicator in bit 5, and the Z80
+    /// stores the subtract flag in bit 1, where the 8080 always sets it.
+    pub fn psw(&self, variant: Variant) -> u8 {
+        let mut psw = u8::from(*self);
+        match variant {
+            Variant::I8080 => {},
+            Variant::I8085 => {
+                psw &= !Flags::OVERFLOW;
+                psw |= Flags::OVERFLOW * (self.overflow() as u8);
+                psw |= Flags::UNDERFLOW * (self.underflow as u8);
+            },
+            Variant::Z80 => {
+                psw &= !Flags::SUBTRACT;
+                psw |= Flags::SUBTRACT * (self.subtract as u8);
+            },
+        }
+        psw
+    }
+
+    /// Unpacks the low byte of the processor status word, as POP PSW
+    /// does on the given processor.
+    pub fn from_psw(variant: Variant, psw: u8) -> Flags {
+        let mut flags = Flags::from(psw);
+        match variant {
+            Variant::I8080 => {},
+            Variant::I8085 => {
+                flags.overflow = (Flags::OVERFLOW & psw) > 0;
+                flags.underflow = (Flags::UNDERFLOW & psw) > 0;
+            },
+            Variant::Z80 => flags.subtract = (Flags::SUBTRACT & psw) > 0,
+        }
+        flags
+    }
+
+    /// Sets zero, sign and parity from `value`, and the carry if given.
     pub fn upd(&mut self, value: u8, cy: Option<bool>) {
-        self.zero = value == 0;
-        self.sign = value != 0;
-        self.parity = (value.count_ones() & 0x1) == 0;
+        self.result = value;
+        self.pending = true;
         if let Some(cy) = cy {
             self.carry = cy;
         }
     }
+
+    /// Records the operands and result of `op` for
+    /// the auxiliary carry and overflow to be derived from.
+    pub(crate) fn upd_op(&mut self, op: Op, lhs: u8, rhs: u8, res: u8) {
+        if op == Op::And || op == Op::Or {
+            self.overflow = self.overflow();
+        }
+        self.op = op;
+        self.lhs = lhs;
+        self.rhs = rhs;
+        self.res = res;
+    }
+
+    /// Stores the derived zero, sign and parity as explicit values.
+    fn settle(&mut self) {
+        if self.pending {
+            self.zero = self.zero();
+            self.sign = self.sign();
+            self.parity = self.parity();
+            self.pending = false;
+        }
+    }
+
+    /// Stores the derived auxiliary carry and overflow as explicit values.
+    fn settle_op(&mut self) {
+        if self.op != Op::None {
+            self.carry_aux = self.carry_aux();
+            self.overflow = self.overflow();
+            self.op = Op::None;
+        }
+    }
+}
+
+impl std::fmt::Debug for Flags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Flags")
+            .field("zero", &self.zero())
+            .field("sign", &self.sign())
+            .field("parity", &self.parity())
+            .field("carry", &self.carry())
+            .field("carry_aux", &self.carry_aux())
+            .field("overflow", &self.overflow())
+            .field("underflow", &self.underflow())
+            .field("subtract", &self.subtract())
+            .finish()
+    }
+}
+
+impl PartialEq for Flags {
+    fn eq(&self, other: &Self) -> bool {
+        self.zero() == other.zero()
+            && self.sign() == other.sign()
+            && self.parity() == other.parity()
+            && self.carry() == other.carry()
+            && self.carry_aux() == other.carry_aux()
+            && self.overflow() == other.overflow()
+            && self.underflow() == other.underflow()
+            && self.subtract() == other.subtract()
+    }
+}
+
+impl Eq for Flags {}
+
+/// Unpacks the processor status word as pushed by PUSH PSW:
+/// `S Z 0 AC 0 P 1 CY`, from the most significant bit down.
+impl From<u8> for Flags {
+    fn from(psw: u8) -> Self {
+        Flags {
+            sign: (Flags::SIGN & psw) > 0,
+            zero: (Flags::ZERO & psw) > 0,
+            carry_aux: (Flags::CARRY_AUX & psw) > 0,
+            parity: (Flags::PARITY & psw) > 0,
+            carry: (Flags::CARRY & psw) > 0,
+            ..Flags::default()
+        }
+    }
+}
+
+/// Packs the flags into the processor status word as pushed by PUSH PSW.
+/// Bit 1 is always set and bits 3 and 5 are always reset.
+impl From<Flags> for u8 {
+    fn from(flags: Flags) -> Self {
+        let mut psw = 0b0000_0010;
+        psw |= Flags::SIGN * (flags.sign() as u8);
+        psw |= Flags::ZERO * (flags.zero() as u8);
+        psw |= Flags::CARRY_AUX * (flags.carry_aux() as u8);
+        psw |= Flags::PARITY * (flags.parity() as u8);
+        psw |= Flags::CARRY * (flags.carry() as u8);
+        psw
+    }
 }
@@ -0,0 +1,76 @@
+//! Opening traces for the binaries, compressed through the gzip
+//! command when their name ends in `.gz`. Not part of the library.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process::{Child, Command, Stdio};
+
+/// Opens a file for reading: stdin for `-`, a file decompressed by the
+/// gzip command for paths ending in `.gz`, or a plain file. The gzip
+/// process is returned to be waited for once the file is read.
+#[allow(dead_code)] // The intel8080 binary only writes traces.
+pub fn open(path: &str) -> io::Result<(Box<dyn Read>, Option<Child>)> {
+    if path == "-" {
+        return Ok((Box::new(io::stdin().lock()), None));
+    }
+    let file = File::open(path)?;
+    if !path.ends_with(".gz") {
+        return Ok((Box::new(file), None));
+    }
+    let mut gzip = Command::new("gzip").arg("-dc").stdin(file).stdout(Stdio::piped()).spawn()?;
+    let stdout = gzip.stdout.take().expect("stdout is piped");
+    Ok((Box::new(stdout), Some(gzip)))
+}
+
+/// Creates a file for writing, like [`open`]. The gzip process is
+/// returned to be waited for once the file is written and dropped.
+pub fn create(path: &str) -> io::Result<(Box<dyn Write>, Option<Child>)> {
+    if path == "-" {
+        return Ok((Box::new(io::stdout().lock()), None));
+    }
+    let file = File::create(path)?;
+    if !path.ends_with(".gz") {
+        return Ok((Box::new(file), None));
+    }
+    let mut gzip = Command::new("gzip").arg("-c").stdin(Stdio::piped()).stdout(file).spawn()?;
+    let stdin = gzip.stdin.take().expect("stdin is piped");
+    Ok((Box::new(stdin), Some(gzip)))
+}
+
+/// Waits for a gzip process returned by [`open`] or [`create`] to exit,
+/// and fails if it did not succeed, in which case the file it read or
+/// wrote is incomplete.
+pub fn wait(mut gzip: Child) -> io::Result<()> {
+    let status = gzip.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("gzip failed: {}", status)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_reports_a_failed_gzip() {
+        let path = std::env::temp_dir().join(format!("intel8080-{}.gz", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let (mut out, gzip) = create(path).unwrap();
+        out.write_all(b"trace").unwrap();
+        drop(out);
+        wait(gzip.unwrap()).unwrap();
+        let (mut input, gunzip) = open(path).unwrap();
+        let mut content = Vec::new();
+        input.read_to_end(&mut content).unwrap();
+        wait(gunzip.unwrap()).unwrap();
+        assert_eq!(content, b"trace");
+
+        std::fs::write(path, b"not compressed").unwrap();
+        let (mut input, gunzip) = open(path).unwrap();
+        input.read_to_end(&mut Vec::new()).unwrap();
+        assert!(wait(gunzip.unwrap()).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
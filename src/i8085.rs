@@ -0,0 +1,322 @@
+use std::io::{self, Read, Write};
+
+use crate::snapshot::{invalid, read_bool, read_u8};
+
+/// The additional interrupt inputs of the 8085, in order of priority.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterruptLine {
+    /// Non-maskable interrupt, vectored to 0x24. Latched on a rising edge.
+    Trap,
+    /// Vectored to 0x3c. Latched on a rising edge.
+    Rst7_5,
+    /// Vectored to 0x34. Recognized while the line is held high.
+    Rst6_5,
+    /// Vectored to 0x2c. Recognized while the line is held high.
+    Rst5_5,
+}
+
+impl InterruptLine {
+    /// Address the processor calls when it acknowledges the interrupt.
+    pub fn vector(&self) -> u16 {
+        match self {
+            InterruptLine::Trap => 0x24,
+            InterruptLine::Rst7_5 => 0x3c,
+            InterruptLine::Rst6_5 => 0x34,
+            InterruptLine::Rst5_5 => 0x2c,
+        }
+    }
+}
+
+/// Interrupt and serial I/O state that only exists on the 8085.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct State {
+    /// Interrupt masks set by SIM: bit 0 for RST 5.5, bit 1
+    /// for RST 6.5 and bit 2 for RST 7.5. A set bit disables the line.
+    masks: u8,
+    trap: bool,
+    trap_level: bool,
+    rst75: bool,
+    rst75_level: bool,
+    rst65: bool,
+    rst55: bool,
+    /// Interrupt enable status from before the last TRAP,
+    /// reported by the next RIM.
+    trap_inte: Option<bool>,
+    pub(crate) sid: bool,
+    pub(crate) sod: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            masks: 0b111,
+            trap: false,
+            trap_level: false,
+            rst75: false,
+            rst75_level: false,
+            rst65: false,
+            rst55: false,
+            trap_inte: None,
+            sid: false,
+            sod: false,
+        }
+    }
+}
+
+impl State {
+    /// Puts the interrupt logic in its reset state. The levels of
+    /// the input lines are external and are left untouched.
+    pub(crate) fn reset(&mut self) {
+        self.masks = 0b111;
+        self.trap = false;
+        self.rst75 = false;
+        self.trap_inte = None;
+        self.sod = false;
+    }
+
+    pub(crate) fn set_line(&mut self, line: InterruptLine, level: bool) {
+        match line {
+            InterruptLine::Trap => {
+                self.trap |= level && !self.trap_level;
+                self.trap_level = level;
+            },
+            InterruptLine::Rst7_5 => {
+                self.rst75 |= level && !self.rst75_level;
+                self.rst75_level = level;
+            },
+            InterruptLine::Rst6_5 => self.rst65 = level,
+            InterruptLine::Rst5_5 => self.rst55 = level,
+        }
+    }
+
+    /// Returns the highest priority interrupt that should be acknowledged,
+    /// and clears its latch. Only TRAP is recognized while `inte` is reset.
+    pub(crate) fn take(&mut self, inte: bool) -> Option<InterruptLine> {
+        if self.trap {
+            self.trap = false;
+            self.trap_inte = Some(inte);
+            return Some(InterruptLine::Trap);
+        }
+        if !inte {
+            return None;
+        }
+        if self.rst75 && self.masks & 0b100 == 0 {
+            self.rst75 = false;
+            return Some(InterruptLine::Rst7_5);
+        }
+        if self.rst65 && self.masks & 0b010 == 0 {
+            return Some(InterruptLine::Rst6_5);
+        }
+        if self.rst55 && self.masks & 0b001 == 0 {
+            return Some(InterruptLine::Rst5_5);
+        }
+        None
+    }
+
+    /// RIM (Read interrupt masks)
+    ///   Bits 0-2 hold the masks, bit 3 the interrupt enable status,
+    ///   bits 4-6 the pending RST 5.5, 6.5 and 7.5 interrupts and
+    ///   bit 7 the serial input data.
+    pub(crate) fn rim(&mut self, inte: bool) -> u8 {
+        let inte = self.trap_inte.take().unwrap_or(inte);
+        let mut value = self.masks;
+        value |= 0b0000_1000 * (inte as u8);
+        value |= 0b0001_0000 * (self.rst55 as u8);
+        value |= 0b0010_0000 * (self.rst65 as u8);
+        value |= 0b0100_0000 * (self.rst75 as u8);
+        value |= 0b1000_0000 * (self.sid as u8);
+        value
+    }
+
+    /// SIM (Set interrupt masks)
+    ///   Bit 3 enables setting the masks from bits 0-2, bit 4 resets the
+    ///   RST 7.5 latch and bit 6 enables latching bit 7 to the serial output.
+    pub(crate) fn sim(&mut self, value: u8) {
+        if value & 0b0000_1000 != 0 {
+            self.masks = value & 0b111;
+        }
+        if value & 0b0001_0000 != 0 {
+            self.rst75 = false;
+        }
+        if value & 0b0100_0000 != 0 {
+            self.sod = value & 0b1000_0000 != 0;
+        }
+    }
+
+    pub(crate) fn save(&self, out: &mut dyn Write) -> io::Result<()> {
+        let trap_inte = match self.trap_inte {
+            None => 0,
+            Some(false) => 1,
+            Some(true) => 2,
+        };
+        out.write_all(&[
+            self.masks,
+            self.trap as u8,
+            self.trap_level as u8,
+            self.rst75 as u8,
+            self.rst75_level as u8,
+            self.rst65 as u8,
+            self.rst55 as u8,
+            trap_inte,
+            self.sid as u8,
+            self.sod as u8,
+        ])
+    }
+
+    pub(crate) fn restore(&mut self, input: &mut dyn Read) -> io::Result<()> {
+        self.masks = read_u8(input)?;
+        self.trap = read_bool(input)?;
+        self.trap_level = read_bool(input)?;
+        self.rst75 = read_bool(input)?;
+        self.rst75_level = read_bool(input)?;
+        self.rst65 = read_bool(input)?;
+        self.rst55 = read_bool(input)?;
+        self.trap_inte = match read_u8(input)? {
+            0 => None,
+            1 => Some(false),
+            2 => Some(true),
+            _ => return Err(invalid()),
+        };
+        self.sid = read_bool(input)?;
+        self.sod = read_bool(input)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::{Cpu, Memory, Opcode, Variant};
+
+    fn cpu(program: &[u8]) -> Cpu {
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(program)).unwrap());
+        cpu.set_variant(Variant::I8085);
+        cpu
+    }
+
+    #[test]
+    fn inx_and_dcx_carry_into_the_high_byte_and_set_k() {
+        // LXI B,00FFH; INX B; LXI H,0FFFFH; INX H; DCX H; LXI SP,0; DCX SP; JK 0100H
+        let mut cpu = cpu(&[
+            0x01, 0xff, 0x00, 0x03, 0x21, 0xff, 0xff, 0x23, 0x2b, 0x31, 0x00, 0x00, 0x3b, 0xfd, 0x00, 0x01,
+        ]);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().bc(), 0x0100);
+        assert!(!cpu.flags().underflow());
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().m(), 0);
+        assert!(cpu.flags().underflow());
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().m(), 0xffff);
+        assert!(cpu.flags().underflow());
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.sp(), 0xffff);
+        assert_eq!(cpu.pc(), 13);
+        cpu.step().unwrap();
+        assert_eq!(cpu.pc(), 0x100);
+    }
+
+    #[test]
+    fn the_undocumented_8085_instructions() {
+        // LXI H,8003H; LXI B,0004H; DSUB; ARHL; LDHI 10H; SHLX; LHLX; LXI B,0100H; DSUB
+        let mut cpu = cpu(&[
+            0x21, 0x03, 0x80, 0x01, 0x04, 0x00, 0x08, 0x10, 0x28, 0x10, 0xd9, 0xed,
+            0x01, 0x00, 0x01, 0x08,
+        ]);
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.reg().m(), 0x7fff);
+        assert!(!cpu.flags().carry());
+        assert!(!cpu.flags().carry_aux());
+        assert!(cpu.flags().overflow());
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().m(), 0x3fff);
+        assert!(cpu.flags().carry());
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().de(), 0x400f);
+        cpu.step().unwrap();
+        assert_eq!((cpu.mem()[0x400f], cpu.mem()[0x4010]), (0xff, 0x3f));
+        cpu.mem_mut()[0x4010] = 0x12;
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().m(), 0x12ff);
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().m(), 0x11ff);
+        assert!(!cpu.flags().carry());
+        assert!(cpu.flags().carry_aux());
+        assert!(!cpu.flags().overflow());
+    }
+
+    #[test]
+    fn instructions_take_8085_timings() {
+        // MOV B,C; JMP 0000H
+        let mut cpu = cpu(&[0x41, 0xc3, 0x00, 0x00]);
+        assert_eq!(cpu.step().unwrap().states, 4);
+        assert_eq!(cpu.step().unwrap().states, 10);
+        cpu.set_variant(Variant::I8080);
+        assert_eq!(cpu.step().unwrap().states, 5);
+    }
+
+    #[test]
+    fn sim_sets_the_masks_and_the_serial_output() {
+        // MVI A,0CAH; SIM; RIM
+        let mut cpu = cpu(&[0x3e, 0xca, 0x30, 0x20]);
+        cpu.set_sid(true);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert!(cpu.sod());
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().a, 0b1000_0010);
+    }
+
+    #[test]
+    fn trap_is_acknowledged_with_interrupts_disabled() {
+        // LXI SP,0100H; ... 0024: RIM
+        let mut program = vec![0x31, 0x00, 0x01];
+        program.resize(0x24, 0);
+        program.push(0x20);
+        let mut cpu = cpu(&program);
+        cpu.step().unwrap();
+        cpu.set_line(InterruptLine::Trap, true);
+
+        let step = cpu.step().unwrap();
+        assert!(step.interrupt);
+        assert!(step.opcode == Opcode::CALL(0x24));
+        assert_eq!(cpu.pc(), 0x24);
+
+        // Held high, TRAP does not trigger again.
+        cpu.step().unwrap();
+        assert_eq!(cpu.pc(), 0x25);
+    }
+
+    #[test]
+    fn masked_lines_wait_and_rst_7_5_is_latched() {
+        let mut state = State::default();
+        state.sim(0b0000_1001);
+        state.set_line(InterruptLine::Rst5_5, true);
+        state.set_line(InterruptLine::Rst7_5, true);
+        state.set_line(InterruptLine::Rst7_5, false);
+        assert_eq!(state.rim(true), 0b0101_1001);
+        assert_eq!(state.take(false), None);
+
+        assert_eq!(state.take(true), Some(InterruptLine::Rst7_5));
+        assert_eq!(state.take(true), None);
+
+        state.sim(0b0000_1000);
+        assert_eq!(state.take(true), Some(InterruptLine::Rst5_5));
+        state.set_line(InterruptLine::Rst7_5, true);
+        state.sim(0b0001_0000);
+        assert_eq!(state.rim(true) & 0b0100_0000, 0);
+    }
+}
@@ -0,0 +1,632 @@
+//! Instructions with their operands broken out of the opcode.
+//!
+//! [`Opcode`] has a variant for every opcode byte, which is convenient to
+//! execute but hides that most of them are the same instruction applied to
+//! different registers. [`Instruction`] describes the same instructions
+//! by their operation and operands, and converts to and from [`Opcode`]
+//! without losing information.
+
+use crate::{z80, Opcode, Variant};
+
+/// An 8-bit operand, in the order of the 3-bit register field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reg8 {
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    /// The memory location addressed by H and L.
+    M,
+    A,
+}
+
+impl Reg8 {
+    /// Decodes the 3-bit register field of an opcode.
+    pub fn from_bits(bits: u8) -> Reg8 {
+        [Reg8::B, Reg8::C, Reg8::D, Reg8::E, Reg8::H, Reg8::L, Reg8::M, Reg8::A][bits as usize & 0b111]
+    }
+
+    /// The 3-bit register field that selects this operand.
+    pub fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// A 16-bit operand, in the order of the 2-bit register pair field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegPair {
+    BC,
+    DE,
+    HL,
+    SP,
+    /// The accumulator and the flags, which take the place
+    /// of SP in PUSH and POP.
+    PSW,
+}
+
+impl RegPair {
+    /// Decodes the 2-bit register pair field of an opcode, which never selects PSW.
+    pub fn from_bits(bits: u8) -> RegPair {
+        [RegPair::BC, RegPair::DE, RegPair::HL, RegPair::SP][bits as usize & 0b11]
+    }
+
+    /// The 2-bit register pair field that selects this operand.
+    pub fn bits(&self) -> u8 {
+        match self {
+            RegPair::PSW => 0b11,
+            _ => *self as u8,
+        }
+    }
+}
+
+/// The condition of a conditional jump, call or return,
+/// in the order of the 3-bit condition field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cond {
+    /// Not zero (Z = 0)
+    NZ,
+    /// Zero (Z = 1)
+    Z,
+    /// No carry (CY = 0)
+    NC,
+    /// Carry (CY = 1)
+    C,
+    /// Parity odd (P = 0)
+    PO,
+    /// Parity even (P = 1)
+    PE,
+    /// Plus (S = 0)
+    P,
+    /// Minus (S = 1)
+    M,
+}
+
+impl Cond {
+    /// Decodes the 3-bit condition field of an opcode.
+    pub fn from_bits(bits: u8) -> Cond {
+        [Cond::NZ, Cond::Z, Cond::NC, Cond::C, Cond::PO, Cond::PE, Cond::P, Cond::M][bits as usize & 0b111]
+    }
+
+    /// The 3-bit condition field that selects this condition.
+    pub fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// The operation of an arithmetic or logical instruction
+/// on the accumulator, in the order of the 3-bit operation field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AluOp {
+    Add,
+    Adc,
+    Sub,
+    Sbb,
+    Ana,
+    Xra,
+    Ora,
+    Cmp,
+}
+
+impl AluOp {
+    /// Decodes the 3-bit operation field of an opcode.
+    pub fn from_bits(bits: u8) -> AluOp {
+        [AluOp::Add, AluOp::Adc, AluOp::Sub, AluOp::Sbb, AluOp::Ana, AluOp::Xra, AluOp::Ora, AluOp::Cmp]
+            [bits as usize & 0b111]
+    }
+
+    /// The 3-bit operation field that selects this operation.
+    pub fn bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// A decoded instruction.
+///
+/// Some operand combinations have no encoding of their own, such as
+/// `Mov { dst: M, src: M }` or `Stax(RegPair::HL)`. They are converted to
+/// the [`Opcode`] that shares their bits, which is HLT and SHLD for these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    // Data transfer group
+    Mov { dst: Reg8, src: Reg8 },
+    Mvi(Reg8, u8),
+    Lxi(RegPair, u16),
+    Lda(u16),
+    Sta(u16),
+    Lhld(u16),
+    Shld(u16),
+    /// Load the accumulator from the address in BC or DE.
+    Ldax(RegPair),
+    /// Store the accumulator at the address in BC or DE.
+    Stax(RegPair),
+    Xchg,
+
+    // Arithmetic and logical groups
+    /// Operate on the accumulator and a register.
+    Alu(AluOp, Reg8),
+    /// Operate on the accumulator and immediate data.
+    AluImm(AluOp, u8),
+    Inr(Reg8),
+    Dcr(Reg8),
+    Inx(RegPair),
+    Dcx(RegPair),
+    Dad(RegPair),
+    Daa,
+    Rlc,
+    Rrc,
+    Ral,
+    Rar,
+    Cma,
+    Cmc,
+    Stc,
+
+    // Branch group
+    Jump { cond: Option<Cond>, addr: u16 },
+    Call { cond: Option<Cond>, addr: u16 },
+    Ret { cond: Option<Cond> },
+    /// Call the restart routine at `8 * n`.
+    Rst(u8),
+    Pchl,
+
+    // Stack, I/O and machine control group
+    Push(RegPair),
+    Pop(RegPair),
+    Xthl,
+    Sphl,
+    In(u8),
+    Out(u8),
+    Ei,
+    Di,
+    Hlt,
+    Nop,
+
+    // Undocumented 8080 opcodes
+    /// One of the opcodes 0x08, 0x10, ... 0x38, which act as NOP.
+    /// Holds the opcode byte.
+    UndocumentedNop(u8),
+    /// The opcode 0xcb, which acts as JMP.
+    UndocumentedJmp(u16),
+    /// The opcode 0xd9, which acts as RET.
+    UndocumentedRet,
+    /// One of the opcodes 0xdd, 0xed and 0xfd, which act as CALL.
+    UndocumentedCall { byte: u8, addr: u16 },
+
+    // Intel 8085 only
+    Rim,
+    Sim,
+    Dsub,
+    Arhl,
+    Rdel,
+    Ldhi(u8),
+    Ldsi(u8),
+    Rstv,
+    Shlx,
+    Lhlx,
+    Jnk(u16),
+    Jk(u16),
+
+    // Zilog Z80 only
+    Z80(z80::Opcode),
+}
+
+impl Instruction {
+    /// The opcode byte, or the first byte of a prefixed Z80 instruction.
+    pub fn opcode(&self) -> u8 {
+        use Instruction::*;
+        match *self {
+            Mov { dst, src } => 0x40 | dst.bits() << 3 | src.bits(),
+            Mvi(r, _) => 0x06 | r.bits() << 3,
+            Lxi(rp, _) => 0x01 | rp.bits() << 4,
+            Lda(_) => 0x3a,
+            Sta(_) => 0x32,
+            Lhld(_) => 0x2a,
+            Shld(_) => 0x22,
+            Ldax(rp) => 0x0a | rp.bits() << 4,
+            Stax(rp) => 0x02 | rp.bits() << 4,
+            Xchg => 0xeb,
+            Alu(op, r) => 0x80 | op.bits() << 3 | r.bits(),
+            AluImm(op, _) => 0xc6 | op.bits() << 3,
+            Inr(r) => 0x04 | r.bits() << 3,
+            Dcr(r) => 0x05 | r.bits() << 3,
+            Inx(rp) => 0x03 | rp.bits() << 4,
+            Dcx(rp) => 0x0b | rp.bits() << 4,
+            Dad(rp) => 0x09 | rp.bits() << 4,
+            Daa => 0x27,
+            Rlc => 0x07,
+            Rrc => 0x0f,
+            Ral => 0x17,
+            Rar => 0x1f,
+            Cma => 0x2f,
+            Cmc => 0x3f,
+            Stc => 0x37,
+            Jump { cond: None, .. } => 0xc3,
+            Jump { cond: Some(cond), .. } => 0xc2 | cond.bits() << 3,
+            Call { cond: None, .. } => 0xcd,
+            Call { cond: Some(cond), .. } => 0xc4 | cond.bits() << 3,
+            Ret { cond: None } => 0xc9,
+            Ret { cond: Some(cond) } => 0xc0 | cond.bits() << 3,
+            Rst(n) => 0xc7 | (n & 0b111) << 3,
+            Pchl => 0xe9,
+            Push(rp) => 0xc5 | rp.bits() << 4,
+            Pop(rp) => 0xc1 | rp.bits() << 4,
+            Xthl => 0xe3,
+            Sphl => 0xf9,
+            In(_) => 0xdb,
+            Out(_) => 0xd3,
+            Ei => 0xfb,
+            Di => 0xf3,
+            Hlt => 0x76,
+            Nop => 0x00,
+            UndocumentedNop(byte) => byte,
+            UndocumentedJmp(_) => 0xcb,
+            UndocumentedRet => 0xd9,
+            UndocumentedCall { byte, .. } => byte,
+            Rim => 0x20,
+            Sim => 0x30,
+            Dsub => 0x08,
+            Arhl => 0x10,
+            Rdel => 0x18,
+            Ldhi(_) => 0x28,
+            Ldsi(_) => 0x38,
+            Rstv => 0xcb,
+            Shlx => 0xd9,
+            Lhlx => 0xed,
+            Jnk(_) => 0xdd,
+            Jk(_) => 0xfd,
+            Z80(opcode) => opcode.byte(),
+        }
+    }
+
+    /// Encodes the instruction as the opcode byte followed by its
+    /// operands, with 16-bit operands in little-endian order.
+    pub fn encode(&self) -> Vec<u8> {
+        use Instruction::*;
+        let mut bytes = vec![self.opcode()];
+        match *self {
+            Mvi(_, data) | AluImm(_, data) | In(data) | Out(data) | Ldhi(data) | Ldsi(data) => {
+                bytes.push(data)
+            },
+            Lxi(_, word) | Lda(word) | Sta(word) | Lhld(word) | Shld(word) => {
+                bytes.extend(word.to_le_bytes())
+            },
+            Jump { addr, .. } | Call { addr, .. } | UndocumentedJmp(addr)
+                | UndocumentedCall { addr, .. } | Jnk(addr) | Jk(addr) => bytes.extend(addr.to_le_bytes()),
+            Z80(opcode) => return opcode.encode(),
+            _ => {},
+        }
+        bytes
+    }
+}
+
+impl From<Opcode> for Instruction {
+    fn from(opcode: Opcode) -> Self {
+        match opcode {
+            Opcode::NOP => Instruction::Nop,
+            Opcode::LXI_BC(rl, rh) => Instruction::Lxi(RegPair::BC, u16::from_le_bytes([rl, rh])),
+            Opcode::STAX_BC => Instruction::Stax(RegPair::BC),
+            Opcode::INX_BC => Instruction::Inx(RegPair::BC),
+            Opcode::INR_B => Instruction::Inr(Reg8::B),
+            Opcode::DCR_B => Instruction::Dcr(Reg8::B),
+            Opcode::MVI_B(data) => Instruction::Mvi(Reg8::B, data),
+            Opcode::RLC => Instruction::Rlc,
+            Opcode::NOP_08 => Instruction::UndocumentedNop(0x08),
+            Opcode::DAD_BC => Instruction::Dad(RegPair::BC),
+            Opcode::LDAX_BC => Instruction::Ldax(RegPair::BC),
+            Opcode::DCX_BC => Instruction::Dcx(RegPair::BC),
+            Opcode::INR_C => Instruction::Inr(Reg8::C),
+            Opcode::DCR_C => Instruction::Dcr(Reg8::C),
+            Opcode::MVI_C(data) => Instruction::Mvi(Reg8::C, data),
+            Opcode::RRC => Instruction::Rrc,
+            Opcode::NOP_10 => Instruction::UndocumentedNop(0x10),
+            Opcode::LXI_DE(rl, rh) => Instruction::Lxi(RegPair::DE, u16::from_le_bytes([rl, rh])),
+            Opcode::STAX_DE => Instruction::Stax(RegPair::DE),
+            Opcode::INX_DE => Instruction::Inx(RegPair::DE),
+            Opcode::INR_D => Instruction::Inr(Reg8::D),
+            Opcode::DCR_D => Instruction::Dcr(Reg8::D),
+            Opcode::MVI_D(data) => Instruction::Mvi(Reg8::D, data),
+            Opcode::RAL => Instruction::Ral,
+            Opcode::NOP_18 => Instruction::UndocumentedNop(0x18),
+            Opcode::DAD_DE => Instruction::Dad(RegPair::DE),
+            Opcode::LDAX_DE => Instruction::Ldax(RegPair::DE),
+            Opcode::DCX_DE => Instruction::Dcx(RegPair::DE),
+            Opcode::INR_E => Instruction::Inr(Reg8::E),
+            Opcode::DCR_E => Instruction::Dcr(Reg8::E),
+            Opcode::MVI_E(data) => Instruction::Mvi(Reg8::E, data),
+            Opcode::RAR => Instruction::Rar,
+            Opcode::NOP_20 => Instruction::UndocumentedNop(0x20),
+            Opcode::LXI_HL(rl, rh) => Instruction::Lxi(RegPair::HL, u16::from_le_bytes([rl, rh])),
+            Opcode::SHLD(addr) => Instruction::Shld(addr),
+            Opcode::INX_HL => Instruction::Inx(RegPair::HL),
+            Opcode::INR_H => Instruction::Inr(Reg8::H),
+            Opcode::DCR_H => Instruction::Dcr(Reg8::H),
+            Opcode::MVI_H(data) => Instruction::Mvi(Reg8::H, data),
+            Opcode::DAA => Instruction::Daa,
+            Opcode::NOP_28 => Instruction::UndocumentedNop(0x28),
+            Opcode::DAD_HL => Instruction::Dad(RegPair::HL),
+            Opcode::LHLD(addr) => Instruction::Lhld(addr),
+            Opcode::DCX_HL => Instruction::Dcx(RegPair::HL),
+            Opcode::INR_L => Instruction::Inr(Reg8::L),
+            Opcode::DCR_L => Instruction::Dcr(Reg8::L),
+            Opcode::MVI_L(data) => Instruction::Mvi(Reg8::L, data),
+            Opcode::CMA => Instruction::Cma,
+            Opcode::NOP_30 => Instruction::UndocumentedNop(0x30),
+            Opcode::LXI_SP(rl, rh) => Instruction::Lxi(RegPair::SP, u16::from_le_bytes([rl, rh])),
+            Opcode::STA(addr) => Instruction::Sta(addr),
+            Opcode::INX_SP => Instruction::Inx(RegPair::SP),
+            Opcode::INR_M => Instruction::Inr(Reg8::M),
+            Opcode::DCR_M => Instruction::Dcr(Reg8::M),
+            Opcode::MVI_M(data) => Instruction::Mvi(Reg8::M, data),
+            Opcode::STC => Instruction::Stc,
+            Opcode::NOP_38 => Instruction::UndocumentedNop(0x38),
+            Opcode::DAD_SP => Instruction::Dad(RegPair::SP),
+            Opcode::LDA(addr) => Instruction::Lda(addr),
+            Opcode::DCX_SP => Instruction::Dcx(RegPair::SP),
+            Opcode::INR_A => Instruction::Inr(Reg8::A),
+            Opcode::DCR_A => Instruction::Dcr(Reg8::A),
+            Opcode::MVI_A(data) => Instruction::Mvi(Reg8::A, data),
+            Opcode::CMC => Instruction::Cmc,
+            Opcode::MOV_BB => Instruction::Mov { dst: Reg8::B, src: Reg8::B },
+            Opcode::MOV_BC => Instruction::Mov { dst: Reg8::B, src: Reg8::C },
+            Opcode::MOV_BD => Instruction::Mov { dst: Reg8::B, src: Reg8::D },
+            Opcode::MOV_BE => Instruction::Mov { dst: Reg8::B, src: Reg8::E },
+            Opcode::MOV_BH => Instruction::Mov { dst: Reg8::B, src: Reg8::H },
+            Opcode::MOV_BL => Instruction::Mov { dst: Reg8::B, src: Reg8::L },
+            Opcode::MOV_BM => Instruction::Mov { dst: Reg8::B, src: Reg8::M },
+            Opcode::MOV_BA => Instruction::Mov { dst: Reg8::B, src: Reg8::A },
+            Opcode::MOV_CB => Instruction::Mov { dst: Reg8::C, src: Reg8::B },
+            Opcode::MOV_CC => Instruction::Mov { dst: Reg8::C, src: Reg8::C },
+            Opcode::MOV_CD => Instruction::Mov { dst: Reg8::C, src: Reg8::D },
+            Opcode::MOV_CE => Instruction::Mov { dst: Reg8::C, src: Reg8::E },
+            Opcode::MOV_CH => Instruction::Mov { dst: Reg8::C, src: Reg8::H },
+            Opcode::MOV_CL => Instruction::Mov { dst: Reg8::C, src: Reg8::L },
+            Opcode::MOV_CM => Instruction::Mov { dst: Reg8::C, src: Reg8::M },
+            Opcode::MOV_CA => Instruction::Mov { dst: Reg8::C, src: Reg8::A },
+            Opcode::MOV_DB => Instruction::Mov { dst: Reg8::D, src: Reg8::B },
+            Opcode::MOV_DC => Instruction::Mov { dst: Reg8::D, src: Reg8::C },
+            Opcode::MOV_DD => Instruction::Mov { dst: Reg8::D, src: Reg8::D },
+            Opcode::MOV_DE => Instruction::Mov { dst: Reg8::D, src: Reg8::E },
+            Opcode::MOV_DH => Instruction::Mov { dst: Reg8::D, src: Reg8::H },
+            Opcode::MOV_DL => Instruction::Mov { dst: Reg8::D, src: Reg8::L },
+            Opcode::MOV_DM => Instruction::Mov { dst: Reg8::D, src: Reg8::M },
+            Opcode::MOV_DA => Instruction::Mov { dst: Reg8::D, src: Reg8::A },
+            Opcode::MOV_EB => Instruction::Mov { dst: Reg8::E, src: Reg8::B },
+            Opcode::MOV_EC => Instruction::Mov { dst: Reg8::E, src: Reg8::C },
+            Opcode::MOV_ED => Instruction::Mov { dst: Reg8::E, src: Reg8::D },
+            Opcode::MOV_EE => Instruction::Mov { dst: Reg8::E, src: Reg8::E },
+            Opcode::MOV_EH => Instruction::Mov { dst: Reg8::E, src: Reg8::H },
+            Opcode::MOV_EL => Instruction::Mov { dst: Reg8::E, src: Reg8::L },
+            Opcode::MOV_EM => Instruction::Mov { dst: Reg8::E, src: Reg8::M },
+            Opcode::MOV_EA => Instruction::Mov { dst: Reg8::E, src: Reg8::A },
+            Opcode::MOV_HB => Instruction::Mov { dst: Reg8::H, src: Reg8::B },
+            Opcode::MOV_HC => Instruction::Mov { dst: Reg8::H, src: Reg8::C },
+            Opcode::MOV_HD => Instruction::Mov { dst: Reg8::H, src: Reg8::D },
+            Opcode::MOV_HE => Instruction::Mov { dst: Reg8::H, src: Reg8::E },
+            Opcode::MOV_HH => Instruction::Mov { dst: Reg8::H, src: Reg8::H },
+            Opcode::MOV_HL => Instruction::Mov { dst: Reg8::H, src: Reg8::L },
+            Opcode::MOV_HM => Instruction::Mov { dst: Reg8::H, src: Reg8::M },
+            Opcode::MOV_HA => Instruction::Mov { dst: Reg8::H, src: Reg8::A },
+            Opcode::MOV_LB => Instruction::Mov { dst: Reg8::L, src: Reg8::B },
+            Opcode::MOV_LC => Instruction::Mov { dst: Reg8::L, src: Reg8::C },
+            Opcode::MOV_LD => Instruction::Mov { dst: Reg8::L, src: Reg8::D },
+            Opcode::MOV_LE => Instruction::Mov { dst: Reg8::L, src: Reg8::E },
+            Opcode::MOV_LH => Instruction::Mov { dst: Reg8::L, src: Reg8::H },
+            Opcode::MOV_LL => Instruction::Mov { dst: Reg8::L, src: Reg8::L },
+            Opcode::MOV_LM => Instruction::Mov { dst: Reg8::L, src: Reg8::M },
+            Opcode::MOV_LA => Instruction::Mov { dst: Reg8::L, src: Reg8::A },
+            Opcode::MOV_MB => Instruction::Mov { dst: Reg8::M, src: Reg8::B },
+            Opcode::MOV_MC => Instruction::Mov { dst: Reg8::M, src: Reg8::C },
+            Opcode::MOV_MD => Instruction::Mov { dst: Reg8::M, src: Reg8::D },
+            Opcode::MOV_ME => Instruction::Mov { dst: Reg8::M, src: Reg8::E },
+            Opcode::MOV_MH => Instruction::Mov { dst: Reg8::M, src: Reg8::H },
+            Opcode::MOV_ML => Instruction::Mov { dst: Reg8::M, src: Reg8::L },
+            Opcode::HLT => Instruction::Hlt,
+            Opcode::MOV_MA => Instruction::Mov { dst: Reg8::M, src: Reg8::A },
+            Opcode::MOV_AB => Instruction::Mov { dst: Reg8::A, src: Reg8::B },
+            Opcode::MOV_AC => Instruction::Mov { dst: Reg8::A, src: Reg8::C },
+            Opcode::MOV_AD => Instruction::Mov { dst: Reg8::A, src: Reg8::D },
+            Opcode::MOV_AE => Instruction::Mov { dst: Reg8::A, src: Reg8::E },
+            Opcode::MOV_AH => Instruction::Mov { dst: Reg8::A, src: Reg8::H },
+            Opcode::MOV_AL => Instruction::Mov { dst: Reg8::A, src: Reg8::L },
+            Opcode::MOV_AM => Instruction::Mov { dst: Reg8::A, src: Reg8::M },
+            Opcode::MOV_AA => Instruction::Mov { dst: Reg8::A, src: Reg8::A },
+            Opcode::ADD_B => Instruction::Alu(AluOp::Add, Reg8::B),
+            Opcode::ADD_C => Instruction::Alu(AluOp::Add, Reg8::C),
+            Opcode::ADD_D => Instruction::Alu(AluOp::Add, Reg8::D),
+            Opcode::ADD_E => Instruction::Alu(AluOp::Add, Reg8::E),
+            Opcode::ADD_H => Instruction::Alu(AluOp::Add, Reg8::H),
+            Opcode::ADD_L => Instruction::Alu(AluOp::Add, Reg8::L),
+            Opcode::ADD_M => Instruction::Alu(AluOp::Add, Reg8::M),
+            Opcode::ADD_A => Instruction::Alu(AluOp::Add, Reg8::A),
+            Opcode::ADC_B => Instruction::Alu(AluOp::Adc, Reg8::B),
+            Opcode::ADC_C => Instruction::Alu(AluOp::Adc, Reg8::C),
+            Opcode::ADC_D => Instruction::Alu(AluOp::Adc, Reg8::D),
+            Opcode::ADC_E => Instruction::Alu(AluOp::Adc, Reg8::E),
+            Opcode::ADC_H => Instruction::Alu(AluOp::Adc, Reg8::H),
+            Opcode::ADC_L => Instruction::Alu(AluOp::Adc, Reg8::L),
+            Opcode::ADC_M => Instruction::Alu(AluOp::Adc, Reg8::M),
+            Opcode::ADC_A => Instruction::Alu(AluOp::Adc, Reg8::A),
+            Opcode::SUB_B => Instruction::Alu(AluOp::Sub, Reg8::B),
+            Opcode::SUB_C => Instruction::Alu(AluOp::Sub, Reg8::C),
+            Opcode::SUB_D => Instruction::Alu(AluOp::Sub, Reg8::D),
+            Opcode::SUB_E => Instruction::Alu(AluOp::Sub, Reg8::E),
+            Opcode::SUB_H => Instruction::Alu(AluOp::Sub, Reg8::H),
+            Opcode::SUB_L => Instruction::Alu(AluOp::Sub, Reg8::L),
+            Opcode::SUB_M => Instruction::Alu(AluOp::Sub, Reg8::M),
+            Opcode::SUB_A => Instruction::Alu(AluOp::Sub, Reg8::A),
+            Opcode::SBB_B => Instruction::Alu(AluOp::Sbb, Reg8::B),
+            Opcode::SBB_C => Instruction::Alu(AluOp::Sbb, Reg8::C),
+            Opcode::SBB_D => Instruction::Alu(AluOp::Sbb, Reg8::D),
+            Opcode::SBB_E => Instruction::Alu(AluOp::Sbb, Reg8::E),
+            Opcode::SBB_H => Instruction::Alu(AluOp::Sbb, Reg8::H),
+            Opcode::SBB_L => Instruction::Alu(AluOp::Sbb, Reg8::L),
+            Opcode::SBB_M => Instruction::Alu(AluOp::Sbb, Reg8::M),
+            Opcode::SBB_A => Instruction::Alu(AluOp::Sbb, Reg8::A),
+            Opcode::ANA_B => Instruction::Alu(AluOp::Ana, Reg8::B),
+            Opcode::ANA_C => Instruction::Alu(AluOp::Ana, Reg8::C),
+            Opcode::ANA_D => Instruction::Alu(AluOp::Ana, Reg8::D),
+            Opcode::ANA_E => Instruction::Alu(AluOp::Ana, Reg8::E),
+            Opcode::ANA_H => Instruction::Alu(AluOp::Ana, Reg8::H),
+            Opcode::ANA_L => Instruction::Alu(AluOp::Ana, Reg8::L),
+            Opcode::ANA_M => Instruction::Alu(AluOp::Ana, Reg8::M),
+            Opcode::ANA_A => Instruction::Alu(AluOp::Ana, Reg8::A),
+            Opcode::XRA_B => Instruction::Alu(AluOp::Xra, Reg8::B),
+            Opcode::XRA_C => Instruction::Alu(AluOp::Xra, Reg8::C),
+            Opcode::XRA_D => Instruction::Alu(AluOp::Xra, Reg8::D),
+            Opcode::XRA_E => Instruction::Alu(AluOp::Xra, Reg8::E),
+            Opcode::XRA_H => Instruction::Alu(AluOp::Xra, Reg8::H),
+            Opcode::XRA_L => Instruction::Alu(AluOp::Xra, Reg8::L),
+            Opcode::XRA_M => Instruction::Alu(AluOp::Xra, Reg8::M),
+            Opcode::XRA_A => Instruction::Alu(AluOp::Xra, Reg8::A),
+            Opcode::ORA_B => Instruction::Alu(AluOp::Ora, Reg8::B),
+            Opcode::ORA_C => Instruction::Alu(AluOp::Ora, Reg8::C),
+            Opcode::ORA_D => Instruction::Alu(AluOp::Ora, Reg8::D),
+            Opcode::ORA_E => Instruction::Alu(AluOp::Ora, Reg8::E),
+            Opcode::ORA_H => Instruction::Alu(AluOp::Ora, Reg8::H),
+            Opcode::ORA_L => Instruction::Alu(AluOp::Ora, Reg8::L),
+            Opcode::ORA_M => Instruction::Alu(AluOp::Ora, Reg8::M),
+            Opcode::ORA_A => Instruction::Alu(AluOp::Ora, Reg8::A),
+            Opcode::CMP_B => Instruction::Alu(AluOp::Cmp, Reg8::B),
+            Opcode::CMP_C => Instruction::Alu(AluOp::Cmp, Reg8::C),
+            Opcode::CMP_D => Instruction::Alu(AluOp::Cmp, Reg8::D),
+            Opcode::CMP_E => Instruction::Alu(AluOp::Cmp, Reg8::E),
+            Opcode::CMP_H => Instruction::Alu(AluOp::Cmp, Reg8::H),
+            Opcode::CMP_L => Instruction::Alu(AluOp::Cmp, Reg8::L),
+            Opcode::CMP_M => Instruction::Alu(AluOp::Cmp, Reg8::M),
+            Opcode::CMP_A => Instruction::Alu(AluOp::Cmp, Reg8::A),
+            Opcode::RNZ => Instruction::Ret { cond: Some(Cond::NZ) },
+            Opcode::POP_BC => Instruction::Pop(RegPair::BC),
+            Opcode::JNZ(addr) => Instruction::Jump { cond: Some(Cond::NZ), addr },
+            Opcode::JMP(addr) => Instruction::Jump { cond: None, addr },
+            Opcode::CNZ(addr) => Instruction::Call { cond: Some(Cond::NZ), addr },
+            Opcode::PUSH_BC => Instruction::Push(RegPair::BC),
+            Opcode::ADI(data) => Instruction::AluImm(AluOp::Add, data),
+            Opcode::RST_0 => Instruction::Rst(0),
+            Opcode::RZ => Instruction::Ret { cond: Some(Cond::Z) },
+            Opcode::RET => Instruction::Ret { cond: None },
+            Opcode::JZ(addr) => Instruction::Jump { cond: Some(Cond::Z), addr },
+            Opcode::JMP_CB(addr) => Instruction::UndocumentedJmp(addr),
+            Opcode::CZ(addr) => Instruction::Call { cond: Some(Cond::Z), addr },
+            Opcode::CALL(addr) => Instruction::Call { cond: None, addr },
+            Opcode::ACI(data) => Instruction::AluImm(AluOp::Adc, data),
+            Opcode::RST_1 => Instruction::Rst(1),
+            Opcode::RNC => Instruction::Ret { cond: Some(Cond::NC) },
+            Opcode::POP_DE => Instruction::Pop(RegPair::DE),
+            Opcode::JNC(addr) => Instruction::Jump { cond: Some(Cond::NC), addr },
+            Opcode::OUT(data) => Instruction::Out(data),
+            Opcode::CNC(addr) => Instruction::Call { cond: Some(Cond::NC), addr },
+            Opcode::PUSH_DE => Instruction::Push(RegPair::DE),
+            Opcode::SUI(data) => Instruction::AluImm(AluOp::Sub, data),
+            Opcode::RST_2 => Instruction::Rst(2),
+            Opcode::RC => Instruction::Ret { cond: Some(Cond::C) },
+            Opcode::RET_D9 => Instruction::UndocumentedRet,
+            Opcode::JC(addr) => Instruction::Jump { cond: Some(Cond::C), addr },
+            Opcode::IN(data) => Instruction::In(data),
+            Opcode::CC(addr) => Instruction::Call { cond: Some(Cond::C), addr },
+            Opcode::CALL_DD(addr) => Instruction::UndocumentedCall { byte: 0xdd, addr },
+            Opcode::SBI(data) => Instruction::AluImm(AluOp::Sbb, data),
+            Opcode::RST_3 => Instruction::Rst(3),
+            Opcode::RPO => Instruction::Ret { cond: Some(Cond::PO) },
+            Opcode::POP_HL => Instruction::Pop(RegPair::HL),
+            Opcode::JPO(addr) => Instruction::Jump { cond: Some(Cond::PO), addr },
+            Opcode::XTHL => Instruction::Xthl,
+            Opcode::CPO(addr) => Instruction::Call { cond: Some(Cond::PO), addr },
+            Opcode::PUSH_HL => Instruction::Push(RegPair::HL),
+            Opcode::ANI(data) => Instruction::AluImm(AluOp::Ana, data),
+            Opcode::RST_4 => Instruction::Rst(4),
+            Opcode::RPE => Instruction::Ret { cond: Some(Cond::PE) },
+            Opcode::PCHL => Instruction::Pchl,
+            Opcode::JPE(addr) => Instruction::Jump { cond: Some(Cond::PE), addr },
+            Opcode::XCHG => Instruction::Xchg,
+            Opcode::CPE(addr) => Instruction::Call { cond: Some(Cond::PE), addr },
+            Opcode::CALL_ED(addr) => Instruction::UndocumentedCall { byte: 0xed, addr },
+            Opcode::XRI(data) => Instruction::AluImm(AluOp::Xra, data),
+            Opcode::RST_5 => Instruction::Rst(5),
+            Opcode::RP => Instruction::Ret { cond: Some(Cond::P) },
+            Opcode::POP_PSW => Instruction::Pop(RegPair::PSW),
+            Opcode::JP(addr) => Instruction::Jump { cond: Some(Cond::P), addr },
+            Opcode::DI => Instruction::Di,
+            Opcode::CP(addr) => Instruction::Call { cond: Some(Cond::P), addr },
+            Opcode::PUSH_PSW => Instruction::Push(RegPair::PSW),
+            Opcode::ORI(data) => Instruction::AluImm(AluOp::Ora, data),
+            Opcode::RST_6 => Instruction::Rst(6),
+            Opcode::RM => Instruction::Ret { cond: Some(Cond::M) },
+            Opcode::SPHL => Instruction::Sphl,
+            Opcode::JM(addr) => Instruction::Jump { cond: Some(Cond::M), addr },
+            Opcode::EI => Instruction::Ei,
+            Opcode::CM(addr) => Instruction::Call { cond: Some(Cond::M), addr },
+            Opcode::CALL_FD(addr) => Instruction::UndocumentedCall { byte: 0xfd, addr },
+            Opcode::CPI(data) => Instruction::AluImm(AluOp::Cmp, data),
+            Opcode::RST_7 => Instruction::Rst(7),
+            Opcode::RIM => Instruction::Rim,
+            Opcode::SIM => Instruction::Sim,
+            Opcode::DSUB => Instruction::Dsub,
+            Opcode::ARHL => Instruction::Arhl,
+            Opcode::RDEL => Instruction::Rdel,
+            Opcode::LDHI(data) => Instruction::Ldhi(data),
+            Opcode::LDSI(data) => Instruction::Ldsi(data),
+            Opcode::RSTV => Instruction::Rstv,
+            Opcode::SHLX => Instruction::Shlx,
+            Opcode::JNK(addr) => Instruction::Jnk(addr),
+            Opcode::LHLX => Instruction::Lhlx,
+            Opcode::JK(addr) => Instruction::Jk(addr),
+            Opcode::Z80(opcode) => Instruction::Z80(opcode),
+        }
+    }
+}
+
+impl From<Instruction> for Opcode {
+    fn from(instruction: Instruction) -> Self {
+        use Instruction::*;
+        let variant = match instruction {
+            Rim | Sim | Dsub | Arhl | Rdel | Ldhi(_) | Ldsi(_) | Rstv | Shlx | Lhlx | Jnk(_) | Jk(_) => {
+                Variant::I8085
+            },
+            Z80(opcode) => return Opcode::Z80(opcode),
+            _ => Variant::I8080,
+        };
+        let mut bytes = instruction.encode().into_iter();
+        Opcode::read(variant, || bytes.next().unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_opcode_converts_to_an_instruction_and_back() {
+        for variant in [Variant::I8080, Variant::I8085] {
+            for byte in 0..=0xff {
+                let mut bytes = [byte, 0x34, 0x12].into_iter();
+                let opcode = Opcode::read(variant, || bytes.next().unwrap());
+                let instruction = Instruction::from(opcode);
+                assert_eq!(instruction.opcode(), byte);
+                assert!(Opcode::from(instruction) == opcode, "{:?} {:?}", variant, opcode);
+            }
+        }
+    }
+
+    #[test]
+    fn operands_are_broken_out_of_the_opcode() {
+        assert_eq!(Instruction::from(Opcode::MOV_AB), Instruction::Mov { dst: Reg8::A, src: Reg8::B });
+        assert_eq!(Instruction::from(Opcode::ADD_M), Instruction::Alu(AluOp::Add, Reg8::M));
+        assert_eq!(Instruction::from(Opcode::CPI(0x20)), Instruction::AluImm(AluOp::Cmp, 0x20));
+        assert_eq!(Instruction::from(Opcode::LXI_SP(0x34, 0x12)), Instruction::Lxi(RegPair::SP, 0x1234));
+        assert_eq!(Instruction::from(Opcode::PUSH_PSW), Instruction::Push(RegPair::PSW));
+        assert_eq!(Instruction::from(Opcode::RST_3), Instruction::Rst(3));
+        assert_eq!(
+            Instruction::from(Opcode::JNZ(0x1234)),
+            Instruction::Jump { cond: Some(Cond::NZ), addr: 0x1234 }
+        );
+        assert_eq!(Instruction::from(Opcode::RET), Instruction::Ret { cond: None });
+    }
+
+    #[test]
+    fn combinations_without_an_encoding_share_the_bits_of_another_opcode() {
+        assert!(Opcode::from(Instruction::Mov { dst: Reg8::M, src: Reg8::M }) == Opcode::HLT);
+        assert!(Opcode::from(Instruction::Stax(RegPair::HL)) == Opcode::SHLD(0));
+        assert!(Opcode::from(Instruction::Rst(9)) == Opcode::RST_1);
+    }
+}
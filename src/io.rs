@@ -0,0 +1,305 @@
+use std::io::{self, Read, Write};
+
+use crate::Access;
+
+/// The I/O side of the system bus, accessed by the IN and OUT instructions.
+pub trait IoBus {
+    /// Returns the byte that the device at the given port places on the
+    /// data bus during an IN instruction.
+    fn read_port(&mut self, port: u8) -> u8;
+
+    /// Receives the content of the accumulator during an OUT instruction.
+    fn write_port(&mut self, port: u8, value: u8);
+
+    /// Returns the access that was rejected since the last call, if any.
+    /// The processor checks this after every instruction and reports
+    /// a rejected access as a fault.
+    fn take_fault(&mut self) -> Option<Access> {
+        None
+    }
+
+    /// Writes the state of the device to a save state. The default fails
+    /// with [`io::ErrorKind::Unsupported`]; devices without state
+    /// implement it to save nothing.
+    fn save(&self, _out: &mut dyn Write) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "device does not support save states"))
+    }
+
+    /// Reads back what [`IoBus::save`] wrote.
+    fn restore(&mut self, _input: &mut dyn Read) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "device does not support save states"))
+    }
+}
+
+/// What happens when an IN or OUT addresses a port without a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnmappedPort {
+    /// Reads return the given value and writes are discarded.
+    Float(u8),
+    /// Like `Float`, but every access is also recorded, to be collected
+    /// with [`Ports::take_unmapped`].
+    Log(u8),
+    /// Every access is rejected, which makes the processor fault.
+    Fault,
+}
+
+impl Default for UnmappedPort {
+    fn default() -> Self {
+        UnmappedPort::Float(0xff)
+    }
+}
+
+/// An I/O bus that dispatches each of the 256 ports to a registered device.
+///
+/// A single device may be mapped to several ports, in which case it receives
+/// the port number so that it can tell the accesses apart.
+pub struct Ports {
+    devices: Vec<Box<dyn IoBus>>,
+    map: [Option<usize>; 0xFF + 1],
+    unmapped: UnmappedPort,
+    log: Vec<(Access, u8)>,
+    fault: Option<Access>,
+}
+
+impl Default for Ports {
+    fn default() -> Self {
+        Ports::new(UnmappedPort::default())
+    }
+}
+
+impl Ports {
+    pub fn new(unmapped: UnmappedPort) -> Self {
+        Ports { devices: Vec::new(), map: [None; 0xFF + 1], unmapped, log: Vec::new(), fault: None }
+    }
+
+    /// Connects a device to the given ports, replacing
+    /// any device previously connected to them.
+    pub fn map<D: IoBus + 'static>(&mut self, ports: impl IntoIterator<Item = u8>, device: D) {
+        let index = self.devices.len();
+        self.devices.push(Box::new(device));
+        for port in ports {
+            self.map[port as usize] = Some(index);
+        }
+        self.drop_orphans();
+    }
+
+    /// Disconnects whatever device is connected to the given port.
+    pub fn unmap(&mut self, port: u8) {
+        self.map[port as usize] = None;
+        self.drop_orphans();
+    }
+
+    pub fn set_unmapped(&mut self, unmapped: UnmappedPort) {
+        self.unmapped = unmapped;
+    }
+
+    /// Drops the devices that are no longer connected to any port,
+    /// so that they are neither kept alive nor saved.
+    fn drop_orphans(&mut self) {
+        let mut used = vec![false; self.devices.len()];
+        for &index in self.map.iter().flatten() {
+            used[index] = true;
+        }
+        let mut renumbered = Vec::with_capacity(used.len());
+        let mut next = 0;
+        for &used in &used {
+            renumbered.push(next);
+            next += used as usize;
+        }
+        let mut used = used.into_iter();
+        self.devices.retain(|_| used.next().unwrap_or_default());
+        for index in self.map.iter_mut().flatten() {
+            *index = renumbered[*index];
+        }
+    }
+
+    /// Removes and returns the accesses to unmapped ports recorded under
+    /// [`UnmappedPort::Log`], oldest first, with the value each one read
+    /// or wrote. Accesses are kept until they are collected.
+    pub fn take_unmapped(&mut self) -> std::vec::Drain<'_, (Access, u8)> {
+        self.log.drain(..)
+    }
+
+    fn device(&mut self, port: u8) -> Option<&mut Box<dyn IoBus>> {
+        let index = self.map[port as usize]?;
+        self.devices.get_mut(index)
+    }
+}
+
+impl IoBus for Ports {
+    fn read_port(&mut self, port: u8) -> u8 {
+        if let Some(device) = self.device(port) {
+            let value = device.read_port(port);
+            let fault = device.take_fault();
+            self.fault = self.fault.or(fault);
+            return value;
+        }
+
+        match self.unmapped {
+            UnmappedPort::Float(value) => value,
+            UnmappedPort::Log(value) => {
+                self.log.push((Access::In(port), value));
+                value
+            },
+            UnmappedPort::Fault => {
+                self.fault = self.fault.or(Some(Access::In(port)));
+                0xff
+            },
+        }
+    }
+
+    fn write_port(&mut self, port: u8, value: u8) {
+        if let Some(device) = self.device(port) {
+            device.write_port(port, value);
+            let fault = device.take_fault();
+            self.fault = self.fault.or(fault);
+            return;
+        }
+
+        match self.unmapped {
+            UnmappedPort::Float(_) => {},
+            UnmappedPort::Log(_) => {
+                self.log.push((Access::Out(port), value));
+            },
+            UnmappedPort::Fault => {
+                self.fault = self.fault.or(Some(Access::Out(port)));
+            },
+        }
+    }
+
+    fn take_fault(&mut self) -> Option<Access> {
+        self.fault.take()
+    }
+
+    /// Saves the state of every device, in the order they were mapped.
+    fn save(&self, out: &mut dyn Write) -> io::Result<()> {
+        for device in &self.devices {
+            device.save(out)?;
+        }
+        Ok(())
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> io::Result<()> {
+        for device in &mut self.devices {
+            device.restore(input)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::{Cpu, Memory, Opcode, StepError};
+
+    /// Remembers the last value written, along with the port
+    /// it went to, and returns it plus one when read.
+    #[derive(Default)]
+    struct Latch {
+        port: u8,
+        value: u8,
+    }
+
+    impl IoBus for Latch {
+        fn read_port(&mut self, _port: u8) -> u8 {
+            self.value.wrapping_add(1)
+        }
+
+        fn write_port(&mut self, port: u8, value: u8) {
+            self.port = port;
+            self.value = value;
+        }
+
+        fn save(&self, out: &mut dyn Write) -> io::Result<()> {
+            out.write_all(&[self.port, self.value])
+        }
+
+        fn restore(&mut self, input: &mut dyn Read) -> io::Result<()> {
+            let mut bytes = [0; 2];
+            input.read_exact(&mut bytes)?;
+            [self.port, self.value] = bytes;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn in_and_out_go_through_the_io_bus() {
+        // MVI A,41H; OUT 10H; IN 10H
+        let program = [0x3e, 0x41, 0xd3, 0x10, 0xdb, 0x10];
+        let mem = Memory::from_reader(&mut BufReader::new(&program[..])).unwrap();
+        let mut cpu = Cpu::with_io(mem, Latch::default());
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!((cpu.io().port, cpu.io().value), (0x10, 0x41));
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().a, 0x42);
+    }
+
+    #[test]
+    fn ports_dispatch_to_the_mapped_device() {
+        let mut ports = Ports::new(UnmappedPort::Float(0x5a));
+        ports.map([1, 2], Latch::default());
+        ports.write_port(2, 7);
+        assert_eq!(ports.read_port(1), 8);
+        assert_eq!(ports.read_port(3), 0x5a);
+
+        ports.map([2], Latch::default());
+        assert_eq!(ports.read_port(2), 1);
+        assert_eq!(ports.read_port(1), 8);
+
+        ports.unmap(1);
+        assert_eq!(ports.read_port(1), 0x5a);
+    }
+
+    #[test]
+    fn devices_without_ports_are_dropped() {
+        let mut ports = Ports::default();
+        ports.map([1, 2], Latch::default());
+        ports.map([3], Latch::default());
+        ports.write_port(3, 7);
+        ports.map([1], Latch::default());
+        assert_eq!(ports.devices.len(), 3);
+
+        ports.map([2], Latch::default());
+        assert_eq!(ports.devices.len(), 3);
+        assert_eq!(ports.read_port(3), 8);
+
+        ports.unmap(3);
+        assert_eq!(ports.devices.len(), 2);
+        ports.map([], Latch::default());
+        assert_eq!(ports.devices.len(), 2);
+
+        let mut out = Vec::new();
+        ports.save(&mut out).unwrap();
+        assert_eq!(out.len(), 4);
+    }
+
+    #[test]
+    fn unmapped_accesses_can_be_logged() {
+        let mut ports = Ports::new(UnmappedPort::Log(0x5a));
+        ports.map([1], Latch::default());
+        assert_eq!(ports.read_port(2), 0x5a);
+        ports.write_port(1, 7);
+        ports.write_port(3, 9);
+        let log: Vec<_> = ports.take_unmapped().collect();
+        assert_eq!(log, [(Access::In(2), 0x5a), (Access::Out(3), 9)]);
+        assert_eq!(ports.take_unmapped().count(), 0);
+        assert_eq!(ports.take_fault(), None);
+    }
+
+    #[test]
+    fn unmapped_ports_can_fault() {
+        // MVI A,1; OUT 10H
+        let program = [0x3e, 0x01, 0xd3, 0x10];
+        let mem = Memory::from_reader(&mut BufReader::new(&program[..])).unwrap();
+        let mut cpu = Cpu::with_io(mem, Ports::new(UnmappedPort::Fault));
+        cpu.step().unwrap();
+        let err = cpu.step().unwrap_err();
+        let access = Access::Out(0x10);
+        assert_eq!(err, StepError::IllegalAccess { pc: 2, opcode: Opcode::OUT(0x10), access });
+        assert_eq!(cpu.pc(), 2);
+        assert_eq!(cpu.io_mut().take_fault(), None);
+    }
+}
@@ -0,0 +1,422 @@
+use std::collections::VecDeque;
+
+use crate::{i8085, z80, Cpu, IoBus, MemoryBus, Opcode, Registers, Step, StepError};
+
+/// A record of the instructions executed by the processor, which allows
+/// stepping backwards through them.
+///
+/// For every instruction the journal keeps the registers and interrupt
+/// state from before it was executed, along with the memory bytes it
+/// overwrote. Only the most recent instructions are kept, up to the
+/// capacity of the journal. Beyond that, the processor is rewound by
+/// restoring one of the full snapshots taken every so often and executing
+/// forward again from there, which assumes that the program and its
+/// devices behave the same way the second time. Interrupts requested by
+/// the host are not replayed.
+///
+/// Port I/O cannot be undone: devices keep their state when stepping
+/// back, unless the processor is rewound to a snapshot. Resetting the
+/// processor or restoring a save state clears the journal.
+pub struct Journal {
+    /// Number of instructions executed since recording started.
+    steps: u64,
+    /// State from before each of the most recent instructions, oldest first.
+    deltas: VecDeque<Delta>,
+    /// Address and previous content of every byte overwritten by
+    /// those instructions, in the order they were written.
+    writes: VecDeque<(u16, u8)>,
+    capacity: usize,
+    /// Save states taken every `interval` instructions,
+    /// with the number of steps they were taken at.
+    snapshots: VecDeque<(u64, Vec<u8>)>,
+    interval: u64,
+    max_snapshots: usize,
+    /// The instruction being recorded.
+    current: Option<Delta>,
+}
+
+/// The state that an instruction may change, other than memory.
+struct Delta {
+    reg: Registers,
+    inte: bool,
+    ei_delay: bool,
+    halted: bool,
+    pending: Option<Opcode>,
+    cycles: u64,
+    /// State of the 8085 or Z80 extensions, only kept
+    /// if the instruction changed it.
+    extensions: Option<Box<(i8085::State, z80::State)>>,
+    /// Number of bytes the instruction overwrote.
+    writes: usize,
+}
+
+impl Journal {
+    /// Creates a journal that keeps the state of the given number of most
+    /// recent instructions, and takes no snapshots.
+    pub fn new(capacity: usize) -> Self {
+        Journal {
+            steps: 0,
+            deltas: VecDeque::new(),
+            writes: VecDeque::new(),
+            capacity,
+            snapshots: VecDeque::new(),
+            interval: 0,
+            max_snapshots: 0,
+            current: None,
+        }
+    }
+
+    /// Takes a save state every `interval` instructions, keeping at most
+    /// `count` of them. Each one holds a copy of memory, so `count` bounds
+    /// how much memory they use; `interval` bounds how many instructions
+    /// are executed again to rewind to a point between two of them.
+    /// No snapshots are taken if the memory or I/O bus does not support
+    /// save states, leaving the capacity as the limit.
+    pub fn set_snapshots(&mut self, interval: u64, count: usize) {
+        self.interval = interval;
+        self.max_snapshots = count;
+    }
+
+    /// Number of instructions executed since recording started,
+    /// less the ones that were stepped back over.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Number of instructions that can be stepped back over without
+    /// restoring a snapshot.
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /// Returns the step at which the byte at `adr` was last written, such
+    /// that stepping back to it undoes the write. Only the instructions
+    /// still in the journal are searched.
+    pub fn last_write(&self, adr: u16) -> Option<u64> {
+        let mut end = self.writes.len();
+        for (index, delta) in self.deltas.iter().enumerate().rev() {
+            let start = end - delta.writes;
+            if self.writes.range(start..end).any(|&(written, _)| written == adr) {
+                let oldest = self.steps - self.deltas.len() as u64;
+                return Some(oldest + index as u64);
+            }
+            end = start;
+        }
+        None
+    }
+
+    pub(crate) fn record_write(&mut self, adr: u16, previous: u8) {
+        if let Some(delta) = &mut self.current {
+            delta.writes += 1;
+            self.writes.push_back((adr, previous));
+        }
+    }
+
+    /// Forgets every recorded instruction and snapshot.
+    pub fn clear(&mut self) {
+        self.steps = 0;
+        self.deltas.clear();
+        self.writes.clear();
+        self.snapshots.clear();
+    }
+
+    fn push(&mut self, delta: Delta) {
+        self.deltas.push_back(delta);
+        self.steps += 1;
+        while self.deltas.len() > self.capacity {
+            if let Some(oldest) = self.deltas.pop_front() {
+                self.writes.drain(..oldest.writes);
+            }
+        }
+    }
+
+    fn snapshot_due(&self) -> bool {
+        self.interval > 0
+            && self.max_snapshots > 0
+            && self.steps.is_multiple_of(self.interval)
+            && self.snapshots.back().is_none_or(|&(steps, _)| steps < self.steps)
+    }
+}
+
+impl<M: MemoryBus, I: IoBus> Cpu<M, I> {
+    /// Starts recording executed instructions in the given journal, so
+    /// that they can be stepped back over. Replaces any previous journal.
+    pub fn set_journal(&mut self, journal: Journal) {
+        self.journal = Some(Box::new(journal));
+        self.take_snapshot();
+    }
+
+    /// Stops recording executed instructions and returns the journal.
+    pub fn take_journal(&mut self) -> Option<Journal> {
+        self.journal.take().map(|journal| *journal)
+    }
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_deref()
+    }
+
+    /// Undoes the last executed instruction. Returns `false` without doing
+    /// anything if there is no journal or nothing left to undo.
+    pub fn step_back(&mut self) -> bool {
+        let Some(journal) = &mut self.journal else {
+            return false;
+        };
+        if let Some(delta) = journal.deltas.pop_back() {
+            journal.steps -= 1;
+            let steps = journal.steps;
+            journal.snapshots.retain(|&(taken, _)| taken <= steps);
+            for _ in 0..delta.writes {
+                if let Some((adr, previous)) = journal.writes.pop_back() {
+                    self.mem.write(adr, previous);
+                }
+            }
+            self.undo(delta);
+            return true;
+        }
+        match journal.steps {
+            0 => false,
+            steps => self.rewind(steps - 1),
+        }
+    }
+
+    /// Steps back until the program counter reaches `pc`, at least one
+    /// instruction back. Returns `false` if the journal ran out first,
+    /// in which case the processor is left at the oldest recorded state.
+    pub fn run_back_to(&mut self, pc: u16) -> bool {
+        while self.step_back() {
+            if self.reg.pc == pc {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Steps back until [`Journal::steps`] equals `steps`. Returns `false`
+    /// if the journal ran out first.
+    pub fn step_back_to(&mut self, steps: u64) -> bool {
+        while self.journal.as_ref().is_some_and(|journal| journal.steps > steps) {
+            if !self.step_back() {
+                return false;
+            }
+        }
+        self.journal.is_some()
+    }
+
+    pub(crate) fn step_recorded(&mut self) -> Result<Step, StepError> {
+        let delta = Delta {
+            reg: self.reg,
+            inte: self.inte,
+            ei_delay: self.ei_delay,
+            halted: self.halted,
+            pending: self.pending,
+            cycles: self.cycles,
+            extensions: None,
+            writes: 0,
+        };
+        let extensions = (self.i8085.clone(), self.z80.clone());
+        if let Some(journal) = &mut self.journal {
+            journal.current = Some(delta);
+        }
+
+        let result = self.advance();
+
+        let Some(journal) = &mut self.journal else {
+            return result;
+        };
+        let Some(mut delta) = journal.current.take() else {
+            return result;
+        };
+        // Only faults that executed the instruction left anything to undo.
+        if !matches!(result, Ok(_) | Err(StepError::IllegalAccess { .. })) {
+            let start = journal.writes.len() - delta.writes;
+            journal.writes.truncate(start);
+            return result;
+        }
+        if extensions.0 != self.i8085 || extensions.1 != self.z80 {
+            delta.extensions = Some(Box::new(extensions));
+        }
+        journal.push(delta);
+
+        self.take_snapshot();
+        result
+    }
+
+    /// Adds a save state to the journal if one is due at the current step.
+    fn take_snapshot(&mut self) {
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        if !journal.snapshot_due() {
+            return;
+        }
+        let steps = journal.steps;
+        let mut snapshot = Vec::new();
+        if self.save(&mut snapshot).is_ok() {
+            let journal = self.journal.as_mut().expect("journal is recording");
+            journal.snapshots.push_back((steps, snapshot));
+            while journal.snapshots.len() > journal.max_snapshots {
+                journal.snapshots.pop_front();
+            }
+        }
+    }
+
+    fn undo(&mut self, delta: Delta) {
+        self.reg = delta.reg;
+        self.inte = delta.inte;
+        self.ei_delay = delta.ei_delay;
+        self.halted = delta.halted;
+        self.pending = delta.pending;
+        self.cycles = delta.cycles;
+        if let Some(extensions) = delta.extensions {
+            (self.i8085, self.z80) = *extensions;
+        }
+    }
+
+    /// Restores the last snapshot taken at or before `target`
+    /// and executes forward until `target` is reached.
+    fn rewind(&mut self, target: u64) -> bool {
+        let Some(journal) = &mut self.journal else {
+            return false;
+        };
+        journal.snapshots.retain(|&(taken, _)| taken <= target);
+        let Some((taken, snapshot)) = journal.snapshots.back().cloned() else {
+            return false;
+        };
+        if self.restore_state(&mut &snapshot[..]).is_err() {
+            return false;
+        }
+
+        let journal = self.journal.as_mut().expect("journal is recording");
+        journal.steps = taken;
+        journal.deltas.clear();
+        journal.writes.clear();
+        // The observer has already seen the instructions executed again.
+        let observer = self.observer.take();
+        let mut reached = true;
+        while self.journal.as_ref().is_some_and(|journal| journal.steps < target) {
+            match self.step_recorded() {
+                Ok(_) | Err(StepError::IllegalAccess { .. }) => {},
+                Err(_) => {
+                    reached = false;
+                    break;
+                },
+            }
+        }
+        self.observer = observer;
+        self.breakpoints.hit = None;
+        reached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::Memory;
+
+    // LXI H,0100H; INR M; MOV A,M; JMP 0003H
+    const PROGRAM: [u8; 8] = [0x21, 0x00, 0x01, 0x34, 0x7e, 0xc3, 0x03, 0x00];
+
+    fn cpu() -> Cpu {
+        Cpu::new(Memory::from_reader(&mut BufReader::new(&PROGRAM[..])).unwrap())
+    }
+
+    fn state(cpu: &Cpu) -> (Registers, u64, u8) {
+        (*cpu.reg(), cpu.cycles(), cpu.mem()[0x100])
+    }
+
+    #[test]
+    fn step_back_undoes_registers_and_memory() {
+        let mut cpu = cpu();
+        assert!(!cpu.step_back());
+        cpu.set_journal(Journal::new(16));
+        let mut states = vec![state(&cpu)];
+        for _ in 0..7 {
+            cpu.step().unwrap();
+            states.push(state(&cpu));
+        }
+        assert_eq!(cpu.mem()[0x100], 2);
+        assert_eq!(cpu.journal().unwrap().last_write(0x100), Some(4));
+        assert_eq!(cpu.journal().unwrap().last_write(0x101), None);
+
+        while let Some(expected) = states.pop() {
+            assert_eq!(state(&cpu), expected);
+            assert_eq!(cpu.journal().unwrap().steps(), states.len() as u64);
+            assert_eq!(cpu.step_back(), !states.is_empty());
+        }
+    }
+
+    #[test]
+    fn run_back_to_stops_at_the_program_counter() {
+        let mut cpu = cpu();
+        cpu.set_journal(Journal::new(16));
+        for _ in 0..7 {
+            cpu.step().unwrap();
+        }
+        assert!(cpu.run_back_to(4));
+        assert_eq!((cpu.pc(), cpu.journal().unwrap().steps(), cpu.mem()[0x100]), (4, 5, 2));
+        assert!(!cpu.run_back_to(0x1234));
+        assert_eq!((cpu.pc(), cpu.journal().unwrap().steps()), (0, 0));
+    }
+
+    #[test]
+    fn snapshots_rewind_beyond_the_capacity() {
+        let mut cpu = cpu();
+        let mut journal = Journal::new(2);
+        journal.set_snapshots(4, 8);
+        cpu.set_journal(journal);
+        let mut states = vec![state(&cpu)];
+        for _ in 0..20 {
+            cpu.step().unwrap();
+            states.push(state(&cpu));
+        }
+        assert_eq!(cpu.journal().unwrap().len(), 2);
+
+        assert!(cpu.step_back_to(9));
+        assert_eq!(state(&cpu), states[9]);
+        assert!(cpu.step_back_to(1));
+        assert_eq!(state(&cpu), states[1]);
+        assert!(cpu.step_back());
+        assert!(!cpu.step_back());
+        assert_eq!(state(&cpu), states[0]);
+    }
+
+    #[test]
+    fn restoring_a_save_state_clears_the_journal() {
+        let mut cpu = cpu();
+        cpu.set_journal(Journal::new(16));
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+        let mut snapshot = Vec::new();
+        cpu.save(&mut snapshot).unwrap();
+        let saved = state(&cpu);
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+
+        cpu.restore(&mut &snapshot[..]).unwrap();
+        assert!(!cpu.step_back());
+        assert_eq!(state(&cpu), saved);
+        assert_eq!(cpu.journal().unwrap().steps(), 0);
+        assert_eq!(cpu.journal().unwrap().last_write(0x100), None);
+    }
+
+    #[test]
+    fn without_snapshots_the_capacity_is_the_limit() {
+        let mut cpu = cpu();
+        cpu.set_journal(Journal::new(2));
+        for _ in 0..5 {
+            cpu.step().unwrap();
+        }
+        assert!(cpu.step_back());
+        assert!(cpu.step_back());
+        assert!(!cpu.step_back());
+        assert_eq!(cpu.journal().unwrap().steps(), 3);
+    }
+}
@@ -1,66 +1,140 @@
+mod breakpoint;
+mod cpu;
+mod decode;
+mod error;
 mod flags;
+mod i8085;
+mod instruction;
+mod io;
+mod journal;
 mod memory;
+mod metadata;
+mod observer;
 mod opcode;
+mod record;
 mod registers;
-
+mod snapshot;
+mod syntax;
+mod trace;
+pub mod z80;
+
+pub use breakpoint::{Condition, Trigger};
+pub use cpu::{Cpu, Step, Undocumented, Variant};
+pub use decode::{decode, decode_with, Instructions};
+pub use error::{Access, ConditionError, DecodeError, SnapshotError, StepError};
 pub use flags::Flags;
-pub use memory::Memory;
+pub use i8085::InterruptLine;
+pub use instruction::{AluOp, Cond, Instruction, Reg8, RegPair};
+pub use io::{IoBus, Ports, UnmappedPort};
+pub use journal::Journal;
+pub use memory::{Memory, MemoryBus};
+pub use metadata::{metadata, Accesses, Group, Metadata};
+pub use observer::Observer;
+pub use opcode::Opcode;
+pub use record::{TraceEvent, TraceReader, TraceRecord, TraceWriter, Z80Registers, VERSION as TRACE_VERSION};
 pub use registers::Registers;
-use opcode::Opcode;
+pub use snapshot::VERSION as SNAPSHOT_VERSION;
+pub use syntax::{Hex, Style, Syntax};
+pub use trace::TextTrace;
+
+use flags::Op;
 
 fn add(a: u8, value: u8, flags: &mut Flags) -> u8 {
-    let (a, cy) = a.overflowing_add(value);
-    flags.upd(a, Some(cy));
-    a
+    let (res, cy) = a.overflowing_add(value);
+    flags.upd(res, Some(cy));
+    flags.upd_op(Op::Add, a, value, res);
+    res
 }
 
 fn adc(a: u8, value: u8, flags: &mut Flags) -> u8 {
-    let (a, cy1) = a.overflowing_add(value);
-    let (a, cy2) = a.overflowing_add(flags.carry as u8);
-    flags.upd(a, Some(cy1 | cy2));
-    a
+    let (res, cy1) = a.overflowing_add(value);
+    let (res, cy2) = res.overflowing_add(flags.carry() as u8);
+    flags.upd(res, Some(cy1 | cy2));
+    flags.upd_op(Op::Add, a, value, res);
+    res
 }
 
 fn sub(a: u8, value: u8, flags: &mut Flags) -> u8 {
-    let (a, cy) = a.overflowing_sub(value);
-    flags.upd(a, Some(cy));
-    a
+    let (res, cy) = a.overflowing_sub(value);
+    flags.upd(res, Some(cy));
+    flags.upd_op(Op::Sub, a, value, res);
+    res
 }
 
 fn sbb(a: u8, value: u8, flags: &mut Flags) -> u8 {
-    let (a, cy1) = a.overflowing_sub(value);
-    let (a, cy2) = a.overflowing_sub(flags.carry as u8);
-    flags.upd(a, Some(cy1 | cy2));
-    a
+    let (res, cy1) = a.overflowing_sub(value);
+    let (res, cy2) = res.overflowing_sub(flags.carry() as u8);
+    flags.upd(res, Some(cy1 | cy2));
+    flags.upd_op(Op::Sub, a, value, res);
+    res
+}
+
+fn inr(value: u8, flags: &mut Flags) -> u8 {
+    let res = value.wrapping_add(1);
+    flags.upd(res, None);
+    flags.upd_op(Op::Inr, value, 1, res);
+    res
+}
+
+fn dcr(value: u8, flags: &mut Flags) -> u8 {
+    let res = value.wrapping_sub(1);
+    flags.upd(res, None);
+    flags.upd_op(Op::Dcr, value, 1, res);
+    res
 }
 
 fn and(a: u8, value: u8, flags: &mut Flags) -> u8 {
-    let a = a & value;
-    flags.upd(a, Some(false));
-    a
+    let res = a & value;
+    flags.upd(res, Some(false));
+    flags.upd_op(Op::And, a, value, res);
+    res
 }
 
 fn xor(a: u8, value: u8, flags: &mut Flags) -> u8 {
-    let a = a ^ value;
-    flags.upd(a, Some(false));
-    a
+    let res = a ^ value;
+    flags.upd(res, Some(false));
+    flags.upd_op(Op::Or, a, value, res);
+    res
 }
 
 fn ior(a: u8, value: u8, flags: &mut Flags) -> u8 {
-    let a = a | value;
-    flags.upd(a, Some(false));
-    a
+    let res = a | value;
+    flags.upd(res, Some(false));
+    flags.upd_op(Op::Or, a, value, res);
+    res
 }
 
 fn cmp(a: u8, value: u8, flags: &mut Flags) {
-    let (a, cy) = a.overflowing_sub(value);
-    flags.upd(a, Some(cy));
+    sub(a, value, flags);
 }
 
-pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
-    //let pc = mem.pc;
-    let opcode = mem.read_opcode();
-    // println!("{:#010x}: {:?}", pc, opcode);
+fn daa(a: u8, flags: &mut Flags) -> u8 {
+    let lsb = a & 0x0f;
+    let msb = a >> 4;
+    let mut correction = 0;
+    let mut cy = flags.carry();
+    if flags.carry_aux() || lsb > 9 {
+        correction |= 0x06;
+    }
+    if cy || msb > 9 || (msb >= 9 && lsb > 9) {
+        correction |= 0x60;
+        cy = true;
+    }
+    let res = add(a, correction, flags);
+    flags.set_carry(cy);
+    res
+}
+
+/// Executes a single decoded instruction and returns the number of states
+/// it took, or `None` if the instruction is not supported.
+pub(crate) fn emulate<M: MemoryBus, I: IoBus>(cpu: &mut Cpu<M, I>, opcode: Opcode) -> Option<u32> {
+    if !opcode.supported_by(cpu.variant) {
+        return None;
+    }
+
+    // Conditional instructions may take longer when the condition is met.
+    let mut taken = false;
+
     match opcode {
         //
         // Data transfer group
@@ -86,54 +160,54 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         Opcode::MOV_HH => {},
         Opcode::MOV_LL => {},
 
-        Opcode::MOV_AB => reg.a = reg.b,
-        Opcode::MOV_AC => reg.a = reg.c,
-        Opcode::MOV_AD => reg.a = reg.d,
-        Opcode::MOV_AE => reg.a = reg.e,
-        Opcode::MOV_AH => reg.a = reg.h,
-        Opcode::MOV_AL => reg.a = reg.l,
-
-        Opcode::MOV_BA => reg.b = reg.a,
-        Opcode::MOV_BC => reg.b = reg.c,
-        Opcode::MOV_BD => reg.b = reg.d,
-        Opcode::MOV_BE => reg.b = reg.e,
-        Opcode::MOV_BH => reg.b = reg.h,
-        Opcode::MOV_BL => reg.b = reg.l,
-
-        Opcode::MOV_CA => reg.c = reg.a,
-        Opcode::MOV_CB => reg.c = reg.b,
-        Opcode::MOV_CD => reg.c = reg.d,
-        Opcode::MOV_CE => reg.c = reg.e,
-        Opcode::MOV_CH => reg.c = reg.h,
-        Opcode::MOV_CL => reg.c = reg.l,
-
-        Opcode::MOV_DA => reg.d = reg.a,
-        Opcode::MOV_DB => reg.d = reg.b,
-        Opcode::MOV_DC => reg.d = reg.c,
-        Opcode::MOV_DE => reg.d = reg.e,
-        Opcode::MOV_DH => reg.d = reg.h,
-        Opcode::MOV_DL => reg.d = reg.l,
-
-        Opcode::MOV_EA => reg.e = reg.a,
-        Opcode::MOV_EB => reg.e = reg.b,
-        Opcode::MOV_EC => reg.e = reg.c,
-        Opcode::MOV_ED => reg.e = reg.d,
-        Opcode::MOV_EH => reg.e = reg.h,
-        Opcode::MOV_EL => reg.e = reg.l,
-
-        Opcode::MOV_HA => reg.h = reg.a,
-        Opcode::MOV_HB => reg.h = reg.b,
-        Opcode::MOV_HC => reg.h = reg.c,
-        Opcode::MOV_HD => reg.h = reg.d,
-        Opcode::MOV_HE => reg.h = reg.e,
-        Opcode::MOV_HL => reg.h = reg.l,
-
-        Opcode::MOV_LA => reg.l = reg.a,
-        Opcode::MOV_LB => reg.l = reg.b,
-        Opcode::MOV_LC => reg.l = reg.c,
-        Opcode::MOV_LD => reg.l = reg.d,
-        Opcode::MOV_LE => reg.l = reg.e,
-        Opcode::MOV_LH => reg.l = reg.h,
+        Opcode::MOV_AB => cpu.reg.a = cpu.reg.b,
+        Opcode::MOV_AC => cpu.reg.a = cpu.reg.c,
+        Opcode::MOV_AD => cpu.reg.a = cpu.reg.d,
+        Opcode::MOV_AE => cpu.reg.a = cpu.reg.e,
+        Opcode::MOV_AH => cpu.reg.a = cpu.reg.h,
+        Opcode::MOV_AL => cpu.reg.a = cpu.reg.l,
+
+        Opcode::MOV_BA => cpu.reg.b = cpu.reg.a,
+        Opcode::MOV_BC => cpu.reg.b = cpu.reg.c,
+        Opcode::MOV_BD => cpu.reg.b = cpu.reg.d,
+        Opcode::MOV_BE => cpu.reg.b = cpu.reg.e,
+        Opcode::MOV_BH => cpu.reg.b = cpu.reg.h,
+        Opcode::MOV_BL => cpu.reg.b = cpu.reg.l,
+
+        Opcode::MOV_CA => cpu.reg.c = cpu.reg.a,
+        Opcode::MOV_CB => cpu.reg.c = cpu.reg.b,
+        Opcode::MOV_CD => cpu.reg.c = cpu.reg.d,
+        Opcode::MOV_CE => cpu.reg.c = cpu.reg.e,
+        Opcode::MOV_CH => cpu.reg.c = cpu.reg.h,
+        Opcode::MOV_CL => cpu.reg.c = cpu.reg.l,
+
+        Opcode::MOV_DA => cpu.reg.d = cpu.reg.a,
+        Opcode::MOV_DB => cpu.reg.d = cpu.reg.b,
+        Opcode::MOV_DC => cpu.reg.d = cpu.reg.c,
+        Opcode::MOV_DE => cpu.reg.d = cpu.reg.e,
+        Opcode::MOV_DH => cpu.reg.d = cpu.reg.h,
+        Opcode::MOV_DL => cpu.reg.d = cpu.reg.l,
+
+        Opcode::MOV_EA => cpu.reg.e = cpu.reg.a,
+        Opcode::MOV_EB => cpu.reg.e = cpu.reg.b,
+        Opcode::MOV_EC => cpu.reg.e = cpu.reg.c,
+        Opcode::MOV_ED => cpu.reg.e = cpu.reg.d,
+        Opcode::MOV_EH => cpu.reg.e = cpu.reg.h,
+        Opcode::MOV_EL => cpu.reg.e = cpu.reg.l,
+
+        Opcode::MOV_HA => cpu.reg.h = cpu.reg.a,
+        Opcode::MOV_HB => cpu.reg.h = cpu.reg.b,
+        Opcode::MOV_HC => cpu.reg.h = cpu.reg.c,
+        Opcode::MOV_HD => cpu.reg.h = cpu.reg.d,
+        Opcode::MOV_HE => cpu.reg.h = cpu.reg.e,
+        Opcode::MOV_HL => cpu.reg.h = cpu.reg.l,
+
+        Opcode::MOV_LA => cpu.reg.l = cpu.reg.a,
+        Opcode::MOV_LB => cpu.reg.l = cpu.reg.b,
+        Opcode::MOV_LC => cpu.reg.l = cpu.reg.c,
+        Opcode::MOV_LD => cpu.reg.l = cpu.reg.d,
+        Opcode::MOV_LE => cpu.reg.l = cpu.reg.e,
+        Opcode::MOV_LH => cpu.reg.l = cpu.reg.h,
 
         // MOV r, M (Move from memory)
         //   (r) ← ((H) (L))
@@ -144,13 +218,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register indirect
         // Flags: none
-        Opcode::MOV_AM => reg.a = mem[reg.m()],
-        Opcode::MOV_BM => reg.b = mem[reg.m()],
-        Opcode::MOV_CM => reg.c = mem[reg.m()],
-        Opcode::MOV_DM => reg.d = mem[reg.m()],
-        Opcode::MOV_EM => reg.e = mem[reg.m()],
-        Opcode::MOV_HM => reg.h = mem[reg.m()],
-        Opcode::MOV_LM => reg.l = mem[reg.m()],
+        Opcode::MOV_AM => cpu.reg.a = cpu.read(cpu.reg.m()),
+        Opcode::MOV_BM => cpu.reg.b = cpu.read(cpu.reg.m()),
+        Opcode::MOV_CM => cpu.reg.c = cpu.read(cpu.reg.m()),
+        Opcode::MOV_DM => cpu.reg.d = cpu.read(cpu.reg.m()),
+        Opcode::MOV_EM => cpu.reg.e = cpu.read(cpu.reg.m()),
+        Opcode::MOV_HM => cpu.reg.h = cpu.read(cpu.reg.m()),
+        Opcode::MOV_LM => cpu.reg.l = cpu.read(cpu.reg.m()),
 
         // MOV M, r (Move to memory)
         //   ((H) (L)) ← (r)
@@ -161,13 +235,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register indirect
         // Flags: none
-        Opcode::MOV_MA => mem[reg.m()] = reg.a,
-        Opcode::MOV_MB => mem[reg.m()] = reg.b,
-        Opcode::MOV_MC => mem[reg.m()] = reg.c,
-        Opcode::MOV_MD => mem[reg.m()] = reg.d,
-        Opcode::MOV_ME => mem[reg.m()] = reg.e,
-        Opcode::MOV_MH => mem[reg.m()] = reg.h,
-        Opcode::MOV_ML => mem[reg.m()] = reg.l,
+        Opcode::MOV_MA => cpu.write(cpu.reg.m(), cpu.reg.a),
+        Opcode::MOV_MB => cpu.write(cpu.reg.m(), cpu.reg.b),
+        Opcode::MOV_MC => cpu.write(cpu.reg.m(), cpu.reg.c),
+        Opcode::MOV_MD => cpu.write(cpu.reg.m(), cpu.reg.d),
+        Opcode::MOV_ME => cpu.write(cpu.reg.m(), cpu.reg.e),
+        Opcode::MOV_MH => cpu.write(cpu.reg.m(), cpu.reg.h),
+        Opcode::MOV_ML => cpu.write(cpu.reg.m(), cpu.reg.l),
 
         // MVI r, data (Move immediate)
         //   (r) ← (byte 2)
@@ -177,13 +251,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: immediate
         // Flags: none
-        Opcode::MVI_A(d8) => reg.a = d8,
-        Opcode::MVI_B(d8) => reg.b = d8,
-        Opcode::MVI_C(d8) => reg.c = d8,
-        Opcode::MVI_D(d8) => reg.d = d8,
-        Opcode::MVI_E(d8) => reg.e = d8,
-        Opcode::MVI_H(d8) => reg.h = d8,
-        Opcode::MVI_L(d8) => reg.l = d8,
+        Opcode::MVI_A(d8) => cpu.reg.a = d8,
+        Opcode::MVI_B(d8) => cpu.reg.b = d8,
+        Opcode::MVI_C(d8) => cpu.reg.c = d8,
+        Opcode::MVI_D(d8) => cpu.reg.d = d8,
+        Opcode::MVI_E(d8) => cpu.reg.e = d8,
+        Opcode::MVI_H(d8) => cpu.reg.h = d8,
+        Opcode::MVI_L(d8) => cpu.reg.l = d8,
 
         // MVI M, data (Move to memory immediate)
         //   ((H) (L)) ← (byte 2)
@@ -194,7 +268,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 10
         // Addressing: immediate/register indirect
         // Flags: none
-        Opcode::MVI_M(d8) => mem[reg.m()] = d8,
+        Opcode::MVI_M(d8) => cpu.write(cpu.reg.m(), d8),
 
         // LXI rp, data 16 (Load register pair immediate)
         //   (rh) ← (byte 3),
@@ -207,10 +281,10 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 10
         // Addressing: immediate
         // Flags: none
-        Opcode::LXI_BC(rl, rh) => { reg.b = rh; reg.c = rl; },
-        Opcode::LXI_DE(rl, rh) => { reg.d = rh; reg.e = rl; },
-        Opcode::LXI_HL(rl, rh) => { reg.h = rh; reg.l = rl; },
-        Opcode::LXI_SP(rl, rh) => mem.set_sp(rh, rl),
+        Opcode::LXI_BC(rl, rh) => { cpu.reg.b = rh; cpu.reg.c = rl; },
+        Opcode::LXI_DE(rl, rh) => { cpu.reg.d = rh; cpu.reg.e = rl; },
+        Opcode::LXI_HL(rl, rh) => { cpu.reg.h = rh; cpu.reg.l = rl; },
+        Opcode::LXI_SP(rl, rh) => cpu.load_sp(rh, rl),
 
         // LDA addr (Load accumulator direct)
         //   (A) ← ((byte 3) (byte 2))
@@ -221,7 +295,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 13
         // Addressing: direct
         // Flags: none
-        Opcode::LDA(adr) => mem[adr] = reg.a,
+        Opcode::LDA(adr) => cpu.reg.a = cpu.read(adr),
 
         // STA addr (Store accumulator direct)
         //   ((byte 3) (byte 2)) ← (A)
@@ -232,7 +306,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 13
         // Addressing: direct
         // Flags: none
-        Opcode::STA(adr) => mem[adr] = reg.a,
+        Opcode::STA(adr) => cpu.write(adr, cpu.reg.a),
 
         // LHLD addr (Load H and L direct)
         //   (L) ← ((byte 3) (byte 2))
@@ -247,8 +321,8 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Addressing: direct
         // Flags: none
         Opcode::LHLD(adr) => {
-            reg.l = mem[adr];
-            reg.h = mem[adr + 1];
+            cpu.reg.l = cpu.read(adr);
+            cpu.reg.h = cpu.read(adr.wrapping_add(1));
         },
 
         // SHLD addr (Store H and L direct)
@@ -263,8 +337,8 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Addressing: direct
         // Flags: none
         Opcode::SHLD(adr) => {
-            mem[adr] = reg.l;
-            mem[adr + 1] = reg.h;
+            cpu.write(adr, cpu.reg.l);
+            cpu.write(adr.wrapping_add(1), cpu.reg.h);
         },
 
         // LDAX rp (Load accumulator indirect)
@@ -276,8 +350,8 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register direct
         // Flags: none
-        Opcode::LDAX_BC => reg.a = mem[reg.bc()],
-        Opcode::LDAX_DE => reg.a = mem[reg.de()],
+        Opcode::LDAX_BC => cpu.reg.a = cpu.read(cpu.reg.bc()),
+        Opcode::LDAX_DE => cpu.reg.a = cpu.read(cpu.reg.de()),
 
         // STAX rp (Store accumulator indirect)
         //   ((rp)) ← (A)
@@ -288,8 +362,8 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register direct
         // Flags: none
-        Opcode::STAX_BC => mem[reg.bc()] = reg.a,
-        Opcode::STAX_DE => mem[reg.de()] = reg.a,
+        Opcode::STAX_BC => cpu.write(cpu.reg.bc(), cpu.reg.a),
+        Opcode::STAX_DE => cpu.write(cpu.reg.de(), cpu.reg.a),
 
         // XCHG (Exchange H and L with D and E)
         //   (H) ↔ (D)
@@ -302,8 +376,8 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Addressing: register
         // Flags: none
         Opcode::XCHG => {
-            std::mem::swap(&mut reg.c, &mut reg.d);
-            std::mem::swap(&mut reg.l, &mut reg.e);
+            std::mem::swap(&mut cpu.reg.h, &mut cpu.reg.d);
+            std::mem::swap(&mut cpu.reg.l, &mut cpu.reg.e);
         },
 
         //
@@ -328,13 +402,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 4
         // Addressing: register
         // Flags: Z, S, P, CY, AC
-        Opcode::ADD_A => reg.a = add(reg.a, reg.a, flags),
-        Opcode::ADD_B => reg.a = add(reg.a, reg.b, flags),
-        Opcode::ADD_C => reg.a = add(reg.a, reg.c, flags),
-        Opcode::ADD_D => reg.a = add(reg.a, reg.d, flags),
-        Opcode::ADD_E => reg.a = add(reg.a, reg.e, flags),
-        Opcode::ADD_H => reg.a = add(reg.a, reg.h, flags),
-        Opcode::ADD_L => reg.a = add(reg.a, reg.l, flags),
+        Opcode::ADD_A => cpu.reg.a = add(cpu.reg.a, cpu.reg.a, &mut cpu.reg.flags),
+        Opcode::ADD_B => cpu.reg.a = add(cpu.reg.a, cpu.reg.b, &mut cpu.reg.flags),
+        Opcode::ADD_C => cpu.reg.a = add(cpu.reg.a, cpu.reg.c, &mut cpu.reg.flags),
+        Opcode::ADD_D => cpu.reg.a = add(cpu.reg.a, cpu.reg.d, &mut cpu.reg.flags),
+        Opcode::ADD_E => cpu.reg.a = add(cpu.reg.a, cpu.reg.e, &mut cpu.reg.flags),
+        Opcode::ADD_H => cpu.reg.a = add(cpu.reg.a, cpu.reg.h, &mut cpu.reg.flags),
+        Opcode::ADD_L => cpu.reg.a = add(cpu.reg.a, cpu.reg.l, &mut cpu.reg.flags),
 
         // ADD M (Add memory)
         //   (A) ← (A) + ((H) (L))
@@ -346,7 +420,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register indirect
         // Flags: Z, S, P, CY, AC
-        Opcode::ADD_M => reg.a = add(reg.a, mem[reg.m()], flags),
+        Opcode::ADD_M => cpu.reg.a = add(cpu.reg.a, cpu.read(cpu.reg.m()), &mut cpu.reg.flags),
 
         // ADI data (Add immediate)
         //   (A) ← (A) + (byte 2)
@@ -358,7 +432,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: immediate
         // Flags: Z, S, P, CY, AC
-        Opcode::ADI(d8) => reg.a = add(reg.a, d8, flags),
+        Opcode::ADI(d8) => cpu.reg.a = add(cpu.reg.a, d8, &mut cpu.reg.flags),
 
         // ADC r (Add register with carry)
         //   (A) ← (A) + (r) + (CY)
@@ -370,13 +444,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 4
         // Addressing: register
         // Flags: Z, S, P, CY, AC
-        Opcode::ADC_A => reg.a = adc(reg.a, reg.a, flags),
-        Opcode::ADC_B => reg.a = adc(reg.a, reg.b, flags),
-        Opcode::ADC_C => reg.a = adc(reg.a, reg.c, flags),
-        Opcode::ADC_D => reg.a = adc(reg.a, reg.d, flags),
-        Opcode::ADC_E => reg.a = adc(reg.a, reg.e, flags),
-        Opcode::ADC_H => reg.a = adc(reg.a, reg.h, flags),
-        Opcode::ADC_L => reg.a = adc(reg.a, reg.l, flags),
+        Opcode::ADC_A => cpu.reg.a = adc(cpu.reg.a, cpu.reg.a, &mut cpu.reg.flags),
+        Opcode::ADC_B => cpu.reg.a = adc(cpu.reg.a, cpu.reg.b, &mut cpu.reg.flags),
+        Opcode::ADC_C => cpu.reg.a = adc(cpu.reg.a, cpu.reg.c, &mut cpu.reg.flags),
+        Opcode::ADC_D => cpu.reg.a = adc(cpu.reg.a, cpu.reg.d, &mut cpu.reg.flags),
+        Opcode::ADC_E => cpu.reg.a = adc(cpu.reg.a, cpu.reg.e, &mut cpu.reg.flags),
+        Opcode::ADC_H => cpu.reg.a = adc(cpu.reg.a, cpu.reg.h, &mut cpu.reg.flags),
+        Opcode::ADC_L => cpu.reg.a = adc(cpu.reg.a, cpu.reg.l, &mut cpu.reg.flags),
 
         // ADC M (Add memory with carry)
         //   (A) ← (A) + ((H) (L)) + (CY)
@@ -385,10 +459,10 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         //   to the accumulator. The result is placed in the accumulator.
         //
         // Cycles: 2
-        // States: 4
+        // States: 7
         // Addressing: register indirect
         // Flags: Z, S, P, CY, AC
-        Opcode::ADC_M => reg.a = adc(reg.a, mem[reg.m()], flags),
+        Opcode::ADC_M => cpu.reg.a = adc(cpu.reg.a, cpu.read(cpu.reg.m()), &mut cpu.reg.flags),
 
         // ACI data (Add immediate with carry)
         //   (A) ← (A) + (byte 2) + (CY)
@@ -400,7 +474,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: immediate
         // Flags: Z, S, P, CY, AC
-        Opcode::ACI(d8) => reg.a = adc(reg.a, d8, flags),
+        Opcode::ACI(d8) => cpu.reg.a = adc(cpu.reg.a, d8, &mut cpu.reg.flags),
 
         // SUB r (Subtract register)
         //   (A) ← (A) - (r)
@@ -411,13 +485,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 4
         // Addressing: register
         // Flags: Z, S, P, CY, AC
-        Opcode::SUB_A => reg.a = sub(reg.a, reg.a, flags),
-        Opcode::SUB_B => reg.a = sub(reg.a, reg.b, flags),
-        Opcode::SUB_C => reg.a = sub(reg.a, reg.c, flags),
-        Opcode::SUB_D => reg.a = sub(reg.a, reg.d, flags),
-        Opcode::SUB_E => reg.a = sub(reg.a, reg.e, flags),
-        Opcode::SUB_H => reg.a = sub(reg.a, reg.h, flags),
-        Opcode::SUB_L => reg.a = sub(reg.a, reg.l, flags),
+        Opcode::SUB_A => cpu.reg.a = sub(cpu.reg.a, cpu.reg.a, &mut cpu.reg.flags),
+        Opcode::SUB_B => cpu.reg.a = sub(cpu.reg.a, cpu.reg.b, &mut cpu.reg.flags),
+        Opcode::SUB_C => cpu.reg.a = sub(cpu.reg.a, cpu.reg.c, &mut cpu.reg.flags),
+        Opcode::SUB_D => cpu.reg.a = sub(cpu.reg.a, cpu.reg.d, &mut cpu.reg.flags),
+        Opcode::SUB_E => cpu.reg.a = sub(cpu.reg.a, cpu.reg.e, &mut cpu.reg.flags),
+        Opcode::SUB_H => cpu.reg.a = sub(cpu.reg.a, cpu.reg.h, &mut cpu.reg.flags),
+        Opcode::SUB_L => cpu.reg.a = sub(cpu.reg.a, cpu.reg.l, &mut cpu.reg.flags),
 
         // SUB M (Subtract memory)
         //   (A) ← (A) - ((H) (L))
@@ -429,7 +503,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register indirect
         // Flags: Z, S, P, CY, AC
-        Opcode::SUB_M => reg.a = sub(reg.a, mem[reg.m()], flags),
+        Opcode::SUB_M => cpu.reg.a = sub(cpu.reg.a, cpu.read(cpu.reg.m()), &mut cpu.reg.flags),
 
         // SUI data (Subtract immediate)
         //   (A) ← (A) - (byte 2)
@@ -441,7 +515,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: immediate
         // Flags: Z, S, P, CY, AC
-        Opcode::SUI(d8) => reg.a = sub(reg.a, d8, flags),
+        Opcode::SUI(d8) => cpu.reg.a = sub(cpu.reg.a, d8, &mut cpu.reg.flags),
 
         // SBB r (Subtract register with borrow)
         //   (A) ← (A) - (r) - (CY)
@@ -453,13 +527,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 4
         // Addressing: register
         // Flags: Z, S, P, CY, AC
-        Opcode::SBB_A => reg.a = sbb(reg.a, reg.a, flags),
-        Opcode::SBB_B => reg.a = sbb(reg.a, reg.b, flags),
-        Opcode::SBB_C => reg.a = sbb(reg.a, reg.c, flags),
-        Opcode::SBB_D => reg.a = sbb(reg.a, reg.d, flags),
-        Opcode::SBB_E => reg.a = sbb(reg.a, reg.e, flags),
-        Opcode::SBB_H => reg.a = sbb(reg.a, reg.h, flags),
-        Opcode::SBB_L => reg.a = sbb(reg.a, reg.l, flags),
+        Opcode::SBB_A => cpu.reg.a = sbb(cpu.reg.a, cpu.reg.a, &mut cpu.reg.flags),
+        Opcode::SBB_B => cpu.reg.a = sbb(cpu.reg.a, cpu.reg.b, &mut cpu.reg.flags),
+        Opcode::SBB_C => cpu.reg.a = sbb(cpu.reg.a, cpu.reg.c, &mut cpu.reg.flags),
+        Opcode::SBB_D => cpu.reg.a = sbb(cpu.reg.a, cpu.reg.d, &mut cpu.reg.flags),
+        Opcode::SBB_E => cpu.reg.a = sbb(cpu.reg.a, cpu.reg.e, &mut cpu.reg.flags),
+        Opcode::SBB_H => cpu.reg.a = sbb(cpu.reg.a, cpu.reg.h, &mut cpu.reg.flags),
+        Opcode::SBB_L => cpu.reg.a = sbb(cpu.reg.a, cpu.reg.l, &mut cpu.reg.flags),
 
         // SBB M (Subtract memory with borrow)
         //   (A) ← (A) - ((H) (L)) - (CY)
@@ -472,7 +546,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register indirect
         // Flags: Z, S, P, CY, AC
-        Opcode::SBB_M => reg.a = sbb(reg.a, mem[reg.m()], flags),
+        Opcode::SBB_M => cpu.reg.a = sbb(cpu.reg.a, cpu.read(cpu.reg.m()), &mut cpu.reg.flags),
 
         // SBI data (Subtract immediate with borrow)
         //   (A) ← (A) - (byte 2) - (CY)
@@ -484,7 +558,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: immediate
         // Flags: Z, S, P, CY, AC
-        Opcode::SBI(d8) => reg.a = sbb(reg.a, d8, flags),
+        Opcode::SBI(d8) => cpu.reg.a = sbb(cpu.reg.a, d8, &mut cpu.reg.flags),
 
         // INR r (Increment register)
         //   (r) ← (r) + 1
@@ -495,13 +569,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 5
         // Addressing: register
         // Flags: Z, S, P, AC
-        Opcode::INR_A => { reg.a = reg.a.wrapping_add(1); flags.upd(reg.a, None); },
-        Opcode::INR_B => { reg.b = reg.b.wrapping_add(1); flags.upd(reg.b, None); },
-        Opcode::INR_C => { reg.c = reg.c.wrapping_add(1); flags.upd(reg.c, None); },
-        Opcode::INR_D => { reg.d = reg.d.wrapping_add(1); flags.upd(reg.d, None); },
-        Opcode::INR_E => { reg.e = reg.e.wrapping_add(1); flags.upd(reg.e, None); },
-        Opcode::INR_H => { reg.h = reg.h.wrapping_add(1); flags.upd(reg.h, None); },
-        Opcode::INR_L => { reg.l = reg.l.wrapping_add(1); flags.upd(reg.l, None); },
+        Opcode::INR_A => cpu.reg.a = inr(cpu.reg.a, &mut cpu.reg.flags),
+        Opcode::INR_B => cpu.reg.b = inr(cpu.reg.b, &mut cpu.reg.flags),
+        Opcode::INR_C => cpu.reg.c = inr(cpu.reg.c, &mut cpu.reg.flags),
+        Opcode::INR_D => cpu.reg.d = inr(cpu.reg.d, &mut cpu.reg.flags),
+        Opcode::INR_E => cpu.reg.e = inr(cpu.reg.e, &mut cpu.reg.flags),
+        Opcode::INR_H => cpu.reg.h = inr(cpu.reg.h, &mut cpu.reg.flags),
+        Opcode::INR_L => cpu.reg.l = inr(cpu.reg.l, &mut cpu.reg.flags),
 
         // INR M (Increment memory)
         //   ((H) (L)) ← ((H) (L)) + 1
@@ -513,7 +587,10 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 10
         // Addressing: register indirect
         // Flags: Z, S, P, AC
-        Opcode::INR_M => { mem[reg.m()] = mem[reg.m()].wrapping_add(1); flags.upd(mem[reg.m()], None); },
+        Opcode::INR_M => {
+            let value = inr(cpu.read(cpu.reg.m()), &mut cpu.reg.flags);
+            cpu.write(cpu.reg.m(), value);
+        },
 
         // DCR r (Decrement register)
         //   (r) ← (r)-1
@@ -524,13 +601,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 5
         // Addressing: register
         // Flags: Z, S, P, AC
-        Opcode::DCR_A => { reg.a = reg.a.wrapping_sub(1); flags.upd(reg.a, None); },
-        Opcode::DCR_B => { reg.b = reg.b.wrapping_sub(1); flags.upd(reg.b, None); },
-        Opcode::DCR_C => { reg.c = reg.c.wrapping_sub(1); flags.upd(reg.c, None); },
-        Opcode::DCR_D => { reg.d = reg.d.wrapping_sub(1); flags.upd(reg.d, None); },
-        Opcode::DCR_E => { reg.e = reg.e.wrapping_sub(1); flags.upd(reg.e, None); },
-        Opcode::DCR_H => { reg.h = reg.h.wrapping_sub(1); flags.upd(reg.h, None); },
-        Opcode::DCR_L => { reg.l = reg.l.wrapping_sub(1); flags.upd(reg.l, None); },
+        Opcode::DCR_A => cpu.reg.a = dcr(cpu.reg.a, &mut cpu.reg.flags),
+        Opcode::DCR_B => cpu.reg.b = dcr(cpu.reg.b, &mut cpu.reg.flags),
+        Opcode::DCR_C => cpu.reg.c = dcr(cpu.reg.c, &mut cpu.reg.flags),
+        Opcode::DCR_D => cpu.reg.d = dcr(cpu.reg.d, &mut cpu.reg.flags),
+        Opcode::DCR_E => cpu.reg.e = dcr(cpu.reg.e, &mut cpu.reg.flags),
+        Opcode::DCR_H => cpu.reg.h = dcr(cpu.reg.h, &mut cpu.reg.flags),
+        Opcode::DCR_L => cpu.reg.l = dcr(cpu.reg.l, &mut cpu.reg.flags),
 
         // DCR M (Decrement memory)
         //   ((H) (L)) ← ((H) (L)) - 1
@@ -542,59 +619,72 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 10
         // Addressing: register indirect
         // Flags: Z, S, P, AC
-        Opcode::DCR_M => { mem[reg.m()] = mem[reg.m()].wrapping_sub(1); flags.upd(mem[reg.m()], None); },
+        Opcode::DCR_M => {
+            let value = dcr(cpu.read(cpu.reg.m()), &mut cpu.reg.flags);
+            cpu.write(cpu.reg.m(), value);
+        },
 
         // INX rp (Increment register pair)
         //   (rh) (rl) ← (rh) (rl) + 1
         //   The content of the register pair rp is incremented by one.
-        //   Note: No condition flags are affected.
+        //   Note: No condition flags are affected. The 8085 sets its
+        //   underflow indicator if the register pair wraps around.
         //
         // Cycles: 1
         // States: 5
         // Addressing: register
         // Flags: none
         Opcode::INX_BC => {
-            let (c, cy) = reg.c.overflowing_add(1);
-            reg.b = reg.b.wrapping_add(1 + cy as u8);
-            reg.c = c;
+            let (value, wrapped) = cpu.get16(RegPair::BC).overflowing_add(1);
+            cpu.set16(RegPair::BC, value);
+            cpu.reg.flags.set_underflow(wrapped);
         },
         Opcode::INX_DE => {
-            let (e, cy) = reg.e.overflowing_add(1);
-            reg.d = reg.d.wrapping_add(1 + cy as u8);
-            reg.e = e;
+            let (value, wrapped) = cpu.get16(RegPair::DE).overflowing_add(1);
+            cpu.set16(RegPair::DE, value);
+            cpu.reg.flags.set_underflow(wrapped);
         },
         Opcode::INX_HL => {
-            let (l, cy) = reg.l.overflowing_add(1);
-            reg.h = reg.h.wrapping_add(1 + cy as u8);
-            reg.l = l;
+            let (value, wrapped) = cpu.get16(RegPair::HL).overflowing_add(1);
+            cpu.set16(RegPair::HL, value);
+            cpu.reg.flags.set_underflow(wrapped);
+        },
+        Opcode::INX_SP => {
+            let (value, wrapped) = cpu.get16(RegPair::SP).overflowing_add(1);
+            cpu.set16(RegPair::SP, value);
+            cpu.reg.flags.set_underflow(wrapped);
         },
-        Opcode::INX_SP => mem.pc = mem.pc.wrapping_add(1),
 
         // DCX rp (Decrement register pair)
         //   (rh) (rl) ← (rh) (rl) - 1
         //   The content of the register pair rp is decremented by one.
-        //   Note: No condition flags are affected.
+        //   Note: No condition flags are affected. The 8085 sets its
+        //   underflow indicator if the register pair wraps around.
         //
         // Cycles: 1
         // States: 5
         // Addressing: register
         // Flags: none
         Opcode::DCX_BC => {
-            let (c, cy) = reg.c.overflowing_sub(1);
-            reg.b = reg.b.wrapping_add(1 + cy as u8);
-            reg.c = c;
+            let (value, wrapped) = cpu.get16(RegPair::BC).overflowing_sub(1);
+            cpu.set16(RegPair::BC, value);
+            cpu.reg.flags.set_underflow(wrapped);
         },
         Opcode::DCX_DE => {
-            let (e, cy) = reg.e.overflowing_sub(1);
-            reg.d = reg.d.wrapping_add(1 + cy as u8);
-            reg.e = e;
+            let (value, wrapped) = cpu.get16(RegPair::DE).overflowing_sub(1);
+            cpu.set16(RegPair::DE, value);
+            cpu.reg.flags.set_underflow(wrapped);
         },
         Opcode::DCX_HL => {
-            let (l, cy) = reg.l.overflowing_sub(1);
-            reg.h = reg.h.wrapping_add(1 + cy as u8);
-            reg.l = l;
+            let (value, wrapped) = cpu.get16(RegPair::HL).overflowing_sub(1);
+            cpu.set16(RegPair::HL, value);
+            cpu.reg.flags.set_underflow(wrapped);
+        },
+        Opcode::DCX_SP => {
+            let (value, wrapped) = cpu.get16(RegPair::SP).overflowing_sub(1);
+            cpu.set16(RegPair::SP, value);
+            cpu.reg.flags.set_underflow(wrapped);
         },
-        Opcode::DCX_SP => mem.pc = mem.pc.wrapping_sub(1),
 
         // DAD rp (Add register pair to H and L)
         //   (H) (L) ← (H) (L) + (rh) (rl)
@@ -609,37 +699,24 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Addressing: register
         // Flags: CY
         Opcode::DAD_BC => {
-            let (l, cy) = reg.l.overflowing_add(reg.b);
-            let (h, cy1) = reg.h.overflowing_add(cy as u8);
-            let (h, cy2) = h.overflowing_add(reg.c);
-            flags.carry = cy1 | cy2;
-            reg.l = l;
-            reg.h = h;
+            let (hl, cy) = cpu.get16(RegPair::HL).overflowing_add(cpu.get16(RegPair::BC));
+            cpu.reg.flags.set_carry(cy);
+            cpu.set16(RegPair::HL, hl);
         },
         Opcode::DAD_DE => {
-            let (l, cy) = reg.l.overflowing_add(reg.d);
-            let (h, cy1) = reg.h.overflowing_add(cy as u8);
-            let (h, cy2) = h.overflowing_add(reg.e);
-            flags.carry = cy1 | cy2;
-            reg.l = l;
-            reg.h = h;
+            let (hl, cy) = cpu.get16(RegPair::HL).overflowing_add(cpu.get16(RegPair::DE));
+            cpu.reg.flags.set_carry(cy);
+            cpu.set16(RegPair::HL, hl);
         },
         Opcode::DAD_HL => {
-            let (l, cy) = reg.l.overflowing_add(reg.h);
-            let (h, cy1) = reg.h.overflowing_add(cy as u8);
-            let (h, cy2) = h.overflowing_add(reg.l);
-            flags.carry = cy1 | cy2;
-            reg.l = l;
-            reg.h = h;
+            let (hl, cy) = cpu.get16(RegPair::HL).overflowing_add(cpu.get16(RegPair::HL));
+            cpu.reg.flags.set_carry(cy);
+            cpu.set16(RegPair::HL, hl);
         },
         Opcode::DAD_SP => {
-            let (rh, rl) = mem.get_pc();
-            let (l, cy) = reg.l.overflowing_add(rh);
-            let (h, cy1) = reg.h.overflowing_add(cy as u8);
-            let (h, cy2) = h.overflowing_add(rl);
-            flags.carry = cy1 | cy2;
-            reg.l = l;
-            reg.h = h;
+            let (hl, cy) = cpu.get16(RegPair::HL).overflowing_add(cpu.get16(RegPair::SP));
+            cpu.reg.flags.set_carry(cy);
+            cpu.set16(RegPair::HL, hl);
         },
 
         // DAA (Decimal adjust accumulator)
@@ -656,13 +733,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 1
         // States: 4
         // Flags: Z, S, P, CY, AC
-        Opcode::DAA => todo!(),
+        Opcode::DAA => cpu.reg.a = daa(cpu.reg.a, &mut cpu.reg.flags),
 
         //
         // Logical group
         //
         // This group of instructions performs logical (Boolean) operations
-        // on data in registers and memory and on condition flags.
+        // on data in registers and memory and on condition cpu.reg.flags.
         // Unless indicated otherwise, all instructions in this group affect
         // the Zero, Sign, Parity, Auxiliary Carry, and Carry flags according
         // to the standard rules.
@@ -678,13 +755,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 4
         // Addressing: register
         // Flags: Z, S, P, CY, AC
-        Opcode::ANA_A => flags.upd(reg.a, Some(false)),
-        Opcode::ANA_B => reg.a = and(reg.a, reg.b, flags),
-        Opcode::ANA_C => reg.a = and(reg.a, reg.c, flags),
-        Opcode::ANA_D => reg.a = and(reg.a, reg.d, flags),
-        Opcode::ANA_E => reg.a = and(reg.a, reg.e, flags),
-        Opcode::ANA_H => reg.a = and(reg.a, reg.h, flags),
-        Opcode::ANA_L => reg.a = and(reg.a, reg.l, flags),
+        Opcode::ANA_A => cpu.reg.a = and(cpu.reg.a, cpu.reg.a, &mut cpu.reg.flags),
+        Opcode::ANA_B => cpu.reg.a = and(cpu.reg.a, cpu.reg.b, &mut cpu.reg.flags),
+        Opcode::ANA_C => cpu.reg.a = and(cpu.reg.a, cpu.reg.c, &mut cpu.reg.flags),
+        Opcode::ANA_D => cpu.reg.a = and(cpu.reg.a, cpu.reg.d, &mut cpu.reg.flags),
+        Opcode::ANA_E => cpu.reg.a = and(cpu.reg.a, cpu.reg.e, &mut cpu.reg.flags),
+        Opcode::ANA_H => cpu.reg.a = and(cpu.reg.a, cpu.reg.h, &mut cpu.reg.flags),
+        Opcode::ANA_L => cpu.reg.a = and(cpu.reg.a, cpu.reg.l, &mut cpu.reg.flags),
 
         // ANA M (AND memory)
         //   (A) ← (A) & ((H) (L))
@@ -697,7 +774,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register indirect
         // Flags: Z, S, P, CY, AC
-        Opcode::ANA_M => reg.a = and(reg.a, mem[reg.m()], flags),
+        Opcode::ANA_M => cpu.reg.a = and(cpu.reg.a, cpu.read(cpu.reg.m()), &mut cpu.reg.flags),
 
         // ANI data (AND immediate)
         //   (A) ← (A) & (byte 2)
@@ -709,7 +786,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: immediate
         // Flags: Z, S, P, CY, AC
-        Opcode::ANI(d8) => reg.a = and(reg.a, d8, flags),
+        Opcode::ANI(d8) => cpu.reg.a = and(cpu.reg.a, d8, &mut cpu.reg.flags),
 
         // XRA r (Exclusive OR register)
         //   (A) ← (A) ^ (r)
@@ -721,16 +798,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 4
         // Addressing: register
         // Flags: Z, S, P, CY, AC
-        Opcode::XRA_A => {
-            flags.upd(0, Some(false));
-            reg.a = 0;
-        },
-        Opcode::XRA_B => reg.a = xor(reg.a, reg.b, flags),
-        Opcode::XRA_C => reg.a = xor(reg.a, reg.c, flags),
-        Opcode::XRA_D => reg.a = xor(reg.a, reg.d, flags),
-        Opcode::XRA_E => reg.a = xor(reg.a, reg.e, flags),
-        Opcode::XRA_H => reg.a = xor(reg.a, reg.h, flags),
-        Opcode::XRA_L => reg.a = xor(reg.a, reg.l, flags),
+        Opcode::XRA_A => cpu.reg.a = xor(cpu.reg.a, cpu.reg.a, &mut cpu.reg.flags),
+        Opcode::XRA_B => cpu.reg.a = xor(cpu.reg.a, cpu.reg.b, &mut cpu.reg.flags),
+        Opcode::XRA_C => cpu.reg.a = xor(cpu.reg.a, cpu.reg.c, &mut cpu.reg.flags),
+        Opcode::XRA_D => cpu.reg.a = xor(cpu.reg.a, cpu.reg.d, &mut cpu.reg.flags),
+        Opcode::XRA_E => cpu.reg.a = xor(cpu.reg.a, cpu.reg.e, &mut cpu.reg.flags),
+        Opcode::XRA_H => cpu.reg.a = xor(cpu.reg.a, cpu.reg.h, &mut cpu.reg.flags),
+        Opcode::XRA_L => cpu.reg.a = xor(cpu.reg.a, cpu.reg.l, &mut cpu.reg.flags),
 
         // XRA M (Exclusive OR memory)
         //   (A) ← (A) ^ ((H) (L))
@@ -743,7 +817,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register indirect
         // Flags: Z, S, P, CY, AC
-        Opcode::XRA_M => reg.a = xor(reg.a, mem[reg.m()], flags),
+        Opcode::XRA_M => cpu.reg.a = xor(cpu.reg.a, cpu.read(cpu.reg.m()), &mut cpu.reg.flags),
 
         // XRI data (Exclusive OR immediate)
         //   (A) ← (A) ^ (byte 2)
@@ -756,7 +830,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: immediate
         // Flags: Z, S, P, CY, AC
-        Opcode::XRI(d8) => reg.a = xor(reg.a, d8, flags),
+        Opcode::XRI(d8) => cpu.reg.a = xor(cpu.reg.a, d8, &mut cpu.reg.flags),
 
         // ORA r (OR register)
         //   (A) ← (A) | (r)
@@ -768,13 +842,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 4
         // Addressing: register
         // Flags: Z, S, P, CY, AC
-        Opcode::ORA_A => flags.upd(0, Some(false)),
-        Opcode::ORA_B => reg.a = ior(reg.a, reg.b, flags),
-        Opcode::ORA_C => reg.a = ior(reg.a, reg.c, flags),
-        Opcode::ORA_D => reg.a = ior(reg.a, reg.d, flags),
-        Opcode::ORA_E => reg.a = ior(reg.a, reg.e, flags),
-        Opcode::ORA_H => reg.a = ior(reg.a, reg.h, flags),
-        Opcode::ORA_L => reg.a = ior(reg.a, reg.l, flags),
+        Opcode::ORA_A => cpu.reg.a = ior(cpu.reg.a, cpu.reg.a, &mut cpu.reg.flags),
+        Opcode::ORA_B => cpu.reg.a = ior(cpu.reg.a, cpu.reg.b, &mut cpu.reg.flags),
+        Opcode::ORA_C => cpu.reg.a = ior(cpu.reg.a, cpu.reg.c, &mut cpu.reg.flags),
+        Opcode::ORA_D => cpu.reg.a = ior(cpu.reg.a, cpu.reg.d, &mut cpu.reg.flags),
+        Opcode::ORA_E => cpu.reg.a = ior(cpu.reg.a, cpu.reg.e, &mut cpu.reg.flags),
+        Opcode::ORA_H => cpu.reg.a = ior(cpu.reg.a, cpu.reg.h, &mut cpu.reg.flags),
+        Opcode::ORA_L => cpu.reg.a = ior(cpu.reg.a, cpu.reg.l, &mut cpu.reg.flags),
 
         // ORA M (OR memory)
         //   (A) ← (A) | ((H) (L))
@@ -787,7 +861,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register indirect
         // Flags: Z, S, P, CY, AC
-        Opcode::ORA_M => reg.a = ior(reg.a, mem[reg.m()], flags),
+        Opcode::ORA_M => cpu.reg.a = ior(cpu.reg.a, cpu.read(cpu.reg.m()), &mut cpu.reg.flags),
 
         // ORI data (OR immediate)
         //   (A) ← (A) | (byte 2)
@@ -800,7 +874,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: immediate
         // Flags: Z, S, P, CY, AC
-        Opcode::ORI(d8) => reg.a = ior(reg.a, d8, flags),
+        Opcode::ORI(d8) => cpu.reg.a = ior(cpu.reg.a, d8, &mut cpu.reg.flags),
 
         // CMP r (Compare register)
         //   (A) - (r)
@@ -813,13 +887,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 4
         // Addressing: register
         // Flags: Z, S, P, CY, AC
-        Opcode::CMP_A => flags.upd(0, Some(false)),
-        Opcode::CMP_B => cmp(reg.a, reg.b, flags),
-        Opcode::CMP_C => cmp(reg.a, reg.c, flags),
-        Opcode::CMP_D => cmp(reg.a, reg.d, flags),
-        Opcode::CMP_E => cmp(reg.a, reg.e, flags),
-        Opcode::CMP_H => cmp(reg.a, reg.h, flags),
-        Opcode::CMP_L => cmp(reg.a, reg.l, flags),
+        Opcode::CMP_A => cmp(cpu.reg.a, cpu.reg.a, &mut cpu.reg.flags),
+        Opcode::CMP_B => cmp(cpu.reg.a, cpu.reg.b, &mut cpu.reg.flags),
+        Opcode::CMP_C => cmp(cpu.reg.a, cpu.reg.c, &mut cpu.reg.flags),
+        Opcode::CMP_D => cmp(cpu.reg.a, cpu.reg.d, &mut cpu.reg.flags),
+        Opcode::CMP_E => cmp(cpu.reg.a, cpu.reg.e, &mut cpu.reg.flags),
+        Opcode::CMP_H => cmp(cpu.reg.a, cpu.reg.h, &mut cpu.reg.flags),
+        Opcode::CMP_L => cmp(cpu.reg.a, cpu.reg.l, &mut cpu.reg.flags),
 
         // CMP M (Compare memory)
         //   (A) - ((H) (L))
@@ -834,7 +908,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 7
         // Addressing: register indirect
         // Flags: Z, S, P, CY, AC
-        Opcode::CMP_M => cmp(reg.a, mem[reg.m()], flags),
+        Opcode::CMP_M => cmp(cpu.reg.a, cpu.read(cpu.reg.m()), &mut cpu.reg.flags),
 
         // CPI data (Compare immediate)
         //   (A) - (byte 2)
@@ -842,7 +916,12 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         //   from the accumulator. The condition flags are set by the result
         //   of the subtraction. The Z flag is set to 1 if (A) = (byte 2).
         //   The CY flag is set to 1 if (A) < (byte 2).
-        Opcode::CPI(d8) => cmp(reg.a, d8, flags),
+        //
+        // Cycles: 2
+        // States: 7
+        // Addressing: immediate
+        // Flags: Z, S, P, CY, AC
+        Opcode::CPI(d8) => cmp(cpu.reg.a, d8, &mut cpu.reg.flags),
 
         // RLC (Rotate left)
         //   (A_{n+1}) ← (A_n)
@@ -858,8 +937,8 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Flags: CY
         Opcode::RLC => {
             const MSB: u8 = 0b1000_0000;
-            flags.carry = (reg.a & MSB) == MSB;
-            reg.a = (reg.a << 1) | ((reg.a & MSB) >> 7);
+            cpu.reg.flags.set_carry((cpu.reg.a & MSB) == MSB);
+            cpu.reg.a = (cpu.reg.a << 1) | ((cpu.reg.a & MSB) >> 7);
         },
 
         // RRC (Rotate right)
@@ -876,8 +955,8 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Flags: CY
         Opcode::RRC => {
             const LSB: u8 = 0b0000_0001;
-            flags.carry = (reg.a & LSB) == LSB;
-            reg.a = (reg.a >> 1) | ((reg.a & LSB) << 7);
+            cpu.reg.flags.set_carry((cpu.reg.a & LSB) == LSB);
+            cpu.reg.a = (cpu.reg.a >> 1) | ((cpu.reg.a & LSB) << 7);
         },
 
         // RAL (Rotate left through carry)
@@ -894,9 +973,9 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Flags: CY
         Opcode::RAL => {
             const MSB: u8 = 0b1000_0000;
-            let cy = flags.carry;
-            flags.carry = (reg.a & MSB) == MSB;
-            reg.a = (reg.a << 1) | (cy as u8);
+            let cy = cpu.reg.flags.carry();
+            cpu.reg.flags.set_carry((cpu.reg.a & MSB) == MSB);
+            cpu.reg.a = (cpu.reg.a << 1) | (cy as u8);
         },
 
         // RAR (Rotate right through carry)
@@ -913,9 +992,9 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Flags: CY
         Opcode::RAR => {
             const LSB: u8 = 0b0000_0001;
-            let cy = flags.carry;
-            flags.carry = (reg.a & LSB) == LSB;
-            reg.a = (reg.a >> 1) | ((cy as u8) << 7);
+            let cy = cpu.reg.flags.carry();
+            cpu.reg.flags.set_carry((cpu.reg.a & LSB) == LSB);
+            cpu.reg.a = (cpu.reg.a >> 1) | ((cy as u8) << 7);
         },
 
         // CMA (Complement accumulator)
@@ -926,7 +1005,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 1
         // States: 4
         // Flags: CY
-        Opcode::CMA => reg.a = !reg.a,
+        Opcode::CMA => cpu.reg.a = !cpu.reg.a,
 
         // CMC (Complement carry)
         //   (CY) ← !(CY)
@@ -935,7 +1014,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 1
         // States: 4
         // Flags: CY
-        Opcode::CMC => flags.carry = !flags.carry,
+        Opcode::CMC => cpu.reg.flags.set_carry(!cpu.reg.flags.carry()),
 
         // STC (Set carry)
         //   (CY) ← 1
@@ -944,7 +1023,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 1
         // States: 4
         // Flags: CY
-        Opcode::STC => flags.carry = true,
+        Opcode::STC => cpu.reg.flags.set_carry(true),
 
         //
         // Branch group
@@ -976,7 +1055,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 3
         // States: 10
         // Addressing: immediate
-        Opcode::JMP(adr) => mem.jump(adr, true),
+        Opcode::JMP(adr) => { cpu.jump(adr, true); },
 
         // JCOND addr (Conditional jump)
         //   If (COND)
@@ -988,14 +1067,14 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 3
         // States: 10
         // Addressing: immediate
-        Opcode::JNZ(adr) => mem.jump(adr, !flags.zero),
-        Opcode::JZ(adr)  => mem.jump(adr, flags.zero),
-        Opcode::JNC(adr) => mem.jump(adr, !flags.carry),
-        Opcode::JC(adr)  => mem.jump(adr, flags.carry),
-        Opcode::JPO(adr) => mem.jump(adr, !flags.parity),
-        Opcode::JPE(adr) => mem.jump(adr, flags.parity),
-        Opcode::JP(adr)  => mem.jump(adr, !flags.sign),
-        Opcode::JM(adr)  => mem.jump(adr, flags.sign),
+        Opcode::JNZ(adr) => taken = cpu.jump(adr, !cpu.reg.flags.zero()),
+        Opcode::JZ(adr)  => taken = cpu.jump(adr, cpu.reg.flags.zero()),
+        Opcode::JNC(adr) => taken = cpu.jump(adr, !cpu.reg.flags.carry()),
+        Opcode::JC(adr)  => taken = cpu.jump(adr, cpu.reg.flags.carry()),
+        Opcode::JPO(adr) => taken = cpu.jump(adr, !cpu.reg.flags.parity()),
+        Opcode::JPE(adr) => taken = cpu.jump(adr, cpu.reg.flags.parity()),
+        Opcode::JP(adr)  => taken = cpu.jump(adr, !cpu.reg.flags.sign()),
+        Opcode::JM(adr)  => taken = cpu.jump(adr, cpu.reg.flags.sign()),
 
         // CALL addr (Call)
         //   ((SP) - 1) ← (PCH)
@@ -1014,7 +1093,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 5
         // States: 17
         // Addressing: immediate/register indirect
-        Opcode::CALL(adr) => mem.call(adr, true),
+        Opcode::CALL(adr) => { cpu.call(adr, true); },
 
         // CCOND addr (Condition call)
         //   If (COND)
@@ -1029,14 +1108,14 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 3/5
         // States: 11/17
         // Addressing: immediate/register indirect
-        Opcode::CNZ(adr) => mem.call(adr, !flags.zero),
-        Opcode::CZ(adr)  => mem.call(adr, flags.zero),
-        Opcode::CNC(adr) => mem.call(adr, !flags.carry),
-        Opcode::CC(adr)  => mem.call(adr, flags.carry),
-        Opcode::CPO(adr) => mem.call(adr, !flags.parity),
-        Opcode::CPE(adr) => mem.call(adr, flags.parity),
-        Opcode::CP(adr)  => mem.call(adr, !flags.sign),
-        Opcode::CM(adr)  => mem.call(adr, flags.sign),
+        Opcode::CNZ(adr) => taken = cpu.call(adr, !cpu.reg.flags.zero()),
+        Opcode::CZ(adr)  => taken = cpu.call(adr, cpu.reg.flags.zero()),
+        Opcode::CNC(adr) => taken = cpu.call(adr, !cpu.reg.flags.carry()),
+        Opcode::CC(adr)  => taken = cpu.call(adr, cpu.reg.flags.carry()),
+        Opcode::CPO(adr) => taken = cpu.call(adr, !cpu.reg.flags.parity()),
+        Opcode::CPE(adr) => taken = cpu.call(adr, cpu.reg.flags.parity()),
+        Opcode::CP(adr)  => taken = cpu.call(adr, !cpu.reg.flags.sign()),
+        Opcode::CM(adr)  => taken = cpu.call(adr, cpu.reg.flags.sign()),
 
         // RET (Return)
         //   (PCl) ← ((SP));
@@ -1051,7 +1130,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 3
         // States: 10
         // Addressing: register indirect
-        Opcode::RET => mem.ret(true),
+        Opcode::RET => { cpu.ret(true); },
 
         // RCOND (Conditional return)
         //   If (COND)
@@ -1065,14 +1144,14 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 1/3
         // States: 5/11
         // Addressing: register indirect
-        Opcode::RNZ => mem.ret(!flags.zero),
-        Opcode::RZ  => mem.ret(flags.zero),
-        Opcode::RNC => mem.ret(!flags.carry),
-        Opcode::RC  => mem.ret(flags.carry),
-        Opcode::RPO => mem.ret(!flags.parity),
-        Opcode::RPE => mem.ret(flags.parity),
-        Opcode::RP  => mem.ret(!flags.sign),
-        Opcode::RM  => mem.ret(flags.sign),
+        Opcode::RNZ => taken = cpu.ret(!cpu.reg.flags.zero()),
+        Opcode::RZ  => taken = cpu.ret(cpu.reg.flags.zero()),
+        Opcode::RNC => taken = cpu.ret(!cpu.reg.flags.carry()),
+        Opcode::RC  => taken = cpu.ret(cpu.reg.flags.carry()),
+        Opcode::RPO => taken = cpu.ret(!cpu.reg.flags.parity()),
+        Opcode::RPE => taken = cpu.ret(cpu.reg.flags.parity()),
+        Opcode::RP  => taken = cpu.ret(!cpu.reg.flags.sign()),
+        Opcode::RM  => taken = cpu.ret(cpu.reg.flags.sign()),
 
         // RST n (Restart)
         //   ((SP) - 1) ← (PCH)
@@ -1094,14 +1173,14 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 3
         // States: 11
         // Addressing: register indirect
-        Opcode::RST_0 => todo!(),
-        Opcode::RST_1 => todo!(),
-        Opcode::RST_2 => todo!(),
-        Opcode::RST_3 => todo!(),
-        Opcode::RST_4 => todo!(),
-        Opcode::RST_5 => todo!(),
-        Opcode::RST_6 => todo!(),
-        Opcode::RST_7 => todo!(),
+        Opcode::RST_0 => { cpu.call(0x00, true); },
+        Opcode::RST_1 => { cpu.call(0x08, true); },
+        Opcode::RST_2 => { cpu.call(0x10, true); },
+        Opcode::RST_3 => { cpu.call(0x18, true); },
+        Opcode::RST_4 => { cpu.call(0x20, true); },
+        Opcode::RST_5 => { cpu.call(0x28, true); },
+        Opcode::RST_6 => { cpu.call(0x30, true); },
+        Opcode::RST_7 => { cpu.call(0x38, true); },
 
         // PCHL (Jump H and l indirect - move H and L to PC)
         //   (PCH) ← (H)
@@ -1113,13 +1192,13 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 1
         // States: 5
         // Addressing: register
-        Opcode::PCHL => mem.set_pc(reg.h, reg.l),
+        Opcode::PCHL => cpu.load_pc(cpu.reg.h, cpu.reg.l),
 
         //
         // Stack, I/O, and machine control group
         //
         // This group of instructions performs I/O, manipulates the Stack,
-        // and alters internal control flags. Unless otherwise specified,
+        // and alters internal control cpu.reg.flags. Unless otherwise specified,
         // condition flags are not affected by any instructions in this group.
         //
 
@@ -1139,9 +1218,9 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 11
         // Addressing: register indirect
         // Flags: none
-        Opcode::PUSH_BC => mem.push(reg.b, reg.c),
-        Opcode::PUSH_DE => mem.push(reg.d, reg.e),
-        Opcode::PUSH_HL => mem.push(reg.h, reg.l),
+        Opcode::PUSH_BC => cpu.push(cpu.reg.b, cpu.reg.c),
+        Opcode::PUSH_DE => cpu.push(cpu.reg.d, cpu.reg.e),
+        Opcode::PUSH_HL => cpu.push(cpu.reg.h, cpu.reg.l),
 
         // PUSH PSW (Push processor status word)
         //   ((SP) - 1) ← (A)
@@ -1165,19 +1244,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 11
         // Addressing: register indirect
         // Flags: none
-        Opcode::PUSH_PSW => {
-            let sp = mem.sp;
-            mem[sp - 1] = reg.a;
-
-            let mut psw = 0b0100_0000;
-            psw |= 0b1000_0000 * (flags.sign as u8);
-            psw |= 0b0100_0000 * (flags.zero as u8);
-            psw |= 0b0001_0000 * (flags.carry_aux as u8);
-            psw |= 0b0000_0100 * (flags.parity as u8);
-            psw |= 0b0000_0001 * (flags.carry as u8);
-            mem[sp - 2] = psw;
-            mem.sp -= 2;
-        },
+        Opcode::PUSH_PSW => cpu.push(cpu.reg.a, cpu.reg.flags.psw(cpu.variant)),
 
         // POP rp (Pop)
         //   (rl) ← ((SP))
@@ -1196,16 +1263,16 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Addressing: register indirect
         // Flags: none
         Opcode::POP_BC => {
-            reg.c = mem.read_sp8();
-            reg.b = mem.read_sp8();
+            cpu.reg.c = cpu.pop8();
+            cpu.reg.b = cpu.pop8();
         },
         Opcode::POP_DE => {
-            reg.e = mem.read_sp8();
-            reg.d = mem.read_sp8();
+            cpu.reg.e = cpu.pop8();
+            cpu.reg.d = cpu.pop8();
         },
         Opcode::POP_HL => {
-            reg.l = mem.read_sp8();
-            reg.h = mem.read_sp8();
+            cpu.reg.l = cpu.pop8();
+            cpu.reg.h = cpu.pop8();
         },
 
         // POP PSW (Pop processor status word)
@@ -1218,7 +1285,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         //   (SP) ← (SP) + 2
         //   The content of the memory location whose address is specified
         //   by the content of register SP is used to restore the condition
-        //   flags. The content of the memory location whose address is one
+        //   cpu.reg.flags. The content of the memory location whose address is one
         //   more than the content of register SP is moved to register A.
         //   The content of register SP is incremented by 2.
         //
@@ -1227,13 +1294,8 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Addressing: register indirect
         // Flags: Z, S, P, CY, AC
         Opcode::POP_PSW => {
-            let psw = mem.read_sp8();
-            flags.sign      = (0b1000_0000 & psw) > 0;
-            flags.zero      = (0b0100_0000 & psw) > 0;
-            flags.carry_aux = (0b0001_0000 & psw) > 0;
-            flags.parity    = (0b0000_0100 & psw) > 0;
-            flags.carry     = (0b0000_0001 & psw) > 0;
-            reg.a = mem.read_sp8();
+            cpu.reg.flags = Flags::from_psw(cpu.variant, cpu.pop8());
+            cpu.reg.a = cpu.pop8();
         },
 
         // XTHL (Exchange stack top with H and L)
@@ -1249,7 +1311,14 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 18
         // Addressing: register indirect
         // Flags: none
-        Opcode::XTHL => todo!(),
+        Opcode::XTHL => {
+            let l = cpu.read(cpu.reg.sp);
+            let h = cpu.read(cpu.reg.sp.wrapping_add(1));
+            cpu.write(cpu.reg.sp, cpu.reg.l);
+            cpu.write(cpu.reg.sp.wrapping_add(1), cpu.reg.h);
+            cpu.reg.l = l;
+            cpu.reg.h = h;
+        },
 
         // SPHL (Move HL to SP)
         //   (SP) ← (H) (L)
@@ -1259,7 +1328,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 5
         // Addressing: register
         // Flags: none
-        Opcode::SPHL => todo!(),
+        Opcode::SPHL => cpu.reg.sp = cpu.reg.m(),
 
         // IN port (Input)
         //   (A) ← (data)
@@ -1270,7 +1339,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 10
         // Addressing: direct
         // Flags: none
-        Opcode::IN(d8) => println!("IN {}", d8),
+        Opcode::IN(d8) => cpu.reg.a = cpu.input(d8),
 
         // OUT port (Output)
         //   (data) ← (A)
@@ -1281,7 +1350,7 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 10
         // Addressing: direct
         // Flags: none
-        Opcode::OUT(d8) => println!("OUT {}", d8),
+        Opcode::OUT(d8) => cpu.output(d8, cpu.reg.a),
 
         // EI (Enable interrupts)
         //   The interrupt system is enabled following the
@@ -1290,7 +1359,10 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 1
         // States: 4
         // Flags: none
-        Opcode::EI => flags.interrupt_enabled = true,
+        Opcode::EI => {
+            cpu.inte = true;
+            cpu.ei_delay = true;
+        },
 
         // DI (Disable interrupts)
         //   The interrupt system is disabled immediately
@@ -1299,15 +1371,16 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // Cycles: 1
         // States: 4
         // Flags: none
-        Opcode::DI => flags.interrupt_enabled = false,
+        Opcode::DI => cpu.inte = false,
 
         // HLT (Halt)
         //   The processor is stopped. The registers and flags are unaffected.
+        //   Only an interrupt or a reset can get the processor running again.
         //
         // Cycles: 1
         // States: 7
         // Flags: none
-        Opcode::HLT => std::process::exit(0),
+        Opcode::HLT => cpu.halted = true,
 
         // NOP (No op)
         //   No operation is performed. The registers and flags are unaffected.
@@ -1316,5 +1389,425 @@ pub fn emulate(reg: &mut Registers, flags: &mut Flags, mem: &mut Memory) {
         // States: 4
         // Flags: none
         Opcode::NOP => {},
+
+        // Undocumented opcodes
+        //   The 8080 does not fully decode these bytes, so each of them
+        //   behaves like the documented instruction it aliases.
+        Opcode::NOP_08
+        | Opcode::NOP_10
+        | Opcode::NOP_18
+        | Opcode::NOP_20
+        | Opcode::NOP_28
+        | Opcode::NOP_30
+        | Opcode::NOP_38 => {},
+        Opcode::JMP_CB(adr) => { cpu.jump(adr, true); },
+        Opcode::RET_D9 => { cpu.ret(true); },
+        Opcode::CALL_DD(adr) | Opcode::CALL_ED(adr) | Opcode::CALL_FD(adr) => { cpu.call(adr, true); },
+
+        //
+        // Intel 8085 group
+        //
+        // These instructions only exist on the 8085. All but RIM and SIM
+        // were left out of its documentation.
+        //
+
+        // RIM (Read interrupt masks)
+        //   (A) ← (SID) (I7.5) (I6.5) (I5.5) (IE) (M7.5) (M6.5) (M5.5)
+        //   The serial input data, the pending interrupts, the interrupt
+        //   enable status and the interrupt masks are moved to register A.
+        //
+        // Cycles: 1
+        // States: 4
+        // Flags: none
+        Opcode::RIM => cpu.reg.a = cpu.i8085.rim(cpu.inte),
+
+        // SIM (Set interrupt masks)
+        //   The content of register A sets the interrupt masks, resets the
+        //   RST 7.5 latch and sets the serial output data, each depending on
+        //   an enable bit in register A.
+        //
+        // Cycles: 1
+        // States: 4
+        // Flags: none
+        Opcode::SIM => cpu.i8085.sim(cpu.reg.a),
+
+        // DSUB (Double subtract)
+        //   (H) (L) ← (H) (L) - (B) (C)
+        //   The content of register pair B and C is subtracted
+        //   from the content of register pair H and L.
+        //
+        // Cycles: 3
+        // States: 10
+        // Flags: Z,S,P,CY,AC,V
+        Opcode::DSUB => {
+            let hl = cpu.reg.m();
+            let bc = cpu.reg.bc();
+            let (res, cy) = hl.overflowing_sub(bc);
+            cpu.reg.h = (res >> 8) as u8;
+            cpu.reg.l = res as u8;
+            cpu.reg.flags.upd(cpu.reg.h, Some(cy));
+            // The auxiliary carry and overflow come from the high-order
+            // bytes, which are subtracted with the borrow of the low-order ones.
+            cpu.reg.flags.upd_op(Op::Sub, (hl >> 8) as u8, (bc >> 8) as u8, cpu.reg.h);
+            cpu.reg.flags.set_zero(res == 0);
+        },
+
+        // ARHL (Arithmetic shift right H and L)
+        //   (H7) ← (H7); (Hn-1) ← (Hn); (L7) ← (H0); (Ln-1) ← (Ln); (CY) ← (L0)
+        //   The content of register pair H and L is shifted right one
+        //   position, keeping the high-order bit unchanged.
+        //
+        // Cycles: 2
+        // States: 7
+        // Flags: CY
+        Opcode::ARHL => {
+            let hl = cpu.reg.m();
+            let res = ((hl as i16) >> 1) as u16;
+            cpu.reg.h = (res >> 8) as u8;
+            cpu.reg.l = res as u8;
+            cpu.reg.flags.set_carry(hl & 1 != 0);
+        },
+
+        // RDEL (Rotate D and E left through carry)
+        //   (Dn+1) ← (Dn); (D0) ← (E7); (En+1) ← (En); (E0) ← (CY); (CY) ← (D7)
+        //   The content of register pair D and E is rotated left one
+        //   position through the carry flag.
+        //
+        // Cycles: 3
+        // States: 10
+        // Flags: CY,V
+        Opcode::RDEL => {
+            let de = cpu.reg.de();
+            let res = (de << 1) | cpu.reg.flags.carry() as u16;
+            cpu.reg.d = (res >> 8) as u8;
+            cpu.reg.e = res as u8;
+            cpu.reg.flags.set_carry(de & 0x8000 != 0);
+            cpu.reg.flags.set_overflow((de ^ res) & 0x8000 != 0);
+        },
+
+        // LDHI data (Load D and E with H and L plus immediate)
+        //   (D) (E) ← (H) (L) + (byte 2)
+        //
+        // Cycles: 3
+        // States: 10
+        // Addressing: immediate
+        // Flags: none
+        Opcode::LDHI(d8) => {
+            let res = cpu.reg.m().wrapping_add(d8 as u16);
+            cpu.reg.d = (res >> 8) as u8;
+            cpu.reg.e = res as u8;
+        },
+
+        // LDSI data (Load D and E with SP plus immediate)
+        //   (D) (E) ← (SP) + (byte 2)
+        //
+        // Cycles: 3
+        // States: 10
+        // Addressing: immediate
+        // Flags: none
+        Opcode::LDSI(d8) => {
+            let res = cpu.reg.sp.wrapping_add(d8 as u16);
+            cpu.reg.d = (res >> 8) as u8;
+            cpu.reg.e = res as u8;
+        },
+
+        // RSTV (Restart on overflow)
+        //   If (V)
+        //     ((SP) - 1) ← (PCH); ((SP) - 2) ← (PCL); (SP) ← (SP) - 2; (PC) ← 0x40
+        //
+        // Cycles: 1/3
+        // States: 6/12
+        // Flags: none
+        Opcode::RSTV => taken = cpu.call(0x40, cpu.reg.flags.overflow()),
+
+        // SHLX (Store H and L indirect through D and E)
+        //   ((D) (E)) ← (L); ((D) (E) + 1) ← (H)
+        //
+        // Cycles: 3
+        // States: 10
+        // Addressing: register indirect
+        // Flags: none
+        Opcode::SHLX => {
+            let adr = cpu.reg.de();
+            cpu.write(adr, cpu.reg.l);
+            cpu.write(adr.wrapping_add(1), cpu.reg.h);
+        },
+
+        // LHLX (Load H and L indirect through D and E)
+        //   (L) ← ((D) (E)); (H) ← ((D) (E) + 1)
+        //
+        // Cycles: 3
+        // States: 10
+        // Addressing: register indirect
+        // Flags: none
+        Opcode::LHLX => {
+            let adr = cpu.reg.de();
+            cpu.reg.l = cpu.read(adr);
+            cpu.reg.h = cpu.read(adr.wrapping_add(1));
+        },
+
+        // JNK/JK addr (Jump on underflow indicator)
+        //   If (K) is reset/set
+        //     (PC) ← (byte 3) (byte 2)
+        //
+        // Cycles: 2/3
+        // States: 7/10
+        // Addressing: immediate
+        // Flags: none
+        Opcode::JNK(adr) => taken = cpu.jump(adr, !cpu.reg.flags.underflow()),
+        Opcode::JK(adr)  => taken = cpu.jump(adr, cpu.reg.flags.underflow()),
+
+        // The Z80 instructions are executed by their own module.
+        Opcode::Z80(_) => return None,
+    }
+
+    // The 8085 always sets the auxiliary carry after a logical AND.
+    if cpu.variant == Variant::I8085 {
+        if let Opcode::ANA_B | Opcode::ANA_C | Opcode::ANA_D | Opcode::ANA_E | Opcode::ANA_H
+            | Opcode::ANA_L | Opcode::ANA_M | Opcode::ANA_A | Opcode::ANI(_) = opcode
+        {
+            cpu.reg.flags.set_carry_aux(true);
+        }
+    }
+
+    if taken {
+        Some(opcode.states_taken(cpu.variant))
+    } else {
+        Some(opcode.states(cpu.variant))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    /// Runs an instruction with an immediate operand on the accumulator
+    /// and returns the accumulator and flags it leaves.
+    fn alu(opcode: u8, a: u8, value: u8, carry: bool) -> (u8, Flags) {
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(&[opcode, value][..])).unwrap());
+        cpu.reg_mut().a = a;
+        cpu.flags_mut().set_carry(carry);
+        cpu.step().unwrap();
+        (cpu.reg().a, *cpu.flags())
+    }
+
+    /// Computes the result and the zero, sign, parity, carry, auxiliary carry
+    /// and overflow flags of an ALU operation eagerly, as the 8080 defines them.
+    /// The operations are numbered ADD, ADC, SUB, SBB, ANA, XRA, ORA, CMP, INR, DCR.
+    fn eager(op: u8, a: u8, value: u8, cy: bool) -> (u8, [bool; 6]) {
+        let (res, carry, carry_aux, signed) = match op {
+            0 | 1 => {
+                let cy = op == 1 && cy;
+                let sum = a as u16 + value as u16 + cy as u16;
+                let carry_aux = (a & 0x0f) + (value & 0x0f) + cy as u8 > 0x0f;
+                (sum as u8, sum > 0xff, carry_aux, Some(a as i8 as i16 + value as i8 as i16 + cy as i16))
+            },
+            2 | 3 | 7 => {
+                let cy = op == 3 && cy;
+                let diff = a as i16 - value as i16 - cy as i16;
+                // The carry out of bit 3 when adding the two's complement.
+                let carry_aux = (a & 0x0f) + (!value & 0x0f) + !cy as u8 > 0x0f;
+                (diff as u8, diff < 0, carry_aux, Some(a as i8 as i16 - value as i8 as i16 - cy as i16))
+            },
+            4 => (a & value, false, (a | value) & 0x08 != 0, None),
+            5 => (a ^ value, false, false, None),
+            6 => (a | value, false, false, None),
+            8 => (a.wrapping_add(1), cy, a & 0x0f == 0x0f, Some(a as i8 as i16 + 1)),
+            _ => (a.wrapping_sub(1), cy, a & 0x0f != 0, Some(a as i8 as i16 - 1)),
+        };
+        // The logical operations keep the overflow left pending before them.
+        let overflow = signed.is_none_or(|signed| !(-128..=127).contains(&signed));
+        let flags = [res == 0, res & 0x80 != 0, res.count_ones() % 2 == 0, carry, carry_aux, overflow];
+        (if op == 7 { a } else { res }, flags)
+    }
+
+    #[test]
+    fn lazy_flags_match_eager_evaluation_for_every_alu_operation() {
+        for opcode in 0..10 {
+            for a in 0..=0xff {
+                for value in 0..=0xff {
+                    for cy in [false, true] {
+                        // Leave an overflow pending, which the logical operations keep.
+                        let mut flags = Flags::default();
+                        sub(0x80, 0x01, &mut flags);
+                        flags.set_carry(cy);
+
+                        let res = match opcode {
+                            0 => add(a, value, &mut flags),
+                            1 => adc(a, value, &mut flags),
+                            2 => sub(a, value, &mut flags),
+                            3 => sbb(a, value, &mut flags),
+                            4 => and(a, value, &mut flags),
+                            5 => xor(a, value, &mut flags),
+                            6 => ior(a, value, &mut flags),
+                            7 => {
+                                cmp(a, value, &mut flags);
+                                a
+                            },
+                            8 => inr(a, &mut flags),
+                            _ => dcr(a, &mut flags),
+                        };
+                        let lazy = [
+                            flags.zero(),
+                            flags.sign(),
+                            flags.parity(),
+                            flags.carry(),
+                            flags.carry_aux(),
+                            flags.overflow(),
+                        ];
+                        let expected = eager(opcode, a, value, cy);
+                        assert_eq!((res, lazy), expected, "{} {:#04x} {:#04x} {}", opcode, a, value, cy);
+
+                        // Settling the pending flags keeps their values.
+                        let settled = flags;
+                        flags.set_zero(flags.zero());
+                        flags.set_carry_aux(flags.carry_aux());
+                        assert_eq!(flags, settled);
+                        assert_eq!(u8::from(flags), u8::from(settled));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn addition_sets_carry_and_auxiliary_carry() {
+        // ADI, ACI
+        let (a, f) = alu(0xc6, 0x3a, 0xc6, false);
+        assert_eq!(a, 0x00);
+        assert!(f.carry() && f.carry_aux() && f.zero() && f.parity() && !f.sign());
+
+        let (a, f) = alu(0xc6, 0x0f, 0x01, true);
+        assert_eq!(a, 0x10);
+        assert!(!f.carry() && f.carry_aux() && !f.zero() && !f.parity());
+
+        let (a, f) = alu(0xce, 0xff, 0x00, true);
+        assert_eq!(a, 0x00);
+        assert!(f.carry() && f.carry_aux() && f.zero());
+
+        let (a, f) = alu(0xce, 0x70, 0x0f, false);
+        assert_eq!(a, 0x7f);
+        assert!(!f.carry() && !f.carry_aux() && !f.sign());
+    }
+
+    #[test]
+    fn subtraction_sets_auxiliary_carry_from_the_twos_complement() {
+        // SUI, SBI, CPI
+        let (a, f) = alu(0xd6, 0x3e, 0x3e, false);
+        assert_eq!(a, 0x00);
+        assert!(!f.carry() && f.carry_aux() && f.zero());
+
+        let (a, f) = alu(0xd6, 0x00, 0x01, false);
+        assert_eq!(a, 0xff);
+        assert!(f.carry() && !f.carry_aux() && f.sign() && f.parity());
+
+        let (a, f) = alu(0xde, 0x10, 0x00, true);
+        assert_eq!(a, 0x0f);
+        assert!(!f.carry() && !f.carry_aux());
+
+        let (a, f) = alu(0xde, 0x00, 0xff, true);
+        assert_eq!(a, 0x00);
+        assert!(f.carry() && !f.carry_aux() && f.zero());
+
+        let (a, f) = alu(0xfe, 0x4a, 0x40, true);
+        assert_eq!(a, 0x4a);
+        assert!(!f.carry() && f.carry_aux() && !f.zero());
+
+        let (a, f) = alu(0xfe, 0x4a, 0x4b, false);
+        assert_eq!(a, 0x4a);
+        assert!(f.carry() && !f.carry_aux() && f.sign());
+    }
+
+    #[test]
+    fn increment_and_decrement_keep_the_carry() {
+        // INR A, DCR A
+        let (a, f) = alu(0x3c, 0x0f, 0, true);
+        assert_eq!(a, 0x10);
+        assert!(f.carry() && f.carry_aux());
+
+        let (a, f) = alu(0x3c, 0xff, 0, false);
+        assert_eq!(a, 0x00);
+        assert!(!f.carry() && f.carry_aux() && f.zero());
+
+        let (a, f) = alu(0x3d, 0x10, 0, true);
+        assert_eq!(a, 0x0f);
+        assert!(f.carry() && !f.carry_aux());
+
+        let (a, f) = alu(0x3d, 0x01, 0, false);
+        assert_eq!(a, 0x00);
+        assert!(!f.carry() && f.carry_aux() && f.zero());
+    }
+
+    #[test]
+    fn logical_operations_clear_the_carry() {
+        // ANI sets AC to the OR of bit 3 of its operands, XRI and ORI clear it.
+        let (a, f) = alu(0xe6, 0x08, 0x00, true);
+        assert_eq!(a, 0x00);
+        assert!(!f.carry() && f.carry_aux() && f.zero());
+
+        let (a, f) = alu(0xe6, 0x01, 0x03, true);
+        assert_eq!(a, 0x01);
+        assert!(!f.carry() && !f.carry_aux());
+
+        for opcode in [0xee, 0xf6] {
+            let (a, f) = alu(opcode, 0x0f, 0x80, true);
+            assert_eq!(a, 0x8f);
+            assert!(!f.carry() && !f.carry_aux() && f.sign() && !f.parity());
+        }
+    }
+
+    #[test]
+    fn daa_corrects_bcd_results() {
+        // ADI 1; DAA
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(&[0xc6, 0x01, 0x27][..])).unwrap());
+        cpu.reg_mut().a = 0x99;
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().a, 0x9a);
+        assert!(!cpu.flags().carry() && !cpu.flags().carry_aux());
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().a, 0x00);
+        assert!(cpu.flags().carry() && cpu.flags().carry_aux() && cpu.flags().zero());
+
+        // DAA after 0x09 + 0x08 and 0x15 + 0x27.
+        let (a, f) = alu(0x27, 0x11, 0, false);
+        assert_eq!(a, 0x11);
+        assert!(!f.carry());
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(&[0x27][..])).unwrap());
+        cpu.reg_mut().a = 0x11;
+        cpu.flags_mut().set_carry_aux(true);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().a, 0x17);
+        let (a, f) = alu(0x27, 0x3c, 0, false);
+        assert_eq!(a, 0x42);
+        assert!(!f.carry() && f.carry_aux());
+
+        // The carry is kept when set, even if no correction of the high digit is needed.
+        let (a, f) = alu(0x27, 0x12, 0, true);
+        assert_eq!(a, 0x72);
+        assert!(f.carry());
+    }
+
+    #[test]
+    fn push_psw_fixes_bits_1_3_and_5() {
+        // LXI SP,0100H; POP PSW; PUSH PSW
+        let mut program = vec![0x31, 0x00, 0x01, 0xf1, 0xf5];
+        program.resize(0x100, 0);
+        program.extend([0xff, 0x42]);
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(&program[..])).unwrap());
+        for _ in 0..2 {
+            cpu.step().unwrap();
+        }
+        let f = *cpu.flags();
+        assert!(f.sign() && f.zero() && f.carry_aux() && f.parity() && f.carry());
+        assert_eq!(cpu.reg().a, 0x42);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.mem()[0x0100], 0b1101_0111);
+        assert_eq!(cpu.mem()[0x0101], 0x42);
+
+        assert_eq!(u8::from(Flags::default()), 0b0000_0010);
+        assert_eq!(Flags::from(0b0010_1010), Flags::default());
     }
 }
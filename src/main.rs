@@ -1,17 +1,114 @@
-use std::{fs::File, io::BufReader};
+mod gzip;
 
-use intel8080::{emulate, Flags, Memory, Registers};
+use std::any::Any;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+
+use intel8080::{Access, Cpu, Memory, Observer, Ports, StepError, TextTrace, TraceWriter, UnmappedPort};
+
+type Output = BufWriter<Box<dyn Write>>;
+
+/// Parses an address written in hexadecimal, with or without a `0x` prefix.
+fn address(arg: Option<String>) -> u16 {
+    let arg = arg.unwrap_or_default();
+    let digits = arg.strip_prefix("0x").unwrap_or(&arg);
+    u16::from_str_radix(digits, 16).unwrap_or_else(|_| {
+        eprintln!("invalid address: {:?}", arg);
+        std::process::exit(2);
+    })
+}
+
+/// Prints the accesses to unmapped ports made since the last call.
+fn log_unmapped(io: &mut Ports) {
+    for (access, value) in io.take_unmapped() {
+        match access {
+            Access::In(port) => eprintln!("IN {:#04x}: unmapped port", port),
+            Access::Out(port) => eprintln!("OUT {:#04x}: unmapped port, value {:#04x}", port, value),
+            _ => {},
+        }
+    }
+}
+
+/// Flushes the trace, returning the first error met while writing it.
+fn finish(observer: Box<dyn Observer>) -> std::io::Result<()> {
+    let observer: Box<dyn Any> = observer;
+    let observer = match observer.downcast::<TextTrace<Output>>() {
+        Ok(trace) => return trace.finish().map(drop),
+        Err(observer) => observer,
+    };
+    match observer.downcast::<TraceWriter<Output>>() {
+        Ok(trace) => trace.finish().map(drop),
+        Err(_) => Ok(()),
+    }
+}
 
 fn main() -> std::io::Result<()> {
-    let args: Vec<String> = std::env::args().collect();
-    let file = File::open(&args[1])?;
+    let mut path = String::new();
+    let mut trace = None;
+    let mut record = None;
+    let mut from = 0;
+    let mut to = 0xffff;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => trace = args.next(),
+            "--trace-from" => from = address(args.next()),
+            "--trace-to" => to = address(args.next()),
+            "--record" => record = args.next(),
+            _ => path = arg,
+        }
+    }
+
+    if trace.is_some() && record.is_some() {
+        eprintln!("--trace and --record cannot be used together");
+        std::process::exit(2);
+    }
+
+    let file = File::open(&path)?;
     let mut reader = BufReader::new(file);
-    let mut mem = Memory::from_reader(&mut reader)?;
+    let mem = Memory::from_reader(&mut reader)?;
 
-    let mut reg = Registers::default();
-    let mut flags = Flags::default();
+    let io = Ports::new(UnmappedPort::Log(0xff));
+    let mut cpu = Cpu::with_io(mem, io);
+
+    let mut gzip = None;
+    if let Some(trace) = trace {
+        let (out, child) = gzip::create(&trace)?;
+        let mut trace = TextTrace::new(BufWriter::new(out), cpu.variant());
+        trace.set_range(from..=to);
+        cpu.set_observer(trace);
+        gzip = child;
+    }
+    if let Some(record) = record {
+        let (out, child) = gzip::create(&record)?;
+        cpu.set_observer(TraceWriter::new(BufWriter::new(out), cpu.variant())?);
+        gzip = child;
+    }
 
-    loop {
-        emulate(&mut reg, &mut flags, &mut mem);
+    let result = loop {
+        let step = cpu.step();
+        log_unmapped(cpu.io_mut());
+        match step {
+            Ok(_) => {},
+            Err(StepError::Halted { .. }) => break Ok(()),
+            Err(err) => break Err(err),
+        }
+    };
+
+    // Flush the trace and let gzip finish writing it.
+    let mut traced = cpu.take_observer().map_or(Ok(()), finish);
+    if let Some(gzip) = gzip {
+        traced = traced.and(gzip::wait(gzip));
+    }
+
+    if let Err(err) = &traced {
+        eprintln!("cannot write the trace: {}", err);
+    }
+    if let Err(err) = &result {
+        eprintln!("{}", err);
+    }
+    if traced.is_err() || result.is_err() {
+        std::process::exit(1);
     }
+    Ok(())
 }
@@ -0,0 +1,958 @@
+//! Static facts about every instruction, indexed by opcode byte.
+
+use crate::{Flags, Variant};
+
+/// The instruction groups of the Intel 8080 manual,
+/// with the last one split into its three parts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Group {
+    DataTransfer,
+    Arithmetic,
+    Logical,
+    Branch,
+    Stack,
+    Io,
+    Control,
+}
+
+/// Bus accesses an instruction performs besides fetching its own bytes.
+/// Conditional calls and returns only access the stack when taken.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Accesses {
+    /// Reads from memory.
+    pub read: bool,
+    /// Writes to memory.
+    pub write: bool,
+    /// Pops from the stack.
+    pub pop: bool,
+    /// Pushes onto the stack.
+    pub push: bool,
+    /// Reads from an I/O port.
+    pub input: bool,
+    /// Writes to an I/O port.
+    pub output: bool,
+}
+
+/// What is known about an instruction without executing it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    /// Intel mnemonic, such as `MOV`.
+    pub mnemonic: &'static str,
+    /// Operands in Intel syntax, with `d8`, `d16` and `a16`
+    /// in place of immediate data and addresses.
+    pub operands: &'static str,
+    /// Length in bytes, including the opcode.
+    pub len: u8,
+    /// Number of states, when the condition is not met.
+    pub states: u8,
+    /// Number of states when the condition is met.
+    /// Equal to `states` for unconditional instructions.
+    pub states_taken: u8,
+    /// The flags the instruction writes, as bits of the
+    /// processor status word, see [`Flags::SIGN`] and the others.
+    pub flags: u8,
+    pub access: Accesses,
+    pub group: Group,
+    /// Whether the opcode is undocumented on this processor.
+    pub undocumented: bool,
+}
+
+/// Returns the facts about the instruction with the given opcode
+/// byte, or `None` if the byte starts a Z80 only instruction,
+/// which is described by [`crate::z80::Opcode`] instead.
+pub fn metadata(variant: Variant, byte: u8) -> Option<&'static Metadata> {
+    let table = match variant {
+        Variant::I8080 => &I8080,
+        Variant::I8085 => &I8085,
+        Variant::Z80 => &Z80,
+    };
+    table[byte as usize].as_ref()
+}
+
+const S: u8 = Flags::SIGN;
+const Z: u8 = Flags::ZERO;
+const K: u8 = Flags::UNDERFLOW;
+const AC: u8 = Flags::CARRY_AUX;
+const P: u8 = Flags::PARITY;
+const V: u8 = Flags::OVERFLOW;
+const N: u8 = Flags::SUBTRACT;
+const CY: u8 = Flags::CARRY;
+
+const NONE: Accesses = Accesses { read: false, write: false, pop: false, push: false, input: false, output: false };
+const READ: Accesses = Accesses { read: true, ..NONE };
+const WRITE: Accesses = Accesses { write: true, ..NONE };
+const READ_WRITE: Accesses = Accesses { read: true, write: true, ..NONE };
+const POP: Accesses = Accesses { pop: true, ..NONE };
+const PUSH: Accesses = Accesses { push: true, ..NONE };
+const EXCHANGE: Accesses = Accesses { pop: true, push: true, ..NONE };
+const IN: Accesses = Accesses { input: true, ..NONE };
+const OUT: Accesses = Accesses { output: true, ..NONE };
+
+use Group::*;
+
+#[allow(clippy::too_many_arguments)]
+const fn op(
+    mnemonic: &'static str,
+    operands: &'static str,
+    len: u8,
+    states: u8,
+    states_taken: u8,
+    flags: u8,
+    access: Accesses,
+    group: Group,
+) -> Metadata {
+    Metadata { mnemonic, operands, len, states, states_taken, flags, access, group, undocumented: false }
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn alias(
+    mnemonic: &'static str,
+    operands: &'static str,
+    len: u8,
+    states: u8,
+    states_taken: u8,
+    flags: u8,
+    access: Accesses,
+    group: Group,
+) -> Metadata {
+    Metadata { undocumented: true, ..op(mnemonic, operands, len, states, states_taken, flags, access, group) }
+}
+
+static I8080: [Option<Metadata>; 256] = [
+    /* 0x00 */ Some(op("NOP", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x01 */ Some(op("LXI", "B,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x02 */ Some(op("STAX", "B", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x03 */ Some(op("INX", "B", 1, 5, 5, 0, NONE, Arithmetic)),
+    /* 0x04 */ Some(op("INR", "B", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x05 */ Some(op("DCR", "B", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x06 */ Some(op("MVI", "B,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x07 */ Some(op("RLC", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x08 */ Some(alias("NOP", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x09 */ Some(op("DAD", "B", 1, 10, 10, CY, NONE, Arithmetic)),
+    /* 0x0a */ Some(op("LDAX", "B", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x0b */ Some(op("DCX", "B", 1, 5, 5, 0, NONE, Arithmetic)),
+    /* 0x0c */ Some(op("INR", "C", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x0d */ Some(op("DCR", "C", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x0e */ Some(op("MVI", "C,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x0f */ Some(op("RRC", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x10 */ Some(alias("NOP", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x11 */ Some(op("LXI", "D,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x12 */ Some(op("STAX", "D", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x13 */ Some(op("INX", "D", 1, 5, 5, 0, NONE, Arithmetic)),
+    /* 0x14 */ Some(op("INR", "D", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x15 */ Some(op("DCR", "D", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x16 */ Some(op("MVI", "D,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x17 */ Some(op("RAL", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x18 */ Some(alias("NOP", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x19 */ Some(op("DAD", "D", 1, 10, 10, CY, NONE, Arithmetic)),
+    /* 0x1a */ Some(op("LDAX", "D", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x1b */ Some(op("DCX", "D", 1, 5, 5, 0, NONE, Arithmetic)),
+    /* 0x1c */ Some(op("INR", "E", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x1d */ Some(op("DCR", "E", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x1e */ Some(op("MVI", "E,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x1f */ Some(op("RAR", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x20 */ Some(alias("NOP", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x21 */ Some(op("LXI", "H,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x22 */ Some(op("SHLD", "a16", 3, 16, 16, 0, WRITE, DataTransfer)),
+    /* 0x23 */ Some(op("INX", "H", 1, 5, 5, 0, NONE, Arithmetic)),
+    /* 0x24 */ Some(op("INR", "H", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x25 */ Some(op("DCR", "H", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x26 */ Some(op("MVI", "H,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x27 */ Some(op("DAA", "", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x28 */ Some(alias("NOP", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x29 */ Some(op("DAD", "H", 1, 10, 10, CY, NONE, Arithmetic)),
+    /* 0x2a */ Some(op("LHLD", "a16", 3, 16, 16, 0, READ, DataTransfer)),
+    /* 0x2b */ Some(op("DCX", "H", 1, 5, 5, 0, NONE, Arithmetic)),
+    /* 0x2c */ Some(op("INR", "L", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x2d */ Some(op("DCR", "L", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x2e */ Some(op("MVI", "L,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x2f */ Some(op("CMA", "", 1, 4, 4, 0, NONE, Logical)),
+    /* 0x30 */ Some(alias("NOP", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x31 */ Some(op("LXI", "SP,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x32 */ Some(op("STA", "a16", 3, 13, 13, 0, WRITE, DataTransfer)),
+    /* 0x33 */ Some(op("INX", "SP", 1, 5, 5, 0, NONE, Arithmetic)),
+    /* 0x34 */ Some(op("INR", "M", 1, 10, 10, S | Z | AC | P, READ_WRITE, Arithmetic)),
+    /* 0x35 */ Some(op("DCR", "M", 1, 10, 10, S | Z | AC | P, READ_WRITE, Arithmetic)),
+    /* 0x36 */ Some(op("MVI", "M,d8", 2, 10, 10, 0, WRITE, DataTransfer)),
+    /* 0x37 */ Some(op("STC", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x38 */ Some(alias("NOP", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x39 */ Some(op("DAD", "SP", 1, 10, 10, CY, NONE, Arithmetic)),
+    /* 0x3a */ Some(op("LDA", "a16", 3, 13, 13, 0, READ, DataTransfer)),
+    /* 0x3b */ Some(op("DCX", "SP", 1, 5, 5, 0, NONE, Arithmetic)),
+    /* 0x3c */ Some(op("INR", "A", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x3d */ Some(op("DCR", "A", 1, 5, 5, S | Z | AC | P, NONE, Arithmetic)),
+    /* 0x3e */ Some(op("MVI", "A,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x3f */ Some(op("CMC", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x40 */ Some(op("MOV", "B,B", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x41 */ Some(op("MOV", "B,C", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x42 */ Some(op("MOV", "B,D", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x43 */ Some(op("MOV", "B,E", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x44 */ Some(op("MOV", "B,H", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x45 */ Some(op("MOV", "B,L", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x46 */ Some(op("MOV", "B,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x47 */ Some(op("MOV", "B,A", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x48 */ Some(op("MOV", "C,B", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x49 */ Some(op("MOV", "C,C", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x4a */ Some(op("MOV", "C,D", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x4b */ Some(op("MOV", "C,E", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x4c */ Some(op("MOV", "C,H", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x4d */ Some(op("MOV", "C,L", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x4e */ Some(op("MOV", "C,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x4f */ Some(op("MOV", "C,A", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x50 */ Some(op("MOV", "D,B", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x51 */ Some(op("MOV", "D,C", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x52 */ Some(op("MOV", "D,D", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x53 */ Some(op("MOV", "D,E", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x54 */ Some(op("MOV", "D,H", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x55 */ Some(op("MOV", "D,L", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x56 */ Some(op("MOV", "D,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x57 */ Some(op("MOV", "D,A", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x58 */ Some(op("MOV", "E,B", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x59 */ Some(op("MOV", "E,C", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x5a */ Some(op("MOV", "E,D", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x5b */ Some(op("MOV", "E,E", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x5c */ Some(op("MOV", "E,H", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x5d */ Some(op("MOV", "E,L", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x5e */ Some(op("MOV", "E,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x5f */ Some(op("MOV", "E,A", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x60 */ Some(op("MOV", "H,B", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x61 */ Some(op("MOV", "H,C", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x62 */ Some(op("MOV", "H,D", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x63 */ Some(op("MOV", "H,E", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x64 */ Some(op("MOV", "H,H", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x65 */ Some(op("MOV", "H,L", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x66 */ Some(op("MOV", "H,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x67 */ Some(op("MOV", "H,A", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x68 */ Some(op("MOV", "L,B", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x69 */ Some(op("MOV", "L,C", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x6a */ Some(op("MOV", "L,D", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x6b */ Some(op("MOV", "L,E", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x6c */ Some(op("MOV", "L,H", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x6d */ Some(op("MOV", "L,L", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x6e */ Some(op("MOV", "L,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x6f */ Some(op("MOV", "L,A", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x70 */ Some(op("MOV", "M,B", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x71 */ Some(op("MOV", "M,C", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x72 */ Some(op("MOV", "M,D", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x73 */ Some(op("MOV", "M,E", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x74 */ Some(op("MOV", "M,H", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x75 */ Some(op("MOV", "M,L", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x76 */ Some(op("HLT", "", 1, 7, 7, 0, NONE, Control)),
+    /* 0x77 */ Some(op("MOV", "M,A", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x78 */ Some(op("MOV", "A,B", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x79 */ Some(op("MOV", "A,C", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x7a */ Some(op("MOV", "A,D", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x7b */ Some(op("MOV", "A,E", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x7c */ Some(op("MOV", "A,H", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x7d */ Some(op("MOV", "A,L", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x7e */ Some(op("MOV", "A,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x7f */ Some(op("MOV", "A,A", 1, 5, 5, 0, NONE, DataTransfer)),
+    /* 0x80 */ Some(op("ADD", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x81 */ Some(op("ADD", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x82 */ Some(op("ADD", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x83 */ Some(op("ADD", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x84 */ Some(op("ADD", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x85 */ Some(op("ADD", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x86 */ Some(op("ADD", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Arithmetic)),
+    /* 0x87 */ Some(op("ADD", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x88 */ Some(op("ADC", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x89 */ Some(op("ADC", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x8a */ Some(op("ADC", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x8b */ Some(op("ADC", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x8c */ Some(op("ADC", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x8d */ Some(op("ADC", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x8e */ Some(op("ADC", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Arithmetic)),
+    /* 0x8f */ Some(op("ADC", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x90 */ Some(op("SUB", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x91 */ Some(op("SUB", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x92 */ Some(op("SUB", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x93 */ Some(op("SUB", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x94 */ Some(op("SUB", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x95 */ Some(op("SUB", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x96 */ Some(op("SUB", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Arithmetic)),
+    /* 0x97 */ Some(op("SUB", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x98 */ Some(op("SBB", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x99 */ Some(op("SBB", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x9a */ Some(op("SBB", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x9b */ Some(op("SBB", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x9c */ Some(op("SBB", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x9d */ Some(op("SBB", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x9e */ Some(op("SBB", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Arithmetic)),
+    /* 0x9f */ Some(op("SBB", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0xa0 */ Some(op("ANA", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa1 */ Some(op("ANA", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa2 */ Some(op("ANA", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa3 */ Some(op("ANA", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa4 */ Some(op("ANA", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa5 */ Some(op("ANA", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa6 */ Some(op("ANA", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Logical)),
+    /* 0xa7 */ Some(op("ANA", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa8 */ Some(op("XRA", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa9 */ Some(op("XRA", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xaa */ Some(op("XRA", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xab */ Some(op("XRA", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xac */ Some(op("XRA", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xad */ Some(op("XRA", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xae */ Some(op("XRA", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Logical)),
+    /* 0xaf */ Some(op("XRA", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb0 */ Some(op("ORA", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb1 */ Some(op("ORA", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb2 */ Some(op("ORA", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb3 */ Some(op("ORA", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb4 */ Some(op("ORA", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb5 */ Some(op("ORA", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb6 */ Some(op("ORA", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Logical)),
+    /* 0xb7 */ Some(op("ORA", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb8 */ Some(op("CMP", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb9 */ Some(op("CMP", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xba */ Some(op("CMP", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xbb */ Some(op("CMP", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xbc */ Some(op("CMP", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xbd */ Some(op("CMP", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xbe */ Some(op("CMP", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Logical)),
+    /* 0xbf */ Some(op("CMP", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xc0 */ Some(op("RNZ", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xc1 */ Some(op("POP", "B", 1, 10, 10, 0, POP, Stack)),
+    /* 0xc2 */ Some(op("JNZ", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xc3 */ Some(op("JMP", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xc4 */ Some(op("CNZ", "a16", 3, 11, 17, 0, PUSH, Branch)),
+    /* 0xc5 */ Some(op("PUSH", "B", 1, 11, 11, 0, PUSH, Stack)),
+    /* 0xc6 */ Some(op("ADI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0xc7 */ Some(op("RST", "0", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xc8 */ Some(op("RZ", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xc9 */ Some(op("RET", "", 1, 10, 10, 0, POP, Branch)),
+    /* 0xca */ Some(op("JZ", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xcb */ Some(alias("JMP", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xcc */ Some(op("CZ", "a16", 3, 11, 17, 0, PUSH, Branch)),
+    /* 0xcd */ Some(op("CALL", "a16", 3, 17, 17, 0, PUSH, Branch)),
+    /* 0xce */ Some(op("ACI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0xcf */ Some(op("RST", "1", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xd0 */ Some(op("RNC", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xd1 */ Some(op("POP", "D", 1, 10, 10, 0, POP, Stack)),
+    /* 0xd2 */ Some(op("JNC", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xd3 */ Some(op("OUT", "d8", 2, 10, 10, 0, OUT, Io)),
+    /* 0xd4 */ Some(op("CNC", "a16", 3, 11, 17, 0, PUSH, Branch)),
+    /* 0xd5 */ Some(op("PUSH", "D", 1, 11, 11, 0, PUSH, Stack)),
+    /* 0xd6 */ Some(op("SUI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0xd7 */ Some(op("RST", "2", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xd8 */ Some(op("RC", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xd9 */ Some(alias("RET", "", 1, 10, 10, 0, POP, Branch)),
+    /* 0xda */ Some(op("JC", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xdb */ Some(op("IN", "d8", 2, 10, 10, 0, IN, Io)),
+    /* 0xdc */ Some(op("CC", "a16", 3, 11, 17, 0, PUSH, Branch)),
+    /* 0xdd */ Some(alias("CALL", "a16", 3, 17, 17, 0, PUSH, Branch)),
+    /* 0xde */ Some(op("SBI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0xdf */ Some(op("RST", "3", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xe0 */ Some(op("RPO", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xe1 */ Some(op("POP", "H", 1, 10, 10, 0, POP, Stack)),
+    /* 0xe2 */ Some(op("JPO", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xe3 */ Some(op("XTHL", "", 1, 18, 18, 0, EXCHANGE, Stack)),
+    /* 0xe4 */ Some(op("CPO", "a16", 3, 11, 17, 0, PUSH, Branch)),
+    /* 0xe5 */ Some(op("PUSH", "H", 1, 11, 11, 0, PUSH, Stack)),
+    /* 0xe6 */ Some(op("ANI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xe7 */ Some(op("RST", "4", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xe8 */ Some(op("RPE", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xe9 */ Some(op("PCHL", "", 1, 5, 5, 0, NONE, Branch)),
+    /* 0xea */ Some(op("JPE", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xeb */ Some(op("XCHG", "", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0xec */ Some(op("CPE", "a16", 3, 11, 17, 0, PUSH, Branch)),
+    /* 0xed */ Some(alias("CALL", "a16", 3, 17, 17, 0, PUSH, Branch)),
+    /* 0xee */ Some(op("XRI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xef */ Some(op("RST", "5", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xf0 */ Some(op("RP", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xf1 */ Some(op("POP", "PSW", 1, 10, 10, S | Z | AC | P | CY, POP, Stack)),
+    /* 0xf2 */ Some(op("JP", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xf3 */ Some(op("DI", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0xf4 */ Some(op("CP", "a16", 3, 11, 17, 0, PUSH, Branch)),
+    /* 0xf5 */ Some(op("PUSH", "PSW", 1, 11, 11, 0, PUSH, Stack)),
+    /* 0xf6 */ Some(op("ORI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xf7 */ Some(op("RST", "6", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xf8 */ Some(op("RM", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xf9 */ Some(op("SPHL", "", 1, 5, 5, 0, NONE, Stack)),
+    /* 0xfa */ Some(op("JM", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xfb */ Some(op("EI", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0xfc */ Some(op("CM", "a16", 3, 11, 17, 0, PUSH, Branch)),
+    /* 0xfd */ Some(alias("CALL", "a16", 3, 17, 17, 0, PUSH, Branch)),
+    /* 0xfe */ Some(op("CPI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xff */ Some(op("RST", "7", 1, 11, 11, 0, PUSH, Branch)),
+];
+
+static I8085: [Option<Metadata>; 256] = [
+    /* 0x00 */ Some(op("NOP", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x01 */ Some(op("LXI", "B,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x02 */ Some(op("STAX", "B", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x03 */ Some(op("INX", "B", 1, 6, 6, K, NONE, Arithmetic)),
+    /* 0x04 */ Some(op("INR", "B", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x05 */ Some(op("DCR", "B", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x06 */ Some(op("MVI", "B,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x07 */ Some(op("RLC", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x08 */ Some(alias("DSUB", "", 1, 10, 10, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x09 */ Some(op("DAD", "B", 1, 10, 10, CY, NONE, Arithmetic)),
+    /* 0x0a */ Some(op("LDAX", "B", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x0b */ Some(op("DCX", "B", 1, 6, 6, K, NONE, Arithmetic)),
+    /* 0x0c */ Some(op("INR", "C", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x0d */ Some(op("DCR", "C", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x0e */ Some(op("MVI", "C,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x0f */ Some(op("RRC", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x10 */ Some(alias("ARHL", "", 1, 7, 7, CY, NONE, Arithmetic)),
+    /* 0x11 */ Some(op("LXI", "D,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x12 */ Some(op("STAX", "D", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x13 */ Some(op("INX", "D", 1, 6, 6, K, NONE, Arithmetic)),
+    /* 0x14 */ Some(op("INR", "D", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x15 */ Some(op("DCR", "D", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x16 */ Some(op("MVI", "D,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x17 */ Some(op("RAL", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x18 */ Some(alias("RDEL", "", 1, 10, 10, CY | V, NONE, Logical)),
+    /* 0x19 */ Some(op("DAD", "D", 1, 10, 10, CY, NONE, Arithmetic)),
+    /* 0x1a */ Some(op("LDAX", "D", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x1b */ Some(op("DCX", "D", 1, 6, 6, K, NONE, Arithmetic)),
+    /* 0x1c */ Some(op("INR", "E", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x1d */ Some(op("DCR", "E", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x1e */ Some(op("MVI", "E,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x1f */ Some(op("RAR", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x20 */ Some(op("RIM", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x21 */ Some(op("LXI", "H,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x22 */ Some(op("SHLD", "a16", 3, 16, 16, 0, WRITE, DataTransfer)),
+    /* 0x23 */ Some(op("INX", "H", 1, 6, 6, K, NONE, Arithmetic)),
+    /* 0x24 */ Some(op("INR", "H", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x25 */ Some(op("DCR", "H", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x26 */ Some(op("MVI", "H,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x27 */ Some(op("DAA", "", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x28 */ Some(alias("LDHI", "d8", 2, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x29 */ Some(op("DAD", "H", 1, 10, 10, CY, NONE, Arithmetic)),
+    /* 0x2a */ Some(op("LHLD", "a16", 3, 16, 16, 0, READ, DataTransfer)),
+    /* 0x2b */ Some(op("DCX", "H", 1, 6, 6, K, NONE, Arithmetic)),
+    /* 0x2c */ Some(op("INR", "L", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x2d */ Some(op("DCR", "L", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x2e */ Some(op("MVI", "L,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x2f */ Some(op("CMA", "", 1, 4, 4, 0, NONE, Logical)),
+    /* 0x30 */ Some(op("SIM", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x31 */ Some(op("LXI", "SP,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x32 */ Some(op("STA", "a16", 3, 13, 13, 0, WRITE, DataTransfer)),
+    /* 0x33 */ Some(op("INX", "SP", 1, 6, 6, K, NONE, Arithmetic)),
+    /* 0x34 */ Some(op("INR", "M", 1, 10, 10, S | Z | AC | P | V, READ_WRITE, Arithmetic)),
+    /* 0x35 */ Some(op("DCR", "M", 1, 10, 10, S | Z | AC | P | V, READ_WRITE, Arithmetic)),
+    /* 0x36 */ Some(op("MVI", "M,d8", 2, 10, 10, 0, WRITE, DataTransfer)),
+    /* 0x37 */ Some(op("STC", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x38 */ Some(alias("LDSI", "d8", 2, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x39 */ Some(op("DAD", "SP", 1, 10, 10, CY, NONE, Arithmetic)),
+    /* 0x3a */ Some(op("LDA", "a16", 3, 13, 13, 0, READ, DataTransfer)),
+    /* 0x3b */ Some(op("DCX", "SP", 1, 6, 6, K, NONE, Arithmetic)),
+    /* 0x3c */ Some(op("INR", "A", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x3d */ Some(op("DCR", "A", 1, 4, 4, S | Z | AC | P | V, NONE, Arithmetic)),
+    /* 0x3e */ Some(op("MVI", "A,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x3f */ Some(op("CMC", "", 1, 4, 4, CY, NONE, Logical)),
+    /* 0x40 */ Some(op("MOV", "B,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x41 */ Some(op("MOV", "B,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x42 */ Some(op("MOV", "B,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x43 */ Some(op("MOV", "B,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x44 */ Some(op("MOV", "B,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x45 */ Some(op("MOV", "B,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x46 */ Some(op("MOV", "B,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x47 */ Some(op("MOV", "B,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x48 */ Some(op("MOV", "C,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x49 */ Some(op("MOV", "C,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x4a */ Some(op("MOV", "C,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x4b */ Some(op("MOV", "C,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x4c */ Some(op("MOV", "C,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x4d */ Some(op("MOV", "C,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x4e */ Some(op("MOV", "C,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x4f */ Some(op("MOV", "C,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x50 */ Some(op("MOV", "D,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x51 */ Some(op("MOV", "D,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x52 */ Some(op("MOV", "D,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x53 */ Some(op("MOV", "D,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x54 */ Some(op("MOV", "D,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x55 */ Some(op("MOV", "D,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x56 */ Some(op("MOV", "D,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x57 */ Some(op("MOV", "D,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x58 */ Some(op("MOV", "E,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x59 */ Some(op("MOV", "E,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x5a */ Some(op("MOV", "E,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x5b */ Some(op("MOV", "E,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x5c */ Some(op("MOV", "E,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x5d */ Some(op("MOV", "E,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x5e */ Some(op("MOV", "E,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x5f */ Some(op("MOV", "E,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x60 */ Some(op("MOV", "H,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x61 */ Some(op("MOV", "H,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x62 */ Some(op("MOV", "H,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x63 */ Some(op("MOV", "H,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x64 */ Some(op("MOV", "H,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x65 */ Some(op("MOV", "H,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x66 */ Some(op("MOV", "H,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x67 */ Some(op("MOV", "H,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x68 */ Some(op("MOV", "L,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x69 */ Some(op("MOV", "L,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x6a */ Some(op("MOV", "L,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x6b */ Some(op("MOV", "L,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x6c */ Some(op("MOV", "L,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x6d */ Some(op("MOV", "L,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x6e */ Some(op("MOV", "L,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x6f */ Some(op("MOV", "L,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x70 */ Some(op("MOV", "M,B", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x71 */ Some(op("MOV", "M,C", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x72 */ Some(op("MOV", "M,D", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x73 */ Some(op("MOV", "M,E", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x74 */ Some(op("MOV", "M,H", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x75 */ Some(op("MOV", "M,L", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x76 */ Some(op("HLT", "", 1, 5, 5, 0, NONE, Control)),
+    /* 0x77 */ Some(op("MOV", "M,A", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x78 */ Some(op("MOV", "A,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x79 */ Some(op("MOV", "A,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x7a */ Some(op("MOV", "A,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x7b */ Some(op("MOV", "A,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x7c */ Some(op("MOV", "A,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x7d */ Some(op("MOV", "A,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x7e */ Some(op("MOV", "A,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x7f */ Some(op("MOV", "A,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x80 */ Some(op("ADD", "B", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x81 */ Some(op("ADD", "C", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x82 */ Some(op("ADD", "D", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x83 */ Some(op("ADD", "E", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x84 */ Some(op("ADD", "H", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x85 */ Some(op("ADD", "L", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x86 */ Some(op("ADD", "M", 1, 7, 7, S | Z | AC | P | CY | V, READ, Arithmetic)),
+    /* 0x87 */ Some(op("ADD", "A", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x88 */ Some(op("ADC", "B", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x89 */ Some(op("ADC", "C", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x8a */ Some(op("ADC", "D", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x8b */ Some(op("ADC", "E", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x8c */ Some(op("ADC", "H", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x8d */ Some(op("ADC", "L", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x8e */ Some(op("ADC", "M", 1, 7, 7, S | Z | AC | P | CY | V, READ, Arithmetic)),
+    /* 0x8f */ Some(op("ADC", "A", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x90 */ Some(op("SUB", "B", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x91 */ Some(op("SUB", "C", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x92 */ Some(op("SUB", "D", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x93 */ Some(op("SUB", "E", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x94 */ Some(op("SUB", "H", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x95 */ Some(op("SUB", "L", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x96 */ Some(op("SUB", "M", 1, 7, 7, S | Z | AC | P | CY | V, READ, Arithmetic)),
+    /* 0x97 */ Some(op("SUB", "A", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x98 */ Some(op("SBB", "B", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x99 */ Some(op("SBB", "C", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x9a */ Some(op("SBB", "D", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x9b */ Some(op("SBB", "E", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x9c */ Some(op("SBB", "H", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x9d */ Some(op("SBB", "L", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0x9e */ Some(op("SBB", "M", 1, 7, 7, S | Z | AC | P | CY | V, READ, Arithmetic)),
+    /* 0x9f */ Some(op("SBB", "A", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0xa0 */ Some(op("ANA", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa1 */ Some(op("ANA", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa2 */ Some(op("ANA", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa3 */ Some(op("ANA", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa4 */ Some(op("ANA", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa5 */ Some(op("ANA", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa6 */ Some(op("ANA", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Logical)),
+    /* 0xa7 */ Some(op("ANA", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa8 */ Some(op("XRA", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xa9 */ Some(op("XRA", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xaa */ Some(op("XRA", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xab */ Some(op("XRA", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xac */ Some(op("XRA", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xad */ Some(op("XRA", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xae */ Some(op("XRA", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Logical)),
+    /* 0xaf */ Some(op("XRA", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb0 */ Some(op("ORA", "B", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb1 */ Some(op("ORA", "C", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb2 */ Some(op("ORA", "D", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb3 */ Some(op("ORA", "E", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb4 */ Some(op("ORA", "H", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb5 */ Some(op("ORA", "L", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb6 */ Some(op("ORA", "M", 1, 7, 7, S | Z | AC | P | CY, READ, Logical)),
+    /* 0xb7 */ Some(op("ORA", "A", 1, 4, 4, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xb8 */ Some(op("CMP", "B", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Logical)),
+    /* 0xb9 */ Some(op("CMP", "C", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Logical)),
+    /* 0xba */ Some(op("CMP", "D", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Logical)),
+    /* 0xbb */ Some(op("CMP", "E", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Logical)),
+    /* 0xbc */ Some(op("CMP", "H", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Logical)),
+    /* 0xbd */ Some(op("CMP", "L", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Logical)),
+    /* 0xbe */ Some(op("CMP", "M", 1, 7, 7, S | Z | AC | P | CY | V, READ, Logical)),
+    /* 0xbf */ Some(op("CMP", "A", 1, 4, 4, S | Z | AC | P | CY | V, NONE, Logical)),
+    /* 0xc0 */ Some(op("RNZ", "", 1, 6, 12, 0, POP, Branch)),
+    /* 0xc1 */ Some(op("POP", "B", 1, 10, 10, 0, POP, Stack)),
+    /* 0xc2 */ Some(op("JNZ", "a16", 3, 7, 10, 0, NONE, Branch)),
+    /* 0xc3 */ Some(op("JMP", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xc4 */ Some(op("CNZ", "a16", 3, 9, 18, 0, PUSH, Branch)),
+    /* 0xc5 */ Some(op("PUSH", "B", 1, 12, 12, 0, PUSH, Stack)),
+    /* 0xc6 */ Some(op("ADI", "d8", 2, 7, 7, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0xc7 */ Some(op("RST", "0", 1, 12, 12, 0, PUSH, Branch)),
+    /* 0xc8 */ Some(op("RZ", "", 1, 6, 12, 0, POP, Branch)),
+    /* 0xc9 */ Some(op("RET", "", 1, 10, 10, 0, POP, Branch)),
+    /* 0xca */ Some(op("JZ", "a16", 3, 7, 10, 0, NONE, Branch)),
+    /* 0xcb */ Some(alias("RSTV", "", 1, 6, 12, 0, PUSH, Branch)),
+    /* 0xcc */ Some(op("CZ", "a16", 3, 9, 18, 0, PUSH, Branch)),
+    /* 0xcd */ Some(op("CALL", "a16", 3, 18, 18, 0, PUSH, Branch)),
+    /* 0xce */ Some(op("ACI", "d8", 2, 7, 7, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0xcf */ Some(op("RST", "1", 1, 12, 12, 0, PUSH, Branch)),
+    /* 0xd0 */ Some(op("RNC", "", 1, 6, 12, 0, POP, Branch)),
+    /* 0xd1 */ Some(op("POP", "D", 1, 10, 10, 0, POP, Stack)),
+    /* 0xd2 */ Some(op("JNC", "a16", 3, 7, 10, 0, NONE, Branch)),
+    /* 0xd3 */ Some(op("OUT", "d8", 2, 10, 10, 0, OUT, Io)),
+    /* 0xd4 */ Some(op("CNC", "a16", 3, 9, 18, 0, PUSH, Branch)),
+    /* 0xd5 */ Some(op("PUSH", "D", 1, 12, 12, 0, PUSH, Stack)),
+    /* 0xd6 */ Some(op("SUI", "d8", 2, 7, 7, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0xd7 */ Some(op("RST", "2", 1, 12, 12, 0, PUSH, Branch)),
+    /* 0xd8 */ Some(op("RC", "", 1, 6, 12, 0, POP, Branch)),
+    /* 0xd9 */ Some(alias("SHLX", "", 1, 10, 10, 0, WRITE, DataTransfer)),
+    /* 0xda */ Some(op("JC", "a16", 3, 7, 10, 0, NONE, Branch)),
+    /* 0xdb */ Some(op("IN", "d8", 2, 10, 10, 0, IN, Io)),
+    /* 0xdc */ Some(op("CC", "a16", 3, 9, 18, 0, PUSH, Branch)),
+    /* 0xdd */ Some(alias("JNK", "a16", 3, 7, 10, 0, NONE, Branch)),
+    /* 0xde */ Some(op("SBI", "d8", 2, 7, 7, S | Z | AC | P | CY | V, NONE, Arithmetic)),
+    /* 0xdf */ Some(op("RST", "3", 1, 12, 12, 0, PUSH, Branch)),
+    /* 0xe0 */ Some(op("RPO", "", 1, 6, 12, 0, POP, Branch)),
+    /* 0xe1 */ Some(op("POP", "H", 1, 10, 10, 0, POP, Stack)),
+    /* 0xe2 */ Some(op("JPO", "a16", 3, 7, 10, 0, NONE, Branch)),
+    /* 0xe3 */ Some(op("XTHL", "", 1, 16, 16, 0, EXCHANGE, Stack)),
+    /* 0xe4 */ Some(op("CPO", "a16", 3, 9, 18, 0, PUSH, Branch)),
+    /* 0xe5 */ Some(op("PUSH", "H", 1, 12, 12, 0, PUSH, Stack)),
+    /* 0xe6 */ Some(op("ANI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xe7 */ Some(op("RST", "4", 1, 12, 12, 0, PUSH, Branch)),
+    /* 0xe8 */ Some(op("RPE", "", 1, 6, 12, 0, POP, Branch)),
+    /* 0xe9 */ Some(op("PCHL", "", 1, 6, 6, 0, NONE, Branch)),
+    /* 0xea */ Some(op("JPE", "a16", 3, 7, 10, 0, NONE, Branch)),
+    /* 0xeb */ Some(op("XCHG", "", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0xec */ Some(op("CPE", "a16", 3, 9, 18, 0, PUSH, Branch)),
+    /* 0xed */ Some(alias("LHLX", "", 1, 10, 10, 0, READ, DataTransfer)),
+    /* 0xee */ Some(op("XRI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xef */ Some(op("RST", "5", 1, 12, 12, 0, PUSH, Branch)),
+    /* 0xf0 */ Some(op("RP", "", 1, 6, 12, 0, POP, Branch)),
+    /* 0xf1 */ Some(op("POP", "PSW", 1, 10, 10, S | Z | AC | P | CY | V | K, POP, Stack)),
+    /* 0xf2 */ Some(op("JP", "a16", 3, 7, 10, 0, NONE, Branch)),
+    /* 0xf3 */ Some(op("DI", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0xf4 */ Some(op("CP", "a16", 3, 9, 18, 0, PUSH, Branch)),
+    /* 0xf5 */ Some(op("PUSH", "PSW", 1, 12, 12, 0, PUSH, Stack)),
+    /* 0xf6 */ Some(op("ORI", "d8", 2, 7, 7, S | Z | AC | P | CY, NONE, Logical)),
+    /* 0xf7 */ Some(op("RST", "6", 1, 12, 12, 0, PUSH, Branch)),
+    /* 0xf8 */ Some(op("RM", "", 1, 6, 12, 0, POP, Branch)),
+    /* 0xf9 */ Some(op("SPHL", "", 1, 6, 6, 0, NONE, Stack)),
+    /* 0xfa */ Some(op("JM", "a16", 3, 7, 10, 0, NONE, Branch)),
+    /* 0xfb */ Some(op("EI", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0xfc */ Some(op("CM", "a16", 3, 9, 18, 0, PUSH, Branch)),
+    /* 0xfd */ Some(alias("JK", "a16", 3, 7, 10, 0, NONE, Branch)),
+    /* 0xfe */ Some(op("CPI", "d8", 2, 7, 7, S | Z | AC | P | CY | V, NONE, Logical)),
+    /* 0xff */ Some(op("RST", "7", 1, 12, 12, 0, PUSH, Branch)),
+];
+
+static Z80: [Option<Metadata>; 256] = [
+    /* 0x00 */ Some(op("NOP", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x01 */ Some(op("LXI", "B,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x02 */ Some(op("STAX", "B", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x03 */ Some(op("INX", "B", 1, 6, 6, 0, NONE, Arithmetic)),
+    /* 0x04 */ Some(op("INR", "B", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x05 */ Some(op("DCR", "B", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x06 */ Some(op("MVI", "B,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x07 */ Some(op("RLC", "", 1, 4, 4, AC | N | CY, NONE, Logical)),
+    /* 0x08 */ None,
+    /* 0x09 */ Some(op("DAD", "B", 1, 11, 11, AC | N | CY, NONE, Arithmetic)),
+    /* 0x0a */ Some(op("LDAX", "B", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x0b */ Some(op("DCX", "B", 1, 6, 6, 0, NONE, Arithmetic)),
+    /* 0x0c */ Some(op("INR", "C", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x0d */ Some(op("DCR", "C", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x0e */ Some(op("MVI", "C,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x0f */ Some(op("RRC", "", 1, 4, 4, AC | N | CY, NONE, Logical)),
+    /* 0x10 */ None,
+    /* 0x11 */ Some(op("LXI", "D,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x12 */ Some(op("STAX", "D", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x13 */ Some(op("INX", "D", 1, 6, 6, 0, NONE, Arithmetic)),
+    /* 0x14 */ Some(op("INR", "D", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x15 */ Some(op("DCR", "D", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x16 */ Some(op("MVI", "D,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x17 */ Some(op("RAL", "", 1, 4, 4, AC | N | CY, NONE, Logical)),
+    /* 0x18 */ None,
+    /* 0x19 */ Some(op("DAD", "D", 1, 11, 11, AC | N | CY, NONE, Arithmetic)),
+    /* 0x1a */ Some(op("LDAX", "D", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x1b */ Some(op("DCX", "D", 1, 6, 6, 0, NONE, Arithmetic)),
+    /* 0x1c */ Some(op("INR", "E", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x1d */ Some(op("DCR", "E", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x1e */ Some(op("MVI", "E,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x1f */ Some(op("RAR", "", 1, 4, 4, AC | N | CY, NONE, Logical)),
+    /* 0x20 */ None,
+    /* 0x21 */ Some(op("LXI", "H,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x22 */ Some(op("SHLD", "a16", 3, 16, 16, 0, WRITE, DataTransfer)),
+    /* 0x23 */ Some(op("INX", "H", 1, 6, 6, 0, NONE, Arithmetic)),
+    /* 0x24 */ Some(op("INR", "H", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x25 */ Some(op("DCR", "H", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x26 */ Some(op("MVI", "H,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x27 */ Some(op("DAA", "", 1, 4, 4, S | Z | AC | P | CY, NONE, Arithmetic)),
+    /* 0x28 */ None,
+    /* 0x29 */ Some(op("DAD", "H", 1, 11, 11, AC | N | CY, NONE, Arithmetic)),
+    /* 0x2a */ Some(op("LHLD", "a16", 3, 16, 16, 0, READ, DataTransfer)),
+    /* 0x2b */ Some(op("DCX", "H", 1, 6, 6, 0, NONE, Arithmetic)),
+    /* 0x2c */ Some(op("INR", "L", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x2d */ Some(op("DCR", "L", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x2e */ Some(op("MVI", "L,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x2f */ Some(op("CMA", "", 1, 4, 4, AC | N, NONE, Logical)),
+    /* 0x30 */ None,
+    /* 0x31 */ Some(op("LXI", "SP,d16", 3, 10, 10, 0, NONE, DataTransfer)),
+    /* 0x32 */ Some(op("STA", "a16", 3, 13, 13, 0, WRITE, DataTransfer)),
+    /* 0x33 */ Some(op("INX", "SP", 1, 6, 6, 0, NONE, Arithmetic)),
+    /* 0x34 */ Some(op("INR", "M", 1, 11, 11, S | Z | AC | P | N, READ_WRITE, Arithmetic)),
+    /* 0x35 */ Some(op("DCR", "M", 1, 11, 11, S | Z | AC | P | N, READ_WRITE, Arithmetic)),
+    /* 0x36 */ Some(op("MVI", "M,d8", 2, 10, 10, 0, WRITE, DataTransfer)),
+    /* 0x37 */ Some(op("STC", "", 1, 4, 4, AC | N | CY, NONE, Logical)),
+    /* 0x38 */ None,
+    /* 0x39 */ Some(op("DAD", "SP", 1, 11, 11, AC | N | CY, NONE, Arithmetic)),
+    /* 0x3a */ Some(op("LDA", "a16", 3, 13, 13, 0, READ, DataTransfer)),
+    /* 0x3b */ Some(op("DCX", "SP", 1, 6, 6, 0, NONE, Arithmetic)),
+    /* 0x3c */ Some(op("INR", "A", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x3d */ Some(op("DCR", "A", 1, 4, 4, S | Z | AC | P | N, NONE, Arithmetic)),
+    /* 0x3e */ Some(op("MVI", "A,d8", 2, 7, 7, 0, NONE, DataTransfer)),
+    /* 0x3f */ Some(op("CMC", "", 1, 4, 4, AC | N | CY, NONE, Logical)),
+    /* 0x40 */ Some(op("MOV", "B,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x41 */ Some(op("MOV", "B,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x42 */ Some(op("MOV", "B,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x43 */ Some(op("MOV", "B,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x44 */ Some(op("MOV", "B,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x45 */ Some(op("MOV", "B,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x46 */ Some(op("MOV", "B,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x47 */ Some(op("MOV", "B,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x48 */ Some(op("MOV", "C,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x49 */ Some(op("MOV", "C,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x4a */ Some(op("MOV", "C,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x4b */ Some(op("MOV", "C,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x4c */ Some(op("MOV", "C,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x4d */ Some(op("MOV", "C,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x4e */ Some(op("MOV", "C,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x4f */ Some(op("MOV", "C,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x50 */ Some(op("MOV", "D,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x51 */ Some(op("MOV", "D,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x52 */ Some(op("MOV", "D,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x53 */ Some(op("MOV", "D,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x54 */ Some(op("MOV", "D,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x55 */ Some(op("MOV", "D,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x56 */ Some(op("MOV", "D,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x57 */ Some(op("MOV", "D,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x58 */ Some(op("MOV", "E,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x59 */ Some(op("MOV", "E,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x5a */ Some(op("MOV", "E,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x5b */ Some(op("MOV", "E,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x5c */ Some(op("MOV", "E,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x5d */ Some(op("MOV", "E,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x5e */ Some(op("MOV", "E,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x5f */ Some(op("MOV", "E,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x60 */ Some(op("MOV", "H,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x61 */ Some(op("MOV", "H,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x62 */ Some(op("MOV", "H,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x63 */ Some(op("MOV", "H,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x64 */ Some(op("MOV", "H,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x65 */ Some(op("MOV", "H,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x66 */ Some(op("MOV", "H,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x67 */ Some(op("MOV", "H,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x68 */ Some(op("MOV", "L,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x69 */ Some(op("MOV", "L,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x6a */ Some(op("MOV", "L,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x6b */ Some(op("MOV", "L,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x6c */ Some(op("MOV", "L,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x6d */ Some(op("MOV", "L,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x6e */ Some(op("MOV", "L,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x6f */ Some(op("MOV", "L,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x70 */ Some(op("MOV", "M,B", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x71 */ Some(op("MOV", "M,C", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x72 */ Some(op("MOV", "M,D", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x73 */ Some(op("MOV", "M,E", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x74 */ Some(op("MOV", "M,H", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x75 */ Some(op("MOV", "M,L", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x76 */ Some(op("HLT", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0x77 */ Some(op("MOV", "M,A", 1, 7, 7, 0, WRITE, DataTransfer)),
+    /* 0x78 */ Some(op("MOV", "A,B", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x79 */ Some(op("MOV", "A,C", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x7a */ Some(op("MOV", "A,D", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x7b */ Some(op("MOV", "A,E", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x7c */ Some(op("MOV", "A,H", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x7d */ Some(op("MOV", "A,L", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x7e */ Some(op("MOV", "A,M", 1, 7, 7, 0, READ, DataTransfer)),
+    /* 0x7f */ Some(op("MOV", "A,A", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0x80 */ Some(op("ADD", "B", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x81 */ Some(op("ADD", "C", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x82 */ Some(op("ADD", "D", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x83 */ Some(op("ADD", "E", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x84 */ Some(op("ADD", "H", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x85 */ Some(op("ADD", "L", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x86 */ Some(op("ADD", "M", 1, 7, 7, S | Z | AC | P | CY | N, READ, Arithmetic)),
+    /* 0x87 */ Some(op("ADD", "A", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x88 */ Some(op("ADC", "B", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x89 */ Some(op("ADC", "C", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x8a */ Some(op("ADC", "D", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x8b */ Some(op("ADC", "E", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x8c */ Some(op("ADC", "H", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x8d */ Some(op("ADC", "L", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x8e */ Some(op("ADC", "M", 1, 7, 7, S | Z | AC | P | CY | N, READ, Arithmetic)),
+    /* 0x8f */ Some(op("ADC", "A", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x90 */ Some(op("SUB", "B", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x91 */ Some(op("SUB", "C", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x92 */ Some(op("SUB", "D", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x93 */ Some(op("SUB", "E", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x94 */ Some(op("SUB", "H", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x95 */ Some(op("SUB", "L", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x96 */ Some(op("SUB", "M", 1, 7, 7, S | Z | AC | P | CY | N, READ, Arithmetic)),
+    /* 0x97 */ Some(op("SUB", "A", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x98 */ Some(op("SBB", "B", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x99 */ Some(op("SBB", "C", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x9a */ Some(op("SBB", "D", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x9b */ Some(op("SBB", "E", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x9c */ Some(op("SBB", "H", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x9d */ Some(op("SBB", "L", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0x9e */ Some(op("SBB", "M", 1, 7, 7, S | Z | AC | P | CY | N, READ, Arithmetic)),
+    /* 0x9f */ Some(op("SBB", "A", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0xa0 */ Some(op("ANA", "B", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xa1 */ Some(op("ANA", "C", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xa2 */ Some(op("ANA", "D", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xa3 */ Some(op("ANA", "E", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xa4 */ Some(op("ANA", "H", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xa5 */ Some(op("ANA", "L", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xa6 */ Some(op("ANA", "M", 1, 7, 7, S | Z | AC | P | CY | N, READ, Logical)),
+    /* 0xa7 */ Some(op("ANA", "A", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xa8 */ Some(op("XRA", "B", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xa9 */ Some(op("XRA", "C", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xaa */ Some(op("XRA", "D", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xab */ Some(op("XRA", "E", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xac */ Some(op("XRA", "H", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xad */ Some(op("XRA", "L", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xae */ Some(op("XRA", "M", 1, 7, 7, S | Z | AC | P | CY | N, READ, Logical)),
+    /* 0xaf */ Some(op("XRA", "A", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xb0 */ Some(op("ORA", "B", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xb1 */ Some(op("ORA", "C", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xb2 */ Some(op("ORA", "D", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xb3 */ Some(op("ORA", "E", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xb4 */ Some(op("ORA", "H", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xb5 */ Some(op("ORA", "L", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xb6 */ Some(op("ORA", "M", 1, 7, 7, S | Z | AC | P | CY | N, READ, Logical)),
+    /* 0xb7 */ Some(op("ORA", "A", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xb8 */ Some(op("CMP", "B", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xb9 */ Some(op("CMP", "C", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xba */ Some(op("CMP", "D", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xbb */ Some(op("CMP", "E", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xbc */ Some(op("CMP", "H", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xbd */ Some(op("CMP", "L", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xbe */ Some(op("CMP", "M", 1, 7, 7, S | Z | AC | P | CY | N, READ, Logical)),
+    /* 0xbf */ Some(op("CMP", "A", 1, 4, 4, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xc0 */ Some(op("RNZ", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xc1 */ Some(op("POP", "B", 1, 10, 10, 0, POP, Stack)),
+    /* 0xc2 */ Some(op("JNZ", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xc3 */ Some(op("JMP", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xc4 */ Some(op("CNZ", "a16", 3, 10, 17, 0, PUSH, Branch)),
+    /* 0xc5 */ Some(op("PUSH", "B", 1, 11, 11, 0, PUSH, Stack)),
+    /* 0xc6 */ Some(op("ADI", "d8", 2, 7, 7, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0xc7 */ Some(op("RST", "0", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xc8 */ Some(op("RZ", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xc9 */ Some(op("RET", "", 1, 10, 10, 0, POP, Branch)),
+    /* 0xca */ Some(op("JZ", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xcb */ None,
+    /* 0xcc */ Some(op("CZ", "a16", 3, 10, 17, 0, PUSH, Branch)),
+    /* 0xcd */ Some(op("CALL", "a16", 3, 17, 17, 0, PUSH, Branch)),
+    /* 0xce */ Some(op("ACI", "d8", 2, 7, 7, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0xcf */ Some(op("RST", "1", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xd0 */ Some(op("RNC", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xd1 */ Some(op("POP", "D", 1, 10, 10, 0, POP, Stack)),
+    /* 0xd2 */ Some(op("JNC", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xd3 */ Some(op("OUT", "d8", 2, 11, 11, 0, OUT, Io)),
+    /* 0xd4 */ Some(op("CNC", "a16", 3, 10, 17, 0, PUSH, Branch)),
+    /* 0xd5 */ Some(op("PUSH", "D", 1, 11, 11, 0, PUSH, Stack)),
+    /* 0xd6 */ Some(op("SUI", "d8", 2, 7, 7, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0xd7 */ Some(op("RST", "2", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xd8 */ Some(op("RC", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xd9 */ None,
+    /* 0xda */ Some(op("JC", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xdb */ Some(op("IN", "d8", 2, 11, 11, 0, IN, Io)),
+    /* 0xdc */ Some(op("CC", "a16", 3, 10, 17, 0, PUSH, Branch)),
+    /* 0xdd */ None,
+    /* 0xde */ Some(op("SBI", "d8", 2, 7, 7, S | Z | AC | P | CY | N, NONE, Arithmetic)),
+    /* 0xdf */ Some(op("RST", "3", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xe0 */ Some(op("RPO", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xe1 */ Some(op("POP", "H", 1, 10, 10, 0, POP, Stack)),
+    /* 0xe2 */ Some(op("JPO", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xe3 */ Some(op("XTHL", "", 1, 19, 19, 0, EXCHANGE, Stack)),
+    /* 0xe4 */ Some(op("CPO", "a16", 3, 10, 17, 0, PUSH, Branch)),
+    /* 0xe5 */ Some(op("PUSH", "H", 1, 11, 11, 0, PUSH, Stack)),
+    /* 0xe6 */ Some(op("ANI", "d8", 2, 7, 7, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xe7 */ Some(op("RST", "4", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xe8 */ Some(op("RPE", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xe9 */ Some(op("PCHL", "", 1, 4, 4, 0, NONE, Branch)),
+    /* 0xea */ Some(op("JPE", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xeb */ Some(op("XCHG", "", 1, 4, 4, 0, NONE, DataTransfer)),
+    /* 0xec */ Some(op("CPE", "a16", 3, 10, 17, 0, PUSH, Branch)),
+    /* 0xed */ None,
+    /* 0xee */ Some(op("XRI", "d8", 2, 7, 7, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xef */ Some(op("RST", "5", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xf0 */ Some(op("RP", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xf1 */ Some(op("POP", "PSW", 1, 10, 10, S | Z | AC | P | CY | N, POP, Stack)),
+    /* 0xf2 */ Some(op("JP", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xf3 */ Some(op("DI", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0xf4 */ Some(op("CP", "a16", 3, 10, 17, 0, PUSH, Branch)),
+    /* 0xf5 */ Some(op("PUSH", "PSW", 1, 11, 11, 0, PUSH, Stack)),
+    /* 0xf6 */ Some(op("ORI", "d8", 2, 7, 7, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xf7 */ Some(op("RST", "6", 1, 11, 11, 0, PUSH, Branch)),
+    /* 0xf8 */ Some(op("RM", "", 1, 5, 11, 0, POP, Branch)),
+    /* 0xf9 */ Some(op("SPHL", "", 1, 6, 6, 0, NONE, Stack)),
+    /* 0xfa */ Some(op("JM", "a16", 3, 10, 10, 0, NONE, Branch)),
+    /* 0xfb */ Some(op("EI", "", 1, 4, 4, 0, NONE, Control)),
+    /* 0xfc */ Some(op("CM", "a16", 3, 10, 17, 0, PUSH, Branch)),
+    /* 0xfd */ None,
+    /* 0xfe */ Some(op("CPI", "d8", 2, 7, 7, S | Z | AC | P | CY | N, NONE, Logical)),
+    /* 0xff */ Some(op("RST", "7", 1, 11, 11, 0, PUSH, Branch)),
+];
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::{Cpu, Memory};
+
+    fn cpu(variant: Variant, byte: u8) -> Cpu {
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(&[byte, 0x5a, 0x00][..])).unwrap());
+        cpu.set_variant(variant);
+        cpu.set_sp(0x100);
+        cpu
+    }
+
+    #[test]
+    fn lengths_match_the_decoder() {
+        for variant in [Variant::I8080, Variant::I8085, Variant::Z80] {
+            for byte in 0..=0xff {
+                let (opcode, len) = crate::decode_with(variant, &[byte, 0, 0, 0]).unwrap();
+                match metadata(variant, byte) {
+                    Some(metadata) => assert_eq!(metadata.len as usize, len, "{:?} {:?}", variant, opcode),
+                    None => assert!(matches!(opcode, crate::Opcode::Z80(_)), "{:?} {:?}", variant, opcode),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn timings_match_execution() {
+        for variant in [Variant::I8080, Variant::I8085, Variant::Z80] {
+            for byte in 0..=0xff {
+                let Some(metadata) = metadata(variant, byte) else { continue };
+                // With the flags all set or all reset, every condition or its opposite is met.
+                for flags in [0xff, 0x00] {
+                    let mut cpu = self::cpu(variant, byte);
+                    *cpu.flags_mut() = Flags::from(flags);
+                    let states = cpu.step().unwrap().states;
+                    let expected = [metadata.states as u32, metadata.states_taken as u32];
+                    assert!(expected.contains(&states), "{:?} {:#04x}", variant, byte);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn only_the_listed_flags_are_written() {
+        for variant in [Variant::I8080, Variant::I8085] {
+            for byte in 0..=0xff {
+                let metadata = metadata(variant, byte).unwrap();
+                for flags in [0xff, 0x00] {
+                    let mut cpu = self::cpu(variant, byte);
+                    cpu.reg_mut().a = 0x5a;
+                    *cpu.flags_mut() = Flags::from(flags);
+                    cpu.step().unwrap();
+                    let changed = (u8::from(*cpu.flags()) ^ u8::from(Flags::from(flags))) & !metadata.flags;
+                    assert_eq!(changed, 0, "{:?} {} {}", variant, metadata.mnemonic, metadata.operands);
+                }
+            }
+        }
+    }
+}
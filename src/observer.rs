@@ -0,0 +1,173 @@
+use std::any::Any;
+
+use crate::{z80, Opcode, Registers, Step};
+
+/// Receives notifications about what the processor does, for tracers,
+/// profilers, coverage tools and the like. Register one with
+/// [`Cpu::set_observer`](crate::Cpu::set_observer).
+///
+/// Every method does nothing by default, so that an observer only
+/// implements the notifications it is interested in. Instruction fetches
+/// are not reported as memory reads.
+///
+/// An observer returned by [`Cpu::take_observer`](crate::Cpu::take_observer)
+/// can be converted to `Box<dyn Any>` and downcast to its own type.
+pub trait Observer: Any {
+    /// Called before the instruction at `address` is executed, with the
+    /// registers as they are before it. The program counter already
+    /// points past the instruction.
+    fn before_instruction(&mut self, _address: u16, _opcode: Opcode, _reg: &Registers) {}
+
+    /// Called on the Z80 just before [`Observer::before_instruction`], with
+    /// the registers that only exist on the Z80 and the state of IFF1, the
+    /// interrupt enable flip-flop.
+    fn before_z80_instruction(&mut self, _z80: &z80::State, _iff1: bool) {}
+
+    /// Called after an instruction was executed, with the registers
+    /// as it left them. Not called for instructions that faulted.
+    fn after_instruction(&mut self, _step: &Step, _reg: &Registers) {}
+
+    /// Called when an instruction reads `value` from memory at `adr`.
+    fn memory_read(&mut self, _adr: u16, _value: u8) {}
+
+    /// Called when an instruction writes `value` to memory at `adr`.
+    fn memory_write(&mut self, _adr: u16, _value: u8) {}
+
+    /// Called when an instruction reads `value` from `port`.
+    fn input(&mut self, _port: u8, _value: u8) {}
+
+    /// Called when an instruction writes `value` to `port`.
+    fn output(&mut self, _port: u8, _value: u8) {}
+
+    /// Called when the processor acknowledges an interrupt, before
+    /// executing the instruction supplied by the interrupting device.
+    fn interrupt(&mut self, _opcode: Opcode) {}
+
+    /// Called when the processor halts. `pc` is the address the
+    /// processor resumes at after an interrupt.
+    fn halt(&mut self, _pc: u16) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io::BufReader;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{Cpu, Memory, Variant};
+
+    /// Writes every notification to a log shared with the test.
+    #[derive(Clone, Default)]
+    struct Log(Rc<RefCell<Vec<String>>>);
+
+    impl Log {
+        fn push(&self, event: String) {
+            self.0.borrow_mut().push(event);
+        }
+
+        fn take(&self) -> Vec<String> {
+            self.0.take()
+        }
+    }
+
+    impl Observer for Log {
+        fn before_instruction(&mut self, address: u16, opcode: Opcode, reg: &Registers) {
+            self.push(format!("{:04x} {} pc={:04x}", address, opcode, reg.pc));
+        }
+
+        fn after_instruction(&mut self, step: &Step, reg: &Registers) {
+            self.push(format!("done {:04x} a={:02x}", step.address, reg.a));
+        }
+
+        fn memory_read(&mut self, adr: u16, value: u8) {
+            self.push(format!("read {:04x} {:02x}", adr, value));
+        }
+
+        fn memory_write(&mut self, adr: u16, value: u8) {
+            self.push(format!("write {:04x} {:02x}", adr, value));
+        }
+
+        fn input(&mut self, port: u8, value: u8) {
+            self.push(format!("in {:02x} {:02x}", port, value));
+        }
+
+        fn output(&mut self, port: u8, value: u8) {
+            self.push(format!("out {:02x} {:02x}", port, value));
+        }
+
+        fn interrupt(&mut self, opcode: Opcode) {
+            self.push(format!("interrupt {}", opcode));
+        }
+
+        fn halt(&mut self, pc: u16) {
+            self.push(format!("halt {:04x}", pc));
+        }
+    }
+
+    fn cpu(program: &[u8]) -> (Cpu, Log) {
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(program)).unwrap());
+        let log = Log::default();
+        cpu.set_observer(log.clone());
+        (cpu, log)
+    }
+
+    #[test]
+    fn instructions_and_their_accesses_are_reported() {
+        // MVI A,5; STA 0100H; LXI H,0100H; INR M; OUT 1; IN 2; HLT
+        let program = [0x3e, 0x05, 0x32, 0x00, 0x01, 0x21, 0x00, 0x01, 0x34, 0xd3, 0x01, 0xdb, 0x02, 0x76];
+        let (mut cpu, log) = cpu(&program);
+        while cpu.step().is_ok() {}
+        assert_eq!(log.take(), [
+            "0000 MVI A,05H pc=0002",
+            "done 0000 a=05",
+            "0002 STA 0100H pc=0005",
+            "write 0100 05",
+            "done 0002 a=05",
+            "0005 LXI H,0100H pc=0008",
+            "done 0005 a=05",
+            "0008 INR M pc=0009",
+            "read 0100 05",
+            "write 0100 06",
+            "done 0008 a=05",
+            "0009 OUT 01H pc=000b",
+            "out 01 05",
+            "done 0009 a=05",
+            "000b IN 02H pc=000d",
+            "in 02 ff",
+            "done 000b a=ff",
+            "000d HLT pc=000e",
+            "done 000d a=ff",
+            "halt 000e",
+        ]);
+    }
+
+    #[test]
+    fn interrupts_are_reported_before_their_instruction() {
+        // EI; HLT
+        let (mut cpu, log) = cpu(&[0xfb, 0x76]);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        log.take();
+        cpu.interrupt(Opcode::RST_7);
+        cpu.step().unwrap();
+        assert_eq!(log.take(), [
+            "interrupt RST 7",
+            "0002 RST 7 pc=0002",
+            "write ffff 00",
+            "write fffe 02",
+            "done 0002 a=00",
+        ]);
+    }
+
+    #[test]
+    fn z80_memory_operands_are_reported() {
+        // LD HL,0100H; SET 0,(HL)
+        let (mut cpu, log) = cpu(&[0x21, 0x00, 0x01, 0xcb, 0xc6]);
+        cpu.set_variant(Variant::Z80);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        let log = log.take();
+        assert_eq!(log[log.len() - 3..], ["read 0100 00", "write 0100 01", "done 0003 a=00"]);
+    }
+}
@@ -1,250 +1,636 @@
-#[repr(u8)]
-#[allow(non_camel_case_types)]
+use crate::{metadata, z80, Instruction, Metadata, Variant};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
 pub enum Opcode {
-    NOP = 0x00,
-    LXI_BC(u8, u8) = 0x01,
-    STAX_BC = 0x02,
-    INX_BC = 0x03,
-    INR_B = 0x04,
-    DCR_B = 0x05,
-    MVI_B(u8) = 0x06,
-    RLC = 0x07,
-    DAD_BC = 0x09,
-    LDAX_BC = 0x0a,
-    DCX_BC = 0x0b,
-    INR_C = 0x0c,
-    DCR_C = 0x0d,
-    MVI_C(u8) = 0x0e,
-    RRC = 0x0f,
-    LXI_DE(u8, u8) = 0x11,
-    STAX_DE = 0x12,
-    INX_DE = 0x13,
-    INR_D = 0x14,
-    DCR_D = 0x15,
-    MVI_D(u8) = 0x16,
-    RAL = 0x17,
-    DAD_DE = 0x19,
-    LDAX_DE = 0x1a,
-    DCX_DE = 0x1b,
-    INR_E = 0x1c,
-    DCR_E = 0x1d,
-    MVI_E(u8) = 0x1e,
-    RAR = 0x1f,
-    LXI_HL(u8, u8) = 0x21,
-    SHLD(u16) = 0x22,
-    INX_HL = 0x23,
-    INR_H = 0x24,
-    DCR_H = 0x25,
-    MVI_H(u8) = 0x26,
-    DAA = 0x27,
-    DAD_HL = 0x29,
-    LHLD(u16) = 0x2a,
-    DCX_HL = 0x2b,
-    INR_L = 0x2c,
-    DCR_L = 0x2d,
-    MVI_L(u8) = 0x2e,
-    CMA = 0x2f,
-    LXI_SP(u8, u8) = 0x31,
-    STA(u16) = 0x32,
-    INX_SP = 0x33,
-    INR_M = 0x34,
-    DCR_M = 0x35,
-    MVI_M(u8) = 0x36,
-    STC = 0x37,
-    DAD_SP = 0x39,
-    LDA(u16) = 0x3a,
-    DCX_SP = 0x3b,
-    INR_A = 0x3c,
-    DCR_A = 0x3d,
-    MVI_A(u8) = 0x3e,
-    CMC = 0x3f,
-    MOV_BB = 0x40,
-    MOV_BC = 0x41,
-    MOV_BD = 0x42,
-    MOV_BE = 0x43,
-    MOV_BH = 0x44,
-    MOV_BL = 0x45,
-    MOV_BM = 0x46,
-    MOV_BA = 0x47,
-    MOV_CB = 0x48,
-    MOV_CC = 0x49,
-    MOV_CD = 0x4a,
-    MOV_CE = 0x4b,
-    MOV_CH = 0x4c,
-    MOV_CL = 0x4d,
-    MOV_CM = 0x4e,
-    MOV_CA = 0x4f,
-    MOV_DB = 0x50,
-    MOV_DC = 0x51,
-    MOV_DD = 0x52,
-    MOV_DE = 0x53,
-    MOV_DH = 0x54,
-    MOV_DL = 0x55,
-    MOV_DM = 0x56,
-    MOV_DA = 0x57,
-    MOV_EB = 0x58,
-    MOV_EC = 0x59,
-    MOV_ED = 0x5a,
-    MOV_EE = 0x5b,
-    MOV_EH = 0x5c,
-    MOV_EL = 0x5d,
-    MOV_EM = 0x5e,
-    MOV_EA = 0x5f,
-    MOV_HB = 0x60,
-    MOV_HC = 0x61,
-    MOV_HD = 0x62,
-    MOV_HE = 0x63,
-    MOV_HH = 0x64,
-    MOV_HL = 0x65,
-    MOV_HM = 0x66,
-    MOV_HA = 0x67,
-    MOV_LB = 0x68,
-    MOV_LC = 0x69,
-    MOV_LD = 0x6a,
-    MOV_LE = 0x6b,
-    MOV_LH = 0x6c,
-    MOV_LL = 0x6d,
-    MOV_LM = 0x6e,
-    MOV_LA = 0x6f,
-    MOV_MB = 0x70,
-    MOV_MC = 0x71,
-    MOV_MD = 0x72,
-    MOV_ME = 0x73,
-    MOV_MH = 0x74,
-    MOV_ML = 0x75,
-    HLT = 0x76,
-    MOV_MA = 0x77,
-    MOV_AB = 0x78,
-    MOV_AC = 0x79,
-    MOV_AD = 0x7a,
-    MOV_AE = 0x7b,
-    MOV_AH = 0x7c,
-    MOV_AL = 0x7d,
-    MOV_AM = 0x7e,
-    MOV_AA = 0x7f,
-    ADD_B = 0x80,
-    ADD_C = 0x81,
-    ADD_D = 0x82,
-    ADD_E = 0x83,
-    ADD_H = 0x84,
-    ADD_L = 0x85,
-    ADD_M = 0x86,
-    ADD_A = 0x87,
-    ADC_B = 0x88,
-    ADC_C = 0x89,
-    ADC_D = 0x8a,
-    ADC_E = 0x8b,
-    ADC_H = 0x8c,
-    ADC_L = 0x8d,
-    ADC_M = 0x8e,
-    ADC_A = 0x8f,
-    SUB_B = 0x90,
-    SUB_C = 0x91,
-    SUB_D = 0x92,
-    SUB_E = 0x93,
-    SUB_H = 0x94,
-    SUB_L = 0x95,
-    SUB_M = 0x96,
-    SUB_A = 0x97,
-    SBB_B = 0x98,
-    SBB_C = 0x99,
-    SBB_D = 0x9a,
-    SBB_E = 0x9b,
-    SBB_H = 0x9c,
-    SBB_L = 0x9d,
-    SBB_M = 0x9e,
-    SBB_A = 0x9f,
-    ANA_B = 0xa0,
-    ANA_C = 0xa1,
-    ANA_D = 0xa2,
-    ANA_E = 0xa3,
-    ANA_H = 0xa4,
-    ANA_L = 0xa5,
-    ANA_M = 0xa6,
-    ANA_A = 0xa7,
-    XRA_B = 0xa8,
-    XRA_C = 0xa9,
-    XRA_D = 0xaa,
-    XRA_E = 0xab,
-    XRA_H = 0xac,
-    XRA_L = 0xad,
-    XRA_M = 0xae,
-    XRA_A = 0xaf,
-    ORA_B = 0xb0,
-    ORA_C = 0xb1,
-    ORA_D = 0xb2,
-    ORA_E = 0xb3,
-    ORA_H = 0xb4,
-    ORA_L = 0xb5,
-    ORA_M = 0xb6,
-    ORA_A = 0xb7,
-    CMP_B = 0xb8,
-    CMP_C = 0xb9,
-    CMP_D = 0xba,
-    CMP_E = 0xbb,
-    CMP_H = 0xbc,
-    CMP_L = 0xbd,
-    CMP_M = 0xbe,
-    CMP_A = 0xbf,
-    RNZ = 0xc0,
-    POP_BC = 0xc1,
-    JNZ(u16) = 0xc2,
-    JMP(u16) = 0xc3,
-    CNZ(u16) = 0xc4,
-    PUSH_BC = 0xc5,
-    ADI(u8) = 0xc6,
-    RST_0 = 0xc7,
-    RZ = 0xc8,
-    RET = 0xc9,
-    JZ(u16) = 0xca,
-    CZ(u16) = 0xcc,
-    CALL(u16) = 0xcd,
-    ACI(u8) = 0xce,
-    RST_1 = 0xcf,
-    RNC = 0xd0,
-    POP_DE = 0xd1,
-    JNC(u16) = 0xd2,
-    OUT(u8) = 0xd3,
-    CNC(u16) = 0xd4,
-    PUSH_DE = 0xd5,
-    SUI(u8) = 0xd6,
-    RST_2 = 0xd7,
-    RC = 0xd8,
-    JC(u16) = 0xda,
-    IN(u8) = 0xdb,
-    CC(u16) = 0xdc,
-    SBI(u8) = 0xde,
-    RST_3 = 0xdf,
-    RPO = 0xe0,
-    POP_HL = 0xe1,
-    JPO(u16) = 0xe2,
-    XTHL = 0xe3,
-    CPO(u16) = 0xe4,
-    PUSH_HL = 0xe5,
-    ANI(u8) = 0xe6,
-    RST_4 = 0xe7,
-    RPE = 0xe8,
-    PCHL = 0xe9,
-    JPE(u16) = 0xea,
-    XCHG = 0xeb,
-    CPE(u16) = 0xec,
-    XRI(u8) = 0xee,
-    RST_5 = 0xef,
-    RP = 0xf0,
-    POP_PSW = 0xf1,
-    JP(u16) = 0xf2,
-    DI = 0xf3,
-    CP(u16) = 0xf4,
-    PUSH_PSW = 0xf5,
-    ORI(u8) = 0xf6,
-    RST_6 = 0xf7,
-    RM = 0xf8,
-    SPHL = 0xf9,
-    JM(u16) = 0xfa,
-    EI = 0xfb,
-    CM(u16) = 0xfc,
-    CPI(u8) = 0xfe,
-    RST_7 = 0xff,
+    NOP,
+    LXI_BC(u8, u8),
+    STAX_BC,
+    INX_BC,
+    INR_B,
+    DCR_B,
+    MVI_B(u8),
+    RLC,
+    NOP_08,
+    DAD_BC,
+    LDAX_BC,
+    DCX_BC,
+    INR_C,
+    DCR_C,
+    MVI_C(u8),
+    RRC,
+    NOP_10,
+    LXI_DE(u8, u8),
+    STAX_DE,
+    INX_DE,
+    INR_D,
+    DCR_D,
+    MVI_D(u8),
+    RAL,
+    NOP_18,
+    DAD_DE,
+    LDAX_DE,
+    DCX_DE,
+    INR_E,
+    DCR_E,
+    MVI_E(u8),
+    RAR,
+    NOP_20,
+    LXI_HL(u8, u8),
+    SHLD(u16),
+    INX_HL,
+    INR_H,
+    DCR_H,
+    MVI_H(u8),
+    DAA,
+    NOP_28,
+    DAD_HL,
+    LHLD(u16),
+    DCX_HL,
+    INR_L,
+    DCR_L,
+    MVI_L(u8),
+    CMA,
+    NOP_30,
+    LXI_SP(u8, u8),
+    STA(u16),
+    INX_SP,
+    INR_M,
+    DCR_M,
+    MVI_M(u8),
+    STC,
+    NOP_38,
+    DAD_SP,
+    LDA(u16),
+    DCX_SP,
+    INR_A,
+    DCR_A,
+    MVI_A(u8),
+    CMC,
+    MOV_BB,
+    MOV_BC,
+    MOV_BD,
+    MOV_BE,
+    MOV_BH,
+    MOV_BL,
+    MOV_BM,
+    MOV_BA,
+    MOV_CB,
+    MOV_CC,
+    MOV_CD,
+    MOV_CE,
+    MOV_CH,
+    MOV_CL,
+    MOV_CM,
+    MOV_CA,
+    MOV_DB,
+    MOV_DC,
+    MOV_DD,
+    MOV_DE,
+    MOV_DH,
+    MOV_DL,
+    MOV_DM,
+    MOV_DA,
+    MOV_EB,
+    MOV_EC,
+    MOV_ED,
+    MOV_EE,
+    MOV_EH,
+    MOV_EL,
+    MOV_EM,
+    MOV_EA,
+    MOV_HB,
+    MOV_HC,
+    MOV_HD,
+    MOV_HE,
+    MOV_HH,
+    MOV_HL,
+    MOV_HM,
+    MOV_HA,
+    MOV_LB,
+    MOV_LC,
+    MOV_LD,
+    MOV_LE,
+    MOV_LH,
+    MOV_LL,
+    MOV_LM,
+    MOV_LA,
+    MOV_MB,
+    MOV_MC,
+    MOV_MD,
+    MOV_ME,
+    MOV_MH,
+    MOV_ML,
+    HLT,
+    MOV_MA,
+    MOV_AB,
+    MOV_AC,
+    MOV_AD,
+    MOV_AE,
+    MOV_AH,
+    MOV_AL,
+    MOV_AM,
+    MOV_AA,
+    ADD_B,
+    ADD_C,
+    ADD_D,
+    ADD_E,
+    ADD_H,
+    ADD_L,
+    ADD_M,
+    ADD_A,
+    ADC_B,
+    ADC_C,
+    ADC_D,
+    ADC_E,
+    ADC_H,
+    ADC_L,
+    ADC_M,
+    ADC_A,
+    SUB_B,
+    SUB_C,
+    SUB_D,
+    SUB_E,
+    SUB_H,
+    SUB_L,
+    SUB_M,
+    SUB_A,
+    SBB_B,
+    SBB_C,
+    SBB_D,
+    SBB_E,
+    SBB_H,
+    SBB_L,
+    SBB_M,
+    SBB_A,
+    ANA_B,
+    ANA_C,
+    ANA_D,
+    ANA_E,
+    ANA_H,
+    ANA_L,
+    ANA_M,
+    ANA_A,
+    XRA_B,
+    XRA_C,
+    XRA_D,
+    XRA_E,
+    XRA_H,
+    XRA_L,
+    XRA_M,
+    XRA_A,
+    ORA_B,
+    ORA_C,
+    ORA_D,
+    ORA_E,
+    ORA_H,
+    ORA_L,
+    ORA_M,
+    ORA_A,
+    CMP_B,
+    CMP_C,
+    CMP_D,
+    CMP_E,
+    CMP_H,
+    CMP_L,
+    CMP_M,
+    CMP_A,
+    RNZ,
+    POP_BC,
+    JNZ(u16),
+    JMP(u16),
+    CNZ(u16),
+    PUSH_BC,
+    ADI(u8),
+    RST_0,
+    RZ,
+    RET,
+    JZ(u16),
+    JMP_CB(u16),
+    CZ(u16),
+    CALL(u16),
+    ACI(u8),
+    RST_1,
+    RNC,
+    POP_DE,
+    JNC(u16),
+    OUT(u8),
+    CNC(u16),
+    PUSH_DE,
+    SUI(u8),
+    RST_2,
+    RC,
+    RET_D9,
+    JC(u16),
+    IN(u8),
+    CC(u16),
+    CALL_DD(u16),
+    SBI(u8),
+    RST_3,
+    RPO,
+    POP_HL,
+    JPO(u16),
+    XTHL,
+    CPO(u16),
+    PUSH_HL,
+    ANI(u8),
+    RST_4,
+    RPE,
+    PCHL,
+    JPE(u16),
+    XCHG,
+    CPE(u16),
+    CALL_ED(u16),
+    XRI(u8),
+    RST_5,
+    RP,
+    POP_PSW,
+    JP(u16),
+    DI,
+    CP(u16),
+    PUSH_PSW,
+    ORI(u8),
+    RST_6,
+    RM,
+    SPHL,
+    JM(u16),
+    EI,
+    CM(u16),
+    CALL_FD(u16),
+    CPI(u8),
+    RST_7,
+
+    // Intel 8085 only
+    RIM,
+    SIM,
+    DSUB,
+    ARHL,
+    RDEL,
+    LDHI(u8),
+    LDSI(u8),
+    RSTV,
+    SHLX,
+    JNK(u16),
+    LHLX,
+    JK(u16),
+
+    // Zilog Z80 only
+    Z80(z80::Opcode),
+}
+
+impl Opcode {
+    /// Decodes a single instruction for the given processor, pulling
+    /// the opcode byte and its operands from `next` one by one.
+    pub(crate) fn read(variant: Variant, mut next: impl FnMut() -> u8) -> Opcode {
+        fn word(next: &mut impl FnMut() -> u8) -> u16 {
+            let rl = next();
+            let rh = next();
+            ((rh as u16) << 8) | (rl as u16)
+        }
+
+        let byte = next();
+        if variant == Variant::Z80 {
+            if let Some(opcode) = z80::Opcode::read(byte, &mut next) {
+                return Opcode::Z80(opcode);
+            }
+        }
+        if variant == Variant::I8085 {
+            // The 8085 assigns most of the opcodes the 8080 leaves undocumented.
+            match byte {
+                0x08 => return Opcode::DSUB,
+                0x10 => return Opcode::ARHL,
+                0x18 => return Opcode::RDEL,
+                0x20 => return Opcode::RIM,
+                0x28 => return Opcode::LDHI(next()),
+                0x30 => return Opcode::SIM,
+                0x38 => return Opcode::LDSI(next()),
+                0xcb => return Opcode::RSTV,
+                0xd9 => return Opcode::SHLX,
+                0xdd => return Opcode::JNK(word(&mut next)),
+                0xed => return Opcode::LHLX,
+                0xfd => return Opcode::JK(word(&mut next)),
+                _ => {},
+            }
+        }
+
+        match byte {
+            0x00 => Opcode::NOP,
+            0x01 => Opcode::LXI_BC(next(), next()),
+            0x02 => Opcode::STAX_BC,
+            0x03 => Opcode::INX_BC,
+            0x04 => Opcode::INR_B,
+            0x05 => Opcode::DCR_B,
+            0x06 => Opcode::MVI_B(next()),
+            0x07 => Opcode::RLC,
+            0x08 => Opcode::NOP_08,
+            0x09 => Opcode::DAD_BC,
+            0x0a => Opcode::LDAX_BC,
+            0x0b => Opcode::DCX_BC,
+            0x0c => Opcode::INR_C,
+            0x0d => Opcode::DCR_C,
+            0x0e => Opcode::MVI_C(next()),
+            0x0f => Opcode::RRC,
+            0x10 => Opcode::NOP_10,
+            0x11 => Opcode::LXI_DE(next(), next()),
+            0x12 => Opcode::STAX_DE,
+            0x13 => Opcode::INX_DE,
+            0x14 => Opcode::INR_D,
+            0x15 => Opcode::DCR_D,
+            0x16 => Opcode::MVI_D(next()),
+            0x17 => Opcode::RAL,
+            0x18 => Opcode::NOP_18,
+            0x19 => Opcode::DAD_DE,
+            0x1a => Opcode::LDAX_DE,
+            0x1b => Opcode::DCX_DE,
+            0x1c => Opcode::INR_E,
+            0x1d => Opcode::DCR_E,
+            0x1e => Opcode::MVI_E(next()),
+            0x1f => Opcode::RAR,
+            0x20 => Opcode::NOP_20,
+            0x21 => Opcode::LXI_HL(next(), next()),
+            0x22 => Opcode::SHLD(word(&mut next)),
+            0x23 => Opcode::INX_HL,
+            0x24 => Opcode::INR_H,
+            0x25 => Opcode::DCR_H,
+            0x26 => Opcode::MVI_H(next()),
+            0x27 => Opcode::DAA,
+            0x28 => Opcode::NOP_28,
+            0x29 => Opcode::DAD_HL,
+            0x2a => Opcode::LHLD(word(&mut next)),
+            0x2b => Opcode::DCX_HL,
+            0x2c => Opcode::INR_L,
+            0x2d => Opcode::DCR_L,
+            0x2e => Opcode::MVI_L(next()),
+            0x2f => Opcode::CMA,
+            0x30 => Opcode::NOP_30,
+            0x31 => Opcode::LXI_SP(next(), next()),
+            0x32 => Opcode::STA(word(&mut next)),
+            0x33 => Opcode::INX_SP,
+            0x34 => Opcode::INR_M,
+            0x35 => Opcode::DCR_M,
+            0x36 => Opcode::MVI_M(next()),
+            0x37 => Opcode::STC,
+            0x38 => Opcode::NOP_38,
+            0x39 => Opcode::DAD_SP,
+            0x3a => Opcode::LDA(word(&mut next)),
+            0x3b => Opcode::DCX_SP,
+            0x3c => Opcode::INR_A,
+            0x3d => Opcode::DCR_A,
+            0x3e => Opcode::MVI_A(next()),
+            0x3f => Opcode::CMC,
+            0x40 => Opcode::MOV_BB,
+            0x41 => Opcode::MOV_BC,
+            0x42 => Opcode::MOV_BD,
+            0x43 => Opcode::MOV_BE,
+            0x44 => Opcode::MOV_BH,
+            0x45 => Opcode::MOV_BL,
+            0x46 => Opcode::MOV_BM,
+            0x47 => Opcode::MOV_BA,
+            0x48 => Opcode::MOV_CB,
+            0x49 => Opcode::MOV_CC,
+            0x4a => Opcode::MOV_CD,
+            0x4b => Opcode::MOV_CE,
+            0x4c => Opcode::MOV_CH,
+            0x4d => Opcode::MOV_CL,
+            0x4e => Opcode::MOV_CM,
+            0x4f => Opcode::MOV_CA,
+            0x50 => Opcode::MOV_DB,
+            0x51 => Opcode::MOV_DC,
+            0x52 => Opcode::MOV_DD,
+            0x53 => Opcode::MOV_DE,
+            0x54 => Opcode::MOV_DH,
+            0x55 => Opcode::MOV_DL,
+            0x56 => Opcode::MOV_DM,
+            0x57 => Opcode::MOV_DA,
+            0x58 => Opcode::MOV_EB,
+            0x59 => Opcode::MOV_EC,
+            0x5a => Opcode::MOV_ED,
+            0x5b => Opcode::MOV_EE,
+            0x5c => Opcode::MOV_EH,
+            0x5d => Opcode::MOV_EL,
+            0x5e => Opcode::MOV_EM,
+            0x5f => Opcode::MOV_EA,
+            0x60 => Opcode::MOV_HB,
+            0x61 => Opcode::MOV_HC,
+            0x62 => Opcode::MOV_HD,
+            0x63 => Opcode::MOV_HE,
+            0x64 => Opcode::MOV_HH,
+            0x65 => Opcode::MOV_HL,
+            0x66 => Opcode::MOV_HM,
+            0x67 => Opcode::MOV_HA,
+            0x68 => Opcode::MOV_LB,
+            0x69 => Opcode::MOV_LC,
+            0x6a => Opcode::MOV_LD,
+            0x6b => Opcode::MOV_LE,
+            0x6c => Opcode::MOV_LH,
+            0x6d => Opcode::MOV_LL,
+            0x6e => Opcode::MOV_LM,
+            0x6f => Opcode::MOV_LA,
+            0x70 => Opcode::MOV_MB,
+            0x71 => Opcode::MOV_MC,
+            0x72 => Opcode::MOV_MD,
+            0x73 => Opcode::MOV_ME,
+            0x74 => Opcode::MOV_MH,
+            0x75 => Opcode::MOV_ML,
+            0x76 => Opcode::HLT,
+            0x77 => Opcode::MOV_MA,
+            0x78 => Opcode::MOV_AB,
+            0x79 => Opcode::MOV_AC,
+            0x7a => Opcode::MOV_AD,
+            0x7b => Opcode::MOV_AE,
+            0x7c => Opcode::MOV_AH,
+            0x7d => Opcode::MOV_AL,
+            0x7e => Opcode::MOV_AM,
+            0x7f => Opcode::MOV_AA,
+            0x80 => Opcode::ADD_B,
+            0x81 => Opcode::ADD_C,
+            0x82 => Opcode::ADD_D,
+            0x83 => Opcode::ADD_E,
+            0x84 => Opcode::ADD_H,
+            0x85 => Opcode::ADD_L,
+            0x86 => Opcode::ADD_M,
+            0x87 => Opcode::ADD_A,
+            0x88 => Opcode::ADC_B,
+            0x89 => Opcode::ADC_C,
+            0x8a => Opcode::ADC_D,
+            0x8b => Opcode::ADC_E,
+            0x8c => Opcode::ADC_H,
+            0x8d => Opcode::ADC_L,
+            0x8e => Opcode::ADC_M,
+            0x8f => Opcode::ADC_A,
+            0x90 => Opcode::SUB_B,
+            0x91 => Opcode::SUB_C,
+            0x92 => Opcode::SUB_D,
+            0x93 => Opcode::SUB_E,
+            0x94 => Opcode::SUB_H,
+            0x95 => Opcode::SUB_L,
+            0x96 => Opcode::SUB_M,
+            0x97 => Opcode::SUB_A,
+            0x98 => Opcode::SBB_B,
+            0x99 => Opcode::SBB_C,
+            0x9a => Opcode::SBB_D,
+            0x9b => Opcode::SBB_E,
+            0x9c => Opcode::SBB_H,
+            0x9d => Opcode::SBB_L,
+            0x9e => Opcode::SBB_M,
+            0x9f => Opcode::SBB_A,
+            0xa0 => Opcode::ANA_B,
+            0xa1 => Opcode::ANA_C,
+            0xa2 => Opcode::ANA_D,
+            0xa3 => Opcode::ANA_E,
+            0xa4 => Opcode::ANA_H,
+            0xa5 => Opcode::ANA_L,
+            0xa6 => Opcode::ANA_M,
+            0xa7 => Opcode::ANA_A,
+            0xa8 => Opcode::XRA_B,
+            0xa9 => Opcode::XRA_C,
+            0xaa => Opcode::XRA_D,
+            0xab => Opcode::XRA_E,
+            0xac => Opcode::XRA_H,
+            0xad => Opcode::XRA_L,
+            0xae => Opcode::XRA_M,
+            0xaf => Opcode::XRA_A,
+            0xb0 => Opcode::ORA_B,
+            0xb1 => Opcode::ORA_C,
+            0xb2 => Opcode::ORA_D,
+            0xb3 => Opcode::ORA_E,
+            0xb4 => Opcode::ORA_H,
+            0xb5 => Opcode::ORA_L,
+            0xb6 => Opcode::ORA_M,
+            0xb7 => Opcode::ORA_A,
+            0xb8 => Opcode::CMP_B,
+            0xb9 => Opcode::CMP_C,
+            0xba => Opcode::CMP_D,
+            0xbb => Opcode::CMP_E,
+            0xbc => Opcode::CMP_H,
+            0xbd => Opcode::CMP_L,
+            0xbe => Opcode::CMP_M,
+            0xbf => Opcode::CMP_A,
+            0xc0 => Opcode::RNZ,
+            0xc1 => Opcode::POP_BC,
+            0xc2 => Opcode::JNZ(word(&mut next)),
+            0xc3 => Opcode::JMP(word(&mut next)),
+            0xc4 => Opcode::CNZ(word(&mut next)),
+            0xc5 => Opcode::PUSH_BC,
+            0xc6 => Opcode::ADI(next()),
+            0xc7 => Opcode::RST_0,
+            0xc8 => Opcode::RZ,
+            0xc9 => Opcode::RET,
+            0xca => Opcode::JZ(word(&mut next)),
+            0xcb => Opcode::JMP_CB(word(&mut next)),
+            0xcc => Opcode::CZ(word(&mut next)),
+            0xcd => Opcode::CALL(word(&mut next)),
+            0xce => Opcode::ACI(next()),
+            0xcf => Opcode::RST_1,
+            0xd0 => Opcode::RNC,
+            0xd1 => Opcode::POP_DE,
+            0xd2 => Opcode::JNC(word(&mut next)),
+            0xd3 => Opcode::OUT(next()),
+            0xd4 => Opcode::CNC(word(&mut next)),
+            0xd5 => Opcode::PUSH_DE,
+            0xd6 => Opcode::SUI(next()),
+            0xd7 => Opcode::RST_2,
+            0xd8 => Opcode::RC,
+            0xd9 => Opcode::RET_D9,
+            0xda => Opcode::JC(word(&mut next)),
+            0xdb => Opcode::IN(next()),
+            0xdc => Opcode::CC(word(&mut next)),
+            0xdd => Opcode::CALL_DD(word(&mut next)),
+            0xde => Opcode::SBI(next()),
+            0xdf => Opcode::RST_3,
+            0xe0 => Opcode::RPO,
+            0xe1 => Opcode::POP_HL,
+            0xe2 => Opcode::JPO(word(&mut next)),
+            0xe3 => Opcode::XTHL,
+            0xe4 => Opcode::CPO(word(&mut next)),
+            0xe5 => Opcode::PUSH_HL,
+            0xe6 => Opcode::ANI(next()),
+            0xe7 => Opcode::RST_4,
+            0xe8 => Opcode::RPE,
+            0xe9 => Opcode::PCHL,
+            0xea => Opcode::JPE(word(&mut next)),
+            0xeb => Opcode::XCHG,
+            0xec => Opcode::CPE(word(&mut next)),
+            0xed => Opcode::CALL_ED(word(&mut next)),
+            0xee => Opcode::XRI(next()),
+            0xef => Opcode::RST_5,
+            0xf0 => Opcode::RP,
+            0xf1 => Opcode::POP_PSW,
+            0xf2 => Opcode::JP(word(&mut next)),
+            0xf3 => Opcode::DI,
+            0xf4 => Opcode::CP(word(&mut next)),
+            0xf5 => Opcode::PUSH_PSW,
+            0xf6 => Opcode::ORI(next()),
+            0xf7 => Opcode::RST_6,
+            0xf8 => Opcode::RM,
+            0xf9 => Opcode::SPHL,
+            0xfa => Opcode::JM(word(&mut next)),
+            0xfb => Opcode::EI,
+            0xfc => Opcode::CM(word(&mut next)),
+            0xfd => Opcode::CALL_FD(word(&mut next)),
+            0xfe => Opcode::CPI(next()),
+            0xff => Opcode::RST_7,
+        }
+    }
+
+    /// Encodes the instruction as the opcode byte followed by its
+    /// operands, with 16-bit operands in little-endian order.
+    pub fn encode(&self) -> Vec<u8> {
+        Instruction::from(*self).encode()
+    }
+
+    /// Whether this is one of the undocumented opcodes, which the 8080
+    /// decodes as an alias of a documented instruction.
+    pub fn is_undocumented(&self) -> bool {
+        use Opcode::*;
+        matches!(
+            self,
+            NOP_08 | NOP_10 | NOP_18 | NOP_20 | NOP_28 | NOP_30 | NOP_38
+                | JMP_CB(_) | RET_D9 | CALL_DD(_) | CALL_ED(_) | CALL_FD(_)
+                | DSUB | ARHL | RDEL | LDHI(_) | LDSI(_) | RSTV | SHLX | JNK(_) | LHLX | JK(_)
+        )
+    }
+
+    /// Whether the given processor is able to execute this instruction.
+    pub fn supported_by(&self, variant: Variant) -> bool {
+        use Opcode::*;
+        let i8085 = matches!(
+            self,
+            RIM | SIM | DSUB | ARHL | RDEL | LDHI(_) | LDSI(_) | RSTV | SHLX | JNK(_) | LHLX | JK(_)
+        );
+        match self {
+            Z80(_) => variant == Variant::Z80,
+            _ => !i8085 || variant == Variant::I8085,
+        }
+    }
+
+    /// Number of states (clock periods) the instruction takes to execute on
+    /// the given processor. For conditional instructions this is the duration
+    /// when the condition is not met, see [`Opcode::states_taken`].
+    pub fn states(&self, variant: Variant) -> u32 {
+        match self {
+            Opcode::Z80(opcode) => opcode.states(),
+            _ => self.metadata(variant).map_or(4, |metadata| metadata.states as u32),
+        }
+    }
+
+    /// Number of states the instruction takes when its condition is met.
+    /// Equal to [`Opcode::states`] for unconditional instructions.
+    pub fn states_taken(&self, variant: Variant) -> u32 {
+        match self {
+            Opcode::Z80(opcode) => opcode.states_taken(),
+            _ => self.metadata(variant).map_or(4, |metadata| metadata.states_taken as u32),
+        }
+    }
+
+    /// The static facts about the instruction on the given processor,
+    /// or `None` for the Z80 only instructions.
+    pub fn metadata(&self, variant: Variant) -> Option<&'static Metadata> {
+        match self {
+            Opcode::Z80(_) => None,
+            _ => metadata(variant, Instruction::from(*self).opcode()),
+        }
+    }
 }
 
 impl std::fmt::Debug for Opcode {
@@ -258,6 +644,7 @@ impl std::fmt::Debug for Opcode {
             Opcode::DCR_B => f.write_str("DCR B"),
             Opcode::MVI_B(d8) => f.write_fmt(format_args!("MVI B,{:#06x}", d8)),
             Opcode::RLC => f.write_str("RLC"),
+            Opcode::NOP_08 => f.write_str("*NOP"),
             Opcode::DAD_BC => f.write_str("DAD B"),
             Opcode::LDAX_BC => f.write_str("LDAX B"),
             Opcode::DCX_BC => f.write_str("DCX B"),
@@ -265,6 +652,7 @@ impl std::fmt::Debug for Opcode {
             Opcode::DCR_C => f.write_str("DCR C"),
             Opcode::MVI_C(d8) => f.write_fmt(format_args!("MVI C,{:#06x}", d8)),
             Opcode::RRC => f.write_str("RRC"),
+            Opcode::NOP_10 => f.write_str("*NOP"),
             Opcode::LXI_DE(rl, rh) => f.write_fmt(format_args!("LXI DE,{:#06x}{:04x}", rl, rh)),
             Opcode::STAX_DE => f.write_str("STAX D"),
             Opcode::INX_DE => f.write_str("INX D"),
@@ -272,6 +660,7 @@ impl std::fmt::Debug for Opcode {
             Opcode::DCR_D => f.write_str("DCR D"),
             Opcode::MVI_D(d8) => f.write_fmt(format_args!("MVI D,{:#06x}", d8)),
             Opcode::RAL => f.write_str("RAL"),
+            Opcode::NOP_18 => f.write_str("*NOP"),
             Opcode::DAD_DE => f.write_str("DAD D"),
             Opcode::LDAX_DE => f.write_str("LDAX D"),
             Opcode::DCX_DE => f.write_str("DCX D"),
@@ -279,6 +668,7 @@ impl std::fmt::Debug for Opcode {
             Opcode::DCR_E => f.write_str("DCR E"),
             Opcode::MVI_E(d8) => f.write_fmt(format_args!("MVI E,{:#06x}", d8)),
             Opcode::RAR => f.write_str("RAR"),
+            Opcode::NOP_20 => f.write_str("*NOP"),
             Opcode::LXI_HL(rl, rh) => f.write_fmt(format_args!("LXI HL,{:#06x}{:04x}", rl, rh)),
             Opcode::SHLD(adr) => f.write_fmt(format_args!("SHLD {:#010x}", adr)),
             Opcode::INX_HL => f.write_str("INX H"),
@@ -286,6 +676,7 @@ impl std::fmt::Debug for Opcode {
             Opcode::DCR_H => f.write_str("DCR H"),
             Opcode::MVI_H(d8) => f.write_fmt(format_args!("MVI {:#06x}", d8)),
             Opcode::DAA => f.write_str("DDA"),
+            Opcode::NOP_28 => f.write_str("*NOP"),
             Opcode::DAD_HL => f.write_str("DAD H"),
             Opcode::LHLD(adr) => f.write_fmt(format_args!("LHLD {:#010x}", adr)),
             Opcode::DCX_HL => f.write_str("DCX H"),
@@ -293,6 +684,7 @@ impl std::fmt::Debug for Opcode {
             Opcode::DCR_L => f.write_str("DCR L"),
             Opcode::MVI_L(d8) => f.write_fmt(format_args!("MVI L,{:#06x}", d8)),
             Opcode::CMA => f.write_str("CMA"),
+            Opcode::NOP_30 => f.write_str("*NOP"),
             Opcode::LXI_SP(rl, rh) => f.write_fmt(format_args!("LXI SP,{:#06x}{:04x}", rh, rl)),
             Opcode::STA(adr) => f.write_fmt(format_args!("STA {:#010x}", adr)),
             Opcode::INX_SP => f.write_str("INX SP"),
@@ -300,6 +692,7 @@ impl std::fmt::Debug for Opcode {
             Opcode::DCR_M => f.write_str("DCR M"),
             Opcode::MVI_M(d8) => f.write_fmt(format_args!("MVI M,{:#06x}", d8)),
             Opcode::STC => f.write_str("STC"),
+            Opcode::NOP_38 => f.write_str("*NOP"),
             Opcode::DAD_SP => f.write_str("DAD SP"),
             Opcode::LDA(adr) => f.write_fmt(format_args!("LDA {:#010x}", adr)),
             Opcode::DCX_SP => f.write_str("DCX SP"),
@@ -446,6 +839,7 @@ impl std::fmt::Debug for Opcode {
             Opcode::RZ => f.write_str("RZ"),
             Opcode::RET => f.write_str("RET"),
             Opcode::JZ(adr) => f.write_fmt(format_args!("JZ {:#010x}", adr)),
+            Opcode::JMP_CB(adr) => f.write_fmt(format_args!("*JMP {:#010x}", adr)),
             Opcode::CZ(adr) => f.write_fmt(format_args!("CZ {:#010x}", adr)),
             Opcode::CALL(adr) => f.write_fmt(format_args!("CALL {:#010x}", adr)),
             Opcode::ACI(d8) => f.write_fmt(format_args!("ACI {:#06x}", d8)),
@@ -459,9 +853,11 @@ impl std::fmt::Debug for Opcode {
             Opcode::SUI(d8)  => f.write_fmt(format_args!("SUI {:#06x}", d8)),
             Opcode::RST_2 => f.write_str("RST 2"),
             Opcode::RC => f.write_str("RC"),
+            Opcode::RET_D9 => f.write_str("*RET"),
             Opcode::JC(adr) => f.write_fmt(format_args!("JC {:#010x}", adr)),
             Opcode::IN(d8) => f.write_fmt(format_args!("IN {:#06x}", d8)),
             Opcode::CC(adr) => f.write_fmt(format_args!("CC {:#010x}", adr)),
+            Opcode::CALL_DD(adr) => f.write_fmt(format_args!("*CALL {:#010x}", adr)),
             Opcode::SBI(d8) => f.write_fmt(format_args!("SBI {:#06x}", d8)),
             Opcode::RST_3 => f.write_str("RST 3"),
             Opcode::RPO => f.write_str("RP 0"),
@@ -477,6 +873,7 @@ impl std::fmt::Debug for Opcode {
             Opcode::JPE(adr) => f.write_fmt(format_args!("JPE {:#010x}", adr)),
             Opcode::XCHG => f.write_str("XCHG"),
             Opcode::CPE(adr) => f.write_fmt(format_args!("CPE {:#010x}", adr)),
+            Opcode::CALL_ED(adr) => f.write_fmt(format_args!("*CALL {:#010x}", adr)),
             Opcode::XRI(d8)  => f.write_fmt(format_args!("XRI {:#06x}", d8)),
             Opcode::RST_5 => f.write_str("RST 5"),
             Opcode::RP => f.write_str("RP"),
@@ -492,8 +889,76 @@ impl std::fmt::Debug for Opcode {
             Opcode::JM(adr) => f.write_fmt(format_args!("JM {:#010x}", adr)),
             Opcode::EI => f.write_str("EI"),
             Opcode::CM(adr) => f.write_fmt(format_args!("CM {:#010x}", adr)),
+            Opcode::CALL_FD(adr) => f.write_fmt(format_args!("*CALL {:#010x}", adr)),
             Opcode::CPI(d8) => f.write_fmt(format_args!("CPI {:#06x}", d8)),
             Opcode::RST_7 => f.write_str("RST 7"),
+
+            Opcode::RIM => f.write_str("RIM"),
+            Opcode::SIM => f.write_str("SIM"),
+            Opcode::DSUB => f.write_str("DSUB"),
+            Opcode::ARHL => f.write_str("ARHL"),
+            Opcode::RDEL => f.write_str("RDEL"),
+            Opcode::LDHI(d8) => f.write_fmt(format_args!("LDHI {:#06x}", d8)),
+            Opcode::LDSI(d8) => f.write_fmt(format_args!("LDSI {:#06x}", d8)),
+            Opcode::RSTV => f.write_str("RSTV"),
+            Opcode::SHLX => f.write_str("SHLX"),
+            Opcode::JNK(adr) => f.write_fmt(format_args!("JNK {:#010x}", adr)),
+            Opcode::LHLX => f.write_str("LHLX"),
+            Opcode::JK(adr) => f.write_fmt(format_args!("JK {:#010x}", adr)),
+
+            Opcode::Z80(opcode) => opcode.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_twelve_undocumented_bytes_decode_as_aliases() {
+        let undocumented = [0x08, 0x10, 0x18, 0x20, 0x28, 0x30, 0x38, 0xcb, 0xd9, 0xdd, 0xed, 0xfd];
+        for byte in 0..=0xff {
+            let mut bytes = [byte, 0x34, 0x12].into_iter();
+            let opcode = Opcode::read(Variant::I8080, || bytes.next().unwrap());
+            assert_eq!(opcode.is_undocumented(), undocumented.contains(&byte), "{:02x}", byte);
+        }
+    }
+
+    /// Every instruction encoding: all opcode bytes, and for the Z80 all
+    /// second bytes of the CB, ED, DD and FD pages and all fourth bytes
+    /// of the DD CB and FD CB pages, followed by arbitrary operands.
+    fn encodings(variant: Variant) -> Vec<Vec<u8>> {
+        let operands = [0x12, 0x34, 0x56];
+        let mut encodings: Vec<Vec<u8>> = (0..=0xff).map(|byte| [&[byte][..], &operands].concat()).collect();
+        if variant == Variant::Z80 {
+            for prefix in [0xcb, 0xed, 0xdd, 0xfd] {
+                encodings.extend((0..=0xff).map(|byte| [&[prefix, byte][..], &operands].concat()));
+            }
+            for prefix in [0xdd, 0xfd] {
+                encodings.extend((0..=0xff).map(|byte| vec![prefix, 0xcb, 0x80, byte]));
+            }
+        }
+        encodings
+    }
+
+    #[test]
+    fn decoding_the_encoding_gives_the_same_instruction() {
+        for variant in [Variant::I8080, Variant::I8085, Variant::Z80] {
+            for bytes in encodings(variant) {
+                let (opcode, len) = crate::decode_with(variant, &bytes).unwrap();
+                let encoded = opcode.encode();
+
+                // The undocumented mirrors of NEG, RETN and IM in the ED page
+                // are encoded as the documented instruction.
+                let mirror = variant == Variant::Z80 && bytes[0] == 0xed && encoded[1] != bytes[1];
+                assert!(mirror || encoded == bytes[..len], "{:?} {:?}", variant, opcode);
+
+                // A NONI is only recognized by the prefix that follows it.
+                let stream = [&encoded[..], &bytes[len..]].concat();
+                let decoded = crate::decode_with(variant, &stream).unwrap();
+                assert!(decoded == (opcode, encoded.len()), "{:?}", opcode);
+            }
         }
     }
 }
@@ -0,0 +1,795 @@
+use std::io::{self, Read, Write};
+
+use crate::snapshot::{pack_flags, read_u16, read_u8, unpack_flags};
+use crate::trace::write_line;
+use crate::z80::{self, InterruptMode};
+use crate::{decode_with, Observer, Opcode, Registers, Step, Variant};
+
+/// Identifies a binary trace.
+const MAGIC: [u8; 4] = *b"8TRC";
+
+/// Version of the binary trace format. Bumped whenever the layout of the
+/// records changes, so that traces are not misread.
+pub const VERSION: u16 = 2;
+
+// Every record starts with a tag. Instruction tags have the top bit set,
+// a bit telling whether an interrupting device supplied the instruction,
+// and the number of bytes of the instruction in the low bits.
+const INSTRUCTION: u8 = 0x80;
+const INTERRUPT: u8 = 0x08;
+const LEN: u8 = 0x07;
+const WRITE: u8 = 0x01;
+const IN: u8 = 0x02;
+const OUT: u8 = 0x03;
+const HALT: u8 = 0x04;
+
+// Bits of the mask following an instruction tag, telling which registers
+// are stored because they changed since the previous instruction. The
+// address is only stored when the previous instruction did not fall
+// through to it.
+const PC: u8 = 0x01;
+const A: u8 = 0x02;
+const F: u8 = 0x04;
+const BC: u8 = 0x08;
+const DE: u8 = 0x10;
+const HL: u8 = 0x20;
+const SP: u8 = 0x40;
+
+// Bits of the mask that follows the number of states in the traces of a
+// Z80, telling which of the registers only the Z80 has are stored. The
+// refresh register changes with every instruction, so I and R are almost
+// always stored, which is cheaper than a bit for each.
+const IX: u8 = 0x01;
+const IY: u8 = 0x02;
+const AF_ALT: u8 = 0x04;
+const BC_ALT: u8 = 0x08;
+const DE_ALT: u8 = 0x10;
+const HL_ALT: u8 = 0x20;
+const IR: u8 = 0x40;
+const IFF: u8 = 0x80;
+
+/// Something that happened while an instruction was executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// `value` was written to memory at `adr`.
+    Write { adr: u16, value: u8 },
+    /// `value` was read from `port`.
+    In { port: u8, value: u8 },
+    /// `value` was written to `port`.
+    Out { port: u8, value: u8 },
+    /// The processor halted, to resume at `pc` after an interrupt.
+    Halt { pc: u16 },
+}
+
+/// Registers and interrupt state that only exist on the Z80,
+/// as stored in a binary trace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Z80Registers {
+    pub ix: u16,
+    pub iy: u16,
+    pub i: u8,
+    pub r: u8,
+    /// The alternate register set, with the alternate accumulator
+    /// and flags. Its program counter and stack pointer are unused.
+    pub alt: Registers,
+    pub iff1: bool,
+    pub iff2: bool,
+    pub im: InterruptMode,
+}
+
+impl Z80Registers {
+    fn new(state: &z80::State, iff1: bool) -> Self {
+        let alt = Registers { a: state.alt_a, flags: state.alt_flags, ..state.alt };
+        let (ix, iy, i, r) = (state.ix, state.iy, state.i, state.r);
+        Z80Registers { ix, iy, i, r, alt, iff1, iff2: state.iff2, im: state.im }
+    }
+
+    /// Packs the interrupt flip-flops and mode in a byte.
+    fn iff(&self) -> u8 {
+        let im = match self.im {
+            InterruptMode::Mode0 => 0,
+            InterruptMode::Mode1 => 1,
+            InterruptMode::Mode2 => 2,
+        };
+        let mut iff = im << 2;
+        iff |= self.iff1 as u8;
+        iff |= (self.iff2 as u8) << 1;
+        iff
+    }
+}
+
+/// An executed instruction, as read from a binary trace.
+#[derive(Clone, Debug)]
+pub struct TraceRecord {
+    /// Number of the instruction, counted from the start of the recording.
+    pub index: u64,
+    /// Address the instruction was fetched from.
+    pub address: u16,
+    /// The decoded instruction.
+    pub opcode: Opcode,
+    /// Whether the instruction was supplied by an interrupting device.
+    pub interrupt: bool,
+    /// Registers from before the instruction, with `pc` set to its address.
+    pub reg: Registers,
+    /// Registers only the Z80 has, from before the instruction,
+    /// in traces of a Z80.
+    pub z80: Option<Z80Registers>,
+    /// Number of states elapsed before the instruction.
+    pub cycles: u64,
+    /// Number of states the instruction took.
+    pub states: u32,
+    /// What the instruction did to memory and ports, in order.
+    pub events: Vec<TraceEvent>,
+    /// The encoded instruction, also when supplied by an interrupting device.
+    bytes: [u8; 4],
+    len: usize,
+    /// The processor the trace was recorded from.
+    variant: Variant,
+}
+
+impl TraceRecord {
+    /// The raw bytes of the instruction, including its operands.
+    /// Empty if the instruction was not fetched from memory.
+    pub fn bytes(&self) -> &[u8] {
+        match self.interrupt {
+            true => &[],
+            false => &self.bytes[..self.len],
+        }
+    }
+
+    /// Writes the line [`TextTrace`](crate::TextTrace) writes for the instruction.
+    pub fn write_text<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        write_line(out, self.address, &self.reg, self.variant, self.cycles, self.bytes(), self.opcode)
+    }
+}
+
+/// An observer that writes a compact binary trace of the executed
+/// instructions, for runs too long for a [`TextTrace`](crate::TextTrace).
+/// Read it back with [`TraceReader`].
+///
+/// Every instruction is stored as its bytes, its number of states and
+/// the registers that changed since the previous one, followed by the
+/// memory writes, port I/O and halt it caused. An instruction takes
+/// about 6 bytes, an order of magnitude less than a line of text trace.
+/// The traces of a Z80 also store the registers only the Z80 has.
+/// States are counted from when the trace was registered.
+///
+/// Writing stops at the first error, which is returned by
+/// [`TraceWriter::finish`]. The output is not flushed on drop,
+/// unless it does so itself.
+pub struct TraceWriter<W: Write> {
+    out: W,
+    /// The first error, after which nothing more is written.
+    error: Option<io::Error>,
+    variant: Variant,
+    /// The registers of the previous instruction, with `pc` set to the
+    /// address of the instruction following it in memory.
+    last: Option<Registers>,
+    last_z80: Option<Z80Registers>,
+    /// Registers from before the instruction being executed.
+    before: Registers,
+    before_z80: Z80Registers,
+    /// Events caused by the instruction being executed.
+    events: Vec<TraceEvent>,
+}
+
+impl<W: Write> TraceWriter<W> {
+    /// Writes the header of a trace of a processor of the given variant.
+    pub fn new(out: W, variant: Variant) -> io::Result<Self> {
+        Self::with_start(out, variant, 0, 0)
+    }
+
+    /// Writes the header of a trace whose first instruction is numbered
+    /// `index` and executed after `cycles` states, for a trace that
+    /// continues or is cut out of another one.
+    pub fn with_start(mut out: W, variant: Variant, index: u64, cycles: u64) -> io::Result<Self> {
+        let id = match variant {
+            Variant::I8080 => 0,
+            Variant::I8085 => 1,
+            Variant::Z80 => 2,
+        };
+        out.write_all(&MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&[id])?;
+        out.write_all(&index.to_le_bytes())?;
+        out.write_all(&cycles.to_le_bytes())?;
+        Ok(TraceWriter {
+            out,
+            error: None,
+            variant,
+            last: None,
+            last_z80: None,
+            before: Registers::default(),
+            before_z80: Z80Registers::default(),
+            events: Vec::new(),
+        })
+    }
+
+    /// Appends a record read from another trace. Its index and
+    /// cycle count are not stored, they follow from the header.
+    ///
+    /// After an error, this and every later write fail, and
+    /// [`TraceWriter::finish`] returns the error.
+    pub fn write(&mut self, record: &TraceRecord) -> io::Result<()> {
+        if self.error.is_some() {
+            return Err(io::Error::other("trace writer failed earlier"));
+        }
+        let bytes = &record.bytes[..record.len];
+        let z80 = record.z80.unwrap_or_default();
+        let buf = self.encode(record.address, &record.reg, &z80, bytes, record.interrupt, record.states);
+        let result = self.out.write_all(&buf).and_then(|_| write_events(&mut self.out, &record.events));
+        // The registers were updated as if the record was written,
+        // so nothing more may be written after it.
+        if let Err(err) = result {
+            let copy = io::Error::new(err.kind(), err.to_string());
+            self.error = Some(err);
+            return Err(copy);
+        }
+        Ok(())
+    }
+
+    /// Flushes the output and returns it, or returns
+    /// the first error met while writing the trace.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Encodes an instruction record against the registers of the
+    /// previous one, and updates them.
+    fn encode(
+        &mut self,
+        address: u16,
+        reg: &Registers,
+        z80: &Z80Registers,
+        bytes: &[u8],
+        interrupt: bool,
+        states: u32,
+    ) -> Vec<u8> {
+        let last = self.last;
+        let changed = [
+            (PC, last.map(|last| last.pc) != Some(address)),
+            (A, last.map(|last| last.a) != Some(reg.a)),
+            (F, last.map(|last| pack_flags(&last.flags)) != Some(pack_flags(&reg.flags))),
+            (BC, last.map(|last| last.bc()) != Some(reg.bc())),
+            (DE, last.map(|last| last.de()) != Some(reg.de())),
+            (HL, last.map(|last| last.hl()) != Some(reg.hl())),
+            (SP, last.map(|last| last.sp) != Some(reg.sp)),
+        ];
+        let mask = changed.iter().filter(|(_, changed)| *changed).fold(0, |mask, (bit, _)| mask | bit);
+
+        let mut buf = Vec::with_capacity(16);
+        buf.push(INSTRUCTION | (INTERRUPT * interrupt as u8) | bytes.len() as u8);
+        buf.push(mask);
+        if mask & PC != 0 {
+            buf.extend(address.to_le_bytes());
+        }
+        if mask & A != 0 {
+            buf.push(reg.a);
+        }
+        if mask & F != 0 {
+            buf.push(pack_flags(&reg.flags));
+        }
+        for (bit, value) in [(BC, reg.bc()), (DE, reg.de()), (HL, reg.hl()), (SP, reg.sp)] {
+            if mask & bit != 0 {
+                buf.extend(value.to_le_bytes());
+            }
+        }
+        buf.extend(bytes);
+        write_varint(&mut buf, states);
+        self.last = Some(Registers { pc: next_address(address, bytes.len(), interrupt), ..*reg });
+
+        if self.variant == Variant::Z80 {
+            let last = self.last_z80;
+            let af_alt = |z80: &Z80Registers| (z80.alt.a, pack_flags(&z80.alt.flags));
+            let changed = [
+                (IX, last.map(|last| last.ix) != Some(z80.ix)),
+                (IY, last.map(|last| last.iy) != Some(z80.iy)),
+                (AF_ALT, last.map(|last| af_alt(&last)) != Some(af_alt(z80))),
+                (BC_ALT, last.map(|last| last.alt.bc()) != Some(z80.alt.bc())),
+                (DE_ALT, last.map(|last| last.alt.de()) != Some(z80.alt.de())),
+                (HL_ALT, last.map(|last| last.alt.hl()) != Some(z80.alt.hl())),
+                (IR, last.map(|last| (last.i, last.r)) != Some((z80.i, z80.r))),
+                (IFF, last.map(|last| last.iff()) != Some(z80.iff())),
+            ];
+            let mask = changed.iter().filter(|(_, changed)| *changed);
+            let mask = mask.fold(0, |mask, (bit, _)| mask | bit);
+            buf.push(mask);
+            for (bit, value) in [(IX, z80.ix), (IY, z80.iy)] {
+                if mask & bit != 0 {
+                    buf.extend(value.to_le_bytes());
+                }
+            }
+            if mask & AF_ALT != 0 {
+                buf.extend([z80.alt.a, pack_flags(&z80.alt.flags)]);
+            }
+            let alt = &z80.alt;
+            for (bit, value) in [(BC_ALT, alt.bc()), (DE_ALT, alt.de()), (HL_ALT, alt.hl())] {
+                if mask & bit != 0 {
+                    buf.extend(value.to_le_bytes());
+                }
+            }
+            if mask & IR != 0 {
+                buf.extend([z80.i, z80.r]);
+            }
+            if mask & IFF != 0 {
+                buf.push(z80.iff());
+            }
+            self.last_z80 = Some(*z80);
+        }
+        buf
+    }
+}
+
+impl<W: Write + 'static> Observer for TraceWriter<W> {
+    fn before_instruction(&mut self, _address: u16, _opcode: Opcode, reg: &Registers) {
+        self.before = *reg;
+        self.events.clear();
+    }
+
+    fn before_z80_instruction(&mut self, z80: &z80::State, iff1: bool) {
+        self.before_z80 = Z80Registers::new(z80, iff1);
+    }
+
+    fn after_instruction(&mut self, step: &Step, _reg: &Registers) {
+        if self.error.is_some() {
+            return;
+        }
+        let encoded;
+        let bytes = match step.interrupt {
+            true => {
+                encoded = step.opcode.encode();
+                &encoded[..]
+            },
+            false => step.bytes(),
+        };
+        let (reg, z80) = (self.before, self.before_z80);
+        let buf = self.encode(step.address, &reg, &z80, bytes, step.interrupt, step.states);
+        let result = self.out.write_all(&buf).and_then(|_| write_events(&mut self.out, &self.events));
+        self.error = result.err();
+    }
+
+    fn memory_write(&mut self, adr: u16, value: u8) {
+        self.events.push(TraceEvent::Write { adr, value });
+    }
+
+    fn input(&mut self, port: u8, value: u8) {
+        self.events.push(TraceEvent::In { port, value });
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        self.events.push(TraceEvent::Out { port, value });
+    }
+
+    fn halt(&mut self, pc: u16) {
+        // Called after the instruction was written, which it belongs to.
+        if self.error.is_some() {
+            return;
+        }
+        self.error = write_events(&mut self.out, &[TraceEvent::Halt { pc }]).err();
+    }
+}
+
+fn write_events(out: &mut dyn Write, events: &[TraceEvent]) -> io::Result<()> {
+    for event in events {
+        match *event {
+            TraceEvent::Write { adr, value } => {
+                let [lo, hi] = adr.to_le_bytes();
+                out.write_all(&[WRITE, lo, hi, value])?;
+            },
+            TraceEvent::In { port, value } => out.write_all(&[IN, port, value])?,
+            TraceEvent::Out { port, value } => out.write_all(&[OUT, port, value])?,
+            TraceEvent::Halt { pc } => {
+                let [lo, hi] = pc.to_le_bytes();
+                out.write_all(&[HALT, lo, hi])?;
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Writes a number seven bits at a time, low bits first, with the top bit
+/// of each byte telling whether another one follows. The number of states
+/// of an instruction takes a single byte, unless it exceeds 127.
+fn write_varint(buf: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn read_varint(input: &mut dyn Read) -> io::Result<u32> {
+    let mut value = 0;
+    for shift in (0..32).step_by(7) {
+        let byte = read_u8(input)?;
+        let bits = (byte & 0x7f) as u32;
+        if bits << shift >> shift != bits {
+            return Err(invalid());
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid())
+}
+
+/// The address an instruction falls through to, which the
+/// address of the next one is not stored when equal to.
+fn next_address(address: u16, len: usize, interrupt: bool) -> u16 {
+    match interrupt {
+        true => address,
+        false => address.wrapping_add(len as u16),
+    }
+}
+
+/// Reads the instructions of a binary trace written by [`TraceWriter`].
+///
+/// Iterating stops at the end of the trace or at the first error. A trace
+/// cut short, such as by a crash of the emulator writing it, is reported
+/// as an [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) error after its
+/// last complete instruction.
+pub struct TraceReader<R: Read> {
+    input: R,
+    variant: Variant,
+    index: u64,
+    cycles: u64,
+    /// The registers of the previous instruction, with `pc` set to the
+    /// address of the instruction following it in memory.
+    last: Registers,
+    last_z80: Z80Registers,
+    /// Tag of the next instruction, read while looking for the end of
+    /// the events of the previous one.
+    next: Option<u8>,
+    done: bool,
+}
+
+impl<R: Read> TraceReader<R> {
+    /// Reads the header of a trace.
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut header = [0; 23];
+        input.read_exact(&mut header)?;
+        if header[0..4] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a binary trace"));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            let message = format!("unsupported trace version {}", version);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        let variant = match header[6] {
+            0 => Variant::I8080,
+            1 => Variant::I8085,
+            2 => Variant::Z80,
+            _ => return Err(invalid()),
+        };
+        let index = u64::from_le_bytes(header[7..15].try_into().unwrap());
+        let cycles = u64::from_le_bytes(header[15..23].try_into().unwrap());
+        let (last, last_z80) = (Registers::default(), Z80Registers::default());
+        Ok(TraceReader { input, variant, index, cycles, last, last_z80, next: None, done: false })
+    }
+
+    /// The processor variant the trace was recorded from.
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Reads the tag of the next record, if there is one.
+    fn tag(&mut self) -> io::Result<Option<u8>> {
+        if let Some(tag) = self.next.take() {
+            return Ok(Some(tag));
+        }
+        let mut tag = [0];
+        loop {
+            match self.input.read(&mut tag) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(tag[0])),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {},
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Reads the registers only the Z80 has, which follow
+    /// the number of states in the traces of a Z80.
+    fn z80_registers(&mut self) -> io::Result<Z80Registers> {
+        let input = &mut self.input;
+        let mask = read_u8(input)?;
+        let z80 = &mut self.last_z80;
+        if mask & IX != 0 {
+            z80.ix = read_u16(input)?;
+        }
+        if mask & IY != 0 {
+            z80.iy = read_u16(input)?;
+        }
+        if mask & AF_ALT != 0 {
+            z80.alt.a = read_u8(input)?;
+            z80.alt.flags = unpack_flags(read_u8(input)?);
+        }
+        if mask & BC_ALT != 0 {
+            z80.alt.set_bc(read_u16(input)?);
+        }
+        if mask & DE_ALT != 0 {
+            z80.alt.set_de(read_u16(input)?);
+        }
+        if mask & HL_ALT != 0 {
+            z80.alt.set_hl(read_u16(input)?);
+        }
+        if mask & IR != 0 {
+            z80.i = read_u8(input)?;
+            z80.r = read_u8(input)?;
+        }
+        if mask & IFF != 0 {
+            let iff = read_u8(input)?;
+            z80.iff1 = iff & 1 != 0;
+            z80.iff2 = iff & 2 != 0;
+            z80.im = match iff >> 2 {
+                0 => InterruptMode::Mode0,
+                1 => InterruptMode::Mode1,
+                2 => InterruptMode::Mode2,
+                _ => return Err(invalid()),
+            };
+        }
+        Ok(*z80)
+    }
+
+    fn record(&mut self) -> io::Result<Option<TraceRecord>> {
+        let Some(tag) = self.tag()? else {
+            return Ok(None);
+        };
+        let len = (tag & LEN) as usize;
+        if tag & INSTRUCTION == 0 || !(1..=4).contains(&len) {
+            return Err(invalid());
+        }
+        let interrupt = tag & INTERRUPT != 0;
+        let input = &mut self.input;
+        let mask = read_u8(input)?;
+        let mut reg = self.last;
+        if mask & PC != 0 {
+            reg.pc = read_u16(input)?;
+        }
+        if mask & A != 0 {
+            reg.a = read_u8(input)?;
+        }
+        if mask & F != 0 {
+            reg.flags = unpack_flags(read_u8(input)?);
+        }
+        if mask & BC != 0 {
+            reg.set_bc(read_u16(input)?);
+        }
+        if mask & DE != 0 {
+            reg.set_de(read_u16(input)?);
+        }
+        if mask & HL != 0 {
+            reg.set_hl(read_u16(input)?);
+        }
+        if mask & SP != 0 {
+            reg.sp = read_u16(input)?;
+        }
+        let mut bytes = [0; 4];
+        input.read_exact(&mut bytes[..len])?;
+        let opcode = match decode_with(self.variant, &bytes[..len]) {
+            Ok((opcode, n)) if n == len => opcode,
+            _ => return Err(invalid()),
+        };
+        let states = read_varint(input)?;
+        let z80 = match self.variant {
+            Variant::Z80 => Some(self.z80_registers()?),
+            _ => None,
+        };
+
+        let mut events = Vec::new();
+        while let Some(tag) = self.tag()? {
+            let input = &mut self.input;
+            let event = match tag {
+                WRITE => TraceEvent::Write { adr: read_u16(input)?, value: read_u8(input)? },
+                IN => TraceEvent::In { port: read_u8(input)?, value: read_u8(input)? },
+                OUT => TraceEvent::Out { port: read_u8(input)?, value: read_u8(input)? },
+                HALT => TraceEvent::Halt { pc: read_u16(input)? },
+                _ => {
+                    self.next = Some(tag);
+                    break;
+                },
+            };
+            events.push(event);
+        }
+
+        let address = reg.pc;
+        let record = TraceRecord {
+            index: self.index,
+            address,
+            opcode,
+            interrupt,
+            reg,
+            z80,
+            cycles: self.cycles,
+            states,
+            events,
+            bytes,
+            len,
+            variant: self.variant,
+        };
+        self.index += 1;
+        self.cycles += states as u64;
+        self.last = Registers { pc: next_address(address, len, interrupt), ..reg };
+        Ok(Some(record))
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = io::Result<TraceRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.record().transpose();
+        self.done = !matches!(record, Some(Ok(_)));
+        record
+    }
+}
+
+fn invalid() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid trace")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::{Cpu, Memory, TextTrace};
+
+    // MVI A,41H; STA 0100H; OUT 1; IN 2; INR A; HLT
+    const PROGRAM: [u8; 11] = [0x3e, 0x41, 0x32, 0x00, 0x01, 0xd3, 0x01, 0xdb, 0x02, 0x3c, 0x76];
+
+    /// Runs the program until it halts with the observer registered.
+    fn run<O: Observer>(program: &[u8], variant: Variant, observer: O) -> O {
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(program)).unwrap());
+        cpu.set_variant(variant);
+        cpu.set_observer(observer);
+        while cpu.step().is_ok() {}
+        let observer: Box<dyn Any> = cpu.take_observer().unwrap();
+        *observer.downcast().unwrap()
+    }
+
+    fn record(program: &[u8], variant: Variant) -> Vec<u8> {
+        run(program, variant, TraceWriter::new(Vec::new(), variant).unwrap()).finish().unwrap()
+    }
+
+    fn read(trace: &[u8]) -> Vec<TraceRecord> {
+        TraceReader::new(trace).unwrap().collect::<io::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn records_read_back_as_the_text_trace() {
+        let trace = record(&PROGRAM, Variant::I8080);
+        let records = read(&trace);
+        assert_eq!(records.len(), 6);
+
+        let mut text = Vec::new();
+        for record in &records {
+            record.write_text(&mut text).unwrap();
+        }
+        let expected = run(&PROGRAM, Variant::I8080, TextTrace::new(Vec::new(), Variant::I8080));
+        assert_eq!(text, expected.finish().unwrap());
+
+        let events: Vec<_> = records.iter().map(|record| record.events.clone()).collect();
+        assert_eq!(events, [
+            vec![],
+            vec![TraceEvent::Write { adr: 0x100, value: 0x41 }],
+            vec![TraceEvent::Out { port: 1, value: 0x41 }],
+            vec![TraceEvent::In { port: 2, value: 0xff }],
+            vec![],
+            vec![TraceEvent::Halt { pc: 11 }],
+        ]);
+        let last = &records[5];
+        assert_eq!((last.index, last.address, last.states), (5, 10, 7));
+        assert_eq!(last.cycles, 7 + 13 + 10 + 10 + 5);
+        assert!(last.opcode == Opcode::HLT && last.z80.is_none());
+    }
+
+    #[test]
+    fn z80_traces_store_the_z80_registers() {
+        // LD B,7; LD A,9; LD IX,1234H; EXX; LD B,5; EX AF,AF'; IM 2; LD A,3; LD I,A; EI; HALT
+        let program = [
+            0x06, 0x07, 0x3e, 0x09, 0xdd, 0x21, 0x34, 0x12, 0xd9, 0x06, 0x05, 0x08, 0xed, 0x5e,
+            0x3e, 0x03, 0xed, 0x47, 0xfb, 0x76,
+        ];
+        let records = read(&record(&program, Variant::Z80));
+        assert_eq!(records.len(), 11);
+        let z80: Vec<_> = records.iter().map(|record| record.z80.unwrap()).collect();
+        assert_eq!((z80[2].ix, z80[3].ix), (0, 0x1234));
+        assert_eq!((records[3].reg.b, z80[3].alt.b, records[4].reg.b, z80[4].alt.b), (7, 0, 0, 7));
+        assert_eq!((records[5].reg.a, z80[5].alt.a, records[6].reg.a, z80[6].alt.a), (9, 0, 0, 9));
+        assert_eq!((z80[6].im, z80[7].im), (InterruptMode::Mode0, InterruptMode::Mode2));
+        assert_eq!((z80[8].i, z80[9].i), (0, 3));
+        assert_eq!((z80[9].iff1, z80[10].iff1, z80[10].iff2), (false, true, true));
+        assert!(z80.windows(2).all(|pair| pair[0].r != pair[1].r));
+    }
+
+    #[test]
+    fn records_can_be_copied_to_a_new_trace() {
+        let records = read(&record(&PROGRAM, Variant::I8080));
+        let start = &records[2];
+        let slice = TraceWriter::with_start(Vec::new(), Variant::I8080, start.index, start.cycles);
+        let mut slice = slice.unwrap();
+        for record in &records[2..5] {
+            slice.write(record).unwrap();
+        }
+        let copied = read(&slice.finish().unwrap());
+        assert_eq!(format!("{:?}", copied), format!("{:?}", &records[2..5]));
+    }
+
+    #[test]
+    fn states_are_stored_in_full() {
+        let lengths = [(0, 1), (7, 1), (127, 1), (128, 2), (300, 2), (0x20_0000, 4), (u32::MAX, 5)];
+        for (states, len) in lengths {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, states);
+            assert_eq!(buf.len(), len);
+            assert_eq!(read_varint(&mut &buf[..]).unwrap(), states);
+        }
+        let err = read_varint(&mut &[0xff, 0xff, 0xff, 0xff, 0x1f][..]).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut records = read(&record(&PROGRAM, Variant::I8080));
+        records[1].states = 1000;
+        let mut slice = TraceWriter::new(Vec::new(), Variant::I8080).unwrap();
+        for record in &records {
+            slice.write(record).unwrap();
+        }
+        let copied = read(&slice.finish().unwrap());
+        assert_eq!((copied[1].states, copied[2].cycles), (1000, 7 + 1000));
+    }
+
+    /// Accepts a given number of bytes, then fails.
+    struct Full(usize);
+
+    impl Write for Full {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.0 == 0 {
+                return Err(io::Error::new(io::ErrorKind::StorageFull, "full"));
+            }
+            let len = buf.len().min(self.0);
+            self.0 -= len;
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn errors_are_returned_to_the_caller() {
+        let writer = run(&PROGRAM, Variant::I8080, TraceWriter::new(Full(30), Variant::I8080).unwrap());
+        assert_eq!(writer.finish().err().unwrap().kind(), io::ErrorKind::StorageFull);
+
+        let trace = record(&PROGRAM, Variant::I8080);
+        assert_eq!(TraceReader::new(&b"8080"[..]).err().unwrap().kind(), io::ErrorKind::UnexpectedEof);
+        let mut damaged = trace.clone();
+        damaged[0] = b'Z';
+        assert_eq!(TraceReader::new(&damaged[..]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+        let records = read(&trace);
+        // Room for the header and the first record.
+        let mut slice = TraceWriter::new(Full(23 + 17), Variant::I8080).unwrap();
+        let results: Vec<_> = records.iter().map(|record| slice.write(record)).collect();
+        assert!(results[0].is_ok());
+        assert_eq!(results[1].as_ref().err().unwrap().kind(), io::ErrorKind::StorageFull);
+        assert!(results[2..].iter().all(|result| result.is_err()));
+        assert_eq!(slice.finish().err().unwrap().kind(), io::ErrorKind::StorageFull);
+
+        // A trace cut short ends with an error after its last complete instruction.
+        let records: Vec<_> = TraceReader::new(&trace[..trace.len() - 2]).unwrap().collect();
+        assert_eq!(records.len(), 6);
+        assert!(records[..5].iter().all(|record| record.is_ok()));
+        assert_eq!(records[5].as_ref().err().unwrap().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
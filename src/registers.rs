@@ -1,4 +1,8 @@
-#[derive(Default)]
+use crate::{Flags, Reg8, RegPair, Variant};
+
+/// The register file of the processor: the accumulator, the six general
+/// purpose registers, the flags, the stack pointer and the program counter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Registers {
     pub a: u8,
     pub b: u8,
@@ -7,6 +11,17 @@ pub struct Registers {
     pub e: u8,
     pub h: u8,
     pub l: u8,
+    pub flags: Flags,
+    /// ### Stack Pointer
+    ///
+    /// 16-bit stack pointer register (SPH and SPL are used to refer
+    /// to the high-order and low-order 8 bits respectively).
+    pub sp: u16,
+    /// ### Program Counter
+    ///
+    /// 16-bit program counter register (PCH and PCl are used to refer
+    /// to the high-order and low-order 8 bits respectively).
+    pub pc: u16,
 }
 
 impl Registers {
@@ -24,7 +39,248 @@ impl Registers {
 
     /// Represents the H,L pair with H as the high-order
     /// register and L as the low-order register
-    pub fn m(&self) -> u16 {
+    pub fn hl(&self) -> u16 {
         ((self.h as u16) << 8) | (self.l as u16)
     }
+
+    /// The address of the memory operand M, held in the H,L pair.
+    pub fn m(&self) -> u16 {
+        self.hl()
+    }
+
+    pub fn sp(&self) -> u16 {
+        self.sp
+    }
+
+    /// Represents the processor status word, with the accumulator as the
+    /// high-order byte and the flags, packed as PUSH PSW does on the given
+    /// processor, as the low-order byte.
+    pub fn psw(&self, variant: Variant) -> u16 {
+        ((self.a as u16) << 8) | (self.flags.psw(variant) as u16)
+    }
+
+    pub fn set_bc(&mut self, value: u16) {
+        self.b = (value >> 8) as u8;
+        self.c = value as u8;
+    }
+
+    pub fn set_de(&mut self, value: u16) {
+        self.d = (value >> 8) as u8;
+        self.e = value as u8;
+    }
+
+    pub fn set_hl(&mut self, value: u16) {
+        self.h = (value >> 8) as u8;
+        self.l = value as u8;
+    }
+
+    pub fn set_sp(&mut self, value: u16) {
+        self.sp = value;
+    }
+
+    pub fn set_psw(&mut self, variant: Variant, value: u16) {
+        self.a = (value >> 8) as u8;
+        self.flags = Flags::from_psw(variant, value as u8);
+    }
+
+    /// Reads an 8-bit register, or returns `None` for [`Reg8::M`],
+    /// which lives in memory; [`Cpu::get8`](crate::Cpu::get8) reads it.
+    pub fn get8(&self, reg: Reg8) -> Option<u8> {
+        match reg {
+            Reg8::B => Some(self.b),
+            Reg8::C => Some(self.c),
+            Reg8::D => Some(self.d),
+            Reg8::E => Some(self.e),
+            Reg8::H => Some(self.h),
+            Reg8::L => Some(self.l),
+            Reg8::M => None,
+            Reg8::A => Some(self.a),
+        }
+    }
+
+    /// Borrows an 8-bit register, or returns `None` for [`Reg8::M`],
+    /// which lives in memory; [`Cpu::set8`](crate::Cpu::set8) writes it.
+    pub fn get8_mut(&mut self, reg: Reg8) -> Option<&mut u8> {
+        match reg {
+            Reg8::B => Some(&mut self.b),
+            Reg8::C => Some(&mut self.c),
+            Reg8::D => Some(&mut self.d),
+            Reg8::E => Some(&mut self.e),
+            Reg8::H => Some(&mut self.h),
+            Reg8::L => Some(&mut self.l),
+            Reg8::M => None,
+            Reg8::A => Some(&mut self.a),
+        }
+    }
+
+    /// Reads a register pair. The flags in [`RegPair::PSW`] are packed
+    /// as PUSH PSW does on the given processor.
+    pub fn get16(&self, pair: RegPair, variant: Variant) -> u16 {
+        match pair {
+            RegPair::BC => self.bc(),
+            RegPair::DE => self.de(),
+            RegPair::HL => self.hl(),
+            RegPair::SP => self.sp(),
+            RegPair::PSW => self.psw(variant),
+        }
+    }
+
+    /// Writes a register pair. The flags in [`RegPair::PSW`] are unpacked
+    /// as POP PSW does on the given processor.
+    pub fn set16(&mut self, pair: RegPair, variant: Variant, value: u16) {
+        match pair {
+            RegPair::BC => self.set_bc(value),
+            RegPair::DE => self.set_de(value),
+            RegPair::HL => self.set_hl(value),
+            RegPair::SP => self.set_sp(value),
+            RegPair::PSW => self.set_psw(variant, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::{Cpu, Memory};
+
+    #[test]
+    fn pairs_combine_their_registers() {
+        let mut reg = Registers::default();
+        reg.set_bc(0x1234);
+        reg.set_de(0x5678);
+        reg.set_hl(0x9abc);
+        reg.set_sp(0xdef0);
+        assert_eq!((reg.b, reg.c, reg.d, reg.e, reg.h, reg.l), (0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc));
+        assert_eq!((reg.bc(), reg.de(), reg.hl(), reg.m(), reg.sp()), (0x1234, 0x5678, 0x9abc, 0x9abc, 0xdef0));
+
+        reg.set_psw(Variant::I8080, 0x42d7);
+        assert_eq!(reg.a, 0x42);
+        assert!(reg.flags.sign() && reg.flags.zero() && reg.flags.carry_aux());
+        assert!(reg.flags.parity() && reg.flags.carry());
+    }
+
+    #[test]
+    fn psw_matches_what_push_psw_stores() {
+        // LXI SP,0; MVI A,7FH; INR A; DCX SP; LXI SP,0100H; PUSH PSW
+        let program = [0x31, 0x00, 0x00, 0x3e, 0x7f, 0x3c, 0x3b, 0x31, 0x00, 0x01, 0xf5];
+        for (variant, psw) in [(Variant::I8080, 0x8092), (Variant::I8085, 0x80b2), (Variant::Z80, 0x8094)] {
+            let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(&program[..])).unwrap());
+            cpu.set_variant(variant);
+            for _ in 0..6 {
+                cpu.step().unwrap();
+            }
+            assert_eq!(cpu.reg().psw(variant), psw, "{:?}", variant);
+            assert_eq!(u16::from_le_bytes([cpu.mem()[0xfe], cpu.mem()[0xff]]), psw, "{:?}", variant);
+
+            let mut reg = Registers::default();
+            reg.set_psw(variant, psw);
+            assert_eq!(reg.psw(variant), psw, "{:?}", variant);
+        }
+    }
+
+    #[test]
+    fn stack_pointer_instructions_leave_pc_alone() {
+        // LXI SP,1234H; INX SP; DCX SP; DCX SP; DAD SP
+        let program = [0x31, 0x34, 0x12, 0x33, 0x3b, 0x3b, 0x39];
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(&program[..])).unwrap());
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!((cpu.sp(), cpu.pc()), (0x1235, 4));
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!((cpu.sp(), cpu.pc()), (0x1233, 6));
+        cpu.step().unwrap();
+        assert_eq!((cpu.reg().hl(), cpu.pc()), (0x1233, 7));
+    }
+
+    #[test]
+    fn pair_instructions_wrap_around_and_carry() {
+        // LXI B,0FFFFH; INX B; DCX B; DCX B; LXI H,8001H; DAD H; DAD B
+        let program = [0x01, 0xff, 0xff, 0x03, 0x0b, 0x0b, 0x21, 0x01, 0x80, 0x29, 0x09];
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(&program[..])).unwrap());
+        cpu.set_variant(Variant::I8085);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().bc(), 0);
+        assert!(cpu.flags().underflow());
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().bc(), 0xffff);
+        assert!(cpu.flags().underflow());
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().bc(), 0xfffe);
+        assert!(!cpu.flags().underflow());
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().hl(), 0x0002);
+        assert!(cpu.flags().carry());
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().hl(), 0x0000);
+        assert!(cpu.flags().carry());
+    }
+
+    /// Logs every access so the tests can see M go through the bus.
+    struct Logged {
+        bytes: Vec<u8>,
+        accesses: Vec<(char, u16, u8)>,
+    }
+
+    impl crate::MemoryBus for Logged {
+        fn read(&mut self, adr: u16) -> u8 {
+            let value = self.bytes[adr as usize];
+            self.accesses.push(('r', adr, value));
+            value
+        }
+
+        fn write(&mut self, adr: u16, value: u8) {
+            self.accesses.push(('w', adr, value));
+            self.bytes[adr as usize] = value;
+        }
+    }
+
+    #[test]
+    fn get8_and_set8_index_the_registers() {
+        let regs = [Reg8::B, Reg8::C, Reg8::D, Reg8::E, Reg8::H, Reg8::L, Reg8::A];
+        let mut reg = Registers::default();
+        for (value, &r) in regs.iter().enumerate() {
+            *reg.get8_mut(r).unwrap() = value as u8 + 1;
+        }
+        assert_eq!((reg.b, reg.c, reg.d, reg.e, reg.h, reg.l, reg.a), (1, 2, 3, 4, 5, 6, 7));
+        for (value, &r) in regs.iter().enumerate() {
+            assert_eq!(reg.get8(r), Some(value as u8 + 1));
+        }
+        assert_eq!(reg.get8(Reg8::M), None);
+        assert!(reg.get8_mut(Reg8::M).is_none());
+    }
+
+    #[test]
+    fn m_goes_through_the_memory_bus() {
+        let mut cpu = Cpu::new(Logged { bytes: vec![0; 0x10000], accesses: Vec::new() });
+        cpu.reg_mut().set_hl(0x1234);
+        cpu.set8(Reg8::A, 0x55);
+        cpu.set8(Reg8::M, 0x99);
+        assert_eq!(cpu.get8(Reg8::M), 0x99);
+        assert_eq!(cpu.get8(Reg8::A), 0x55);
+        assert_eq!(cpu.mem().accesses, [('w', 0x1234, 0x99), ('r', 0x1234, 0x99)]);
+    }
+
+    #[test]
+    fn get16_and_set16_pack_the_psw_per_variant() {
+        for (variant, psw) in [(Variant::I8080, 0x8092), (Variant::I8085, 0x80b2), (Variant::Z80, 0x8094)] {
+            let mut reg = Registers::default();
+            reg.set16(RegPair::PSW, variant, psw);
+            assert_eq!(reg.get16(RegPair::PSW, variant), psw, "{:?}", variant);
+            assert_eq!(reg.get16(RegPair::PSW, variant), reg.psw(variant), "{:?}", variant);
+        }
+
+        let mut reg = Registers::default();
+        let pairs = [(0x1234, RegPair::BC), (0x5678, RegPair::DE), (0x9abc, RegPair::HL), (0xdef0, RegPair::SP)];
+        for (value, pair) in pairs {
+            reg.set16(pair, Variant::I8080, value);
+            assert_eq!(reg.get16(pair, Variant::I8080), value);
+        }
+        assert_eq!((reg.bc(), reg.de(), reg.hl(), reg.sp()), (0x1234, 0x5678, 0x9abc, 0xdef0));
+    }
 }
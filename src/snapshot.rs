@@ -0,0 +1,378 @@
+use std::io::{self, Read, Write};
+
+use crate::{decode_with, Cpu, Flags, IoBus, MemoryBus, Registers, SnapshotError, Undocumented, Variant};
+
+/// Identifies a save state file.
+const MAGIC: [u8; 4] = *b"8080";
+
+/// Version of the save state format. Bumped whenever the layout of the
+/// payload changes, so that stale snapshots are rejected.
+pub const VERSION: u16 = 1;
+
+impl<M: MemoryBus, I: IoBus> Cpu<M, I> {
+    /// Writes a snapshot of the whole machine: the registers, the interrupt
+    /// state, the cycle counter, and the state of memory and of the devices.
+    ///
+    /// The snapshot starts with a header holding the format version,
+    /// [`SNAPSHOT_VERSION`](crate::SNAPSHOT_VERSION), and a checksum
+    /// of the payload, both checked by [`Cpu::restore`].
+    pub fn save<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut payload = Vec::new();
+        self.save_payload(&mut payload)?;
+
+        out.write_all(&MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&(payload.len() as u32).to_le_bytes())?;
+        out.write_all(&crc32(&payload).to_le_bytes())?;
+        out.write_all(&payload)
+    }
+
+    /// Puts the machine back into the state written by [`Cpu::save`].
+    ///
+    /// The header and checksum are checked and the payload is decoded
+    /// before anything is restored, so that a snapshot of another version
+    /// or a damaged one leaves the machine untouched. Memory and devices
+    /// must be set up like the ones that were saved; if one of them fails
+    /// to restore, the ones before it may already be restored, but the
+    /// processor is left untouched.
+    ///
+    /// The journal, if any, is cleared, as what it recorded
+    /// no longer leads up to the restored state.
+    pub fn restore<R: Read>(&mut self, input: &mut R) -> Result<(), SnapshotError> {
+        self.restore_state(input)?;
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+        Ok(())
+    }
+
+    /// Does what [`Cpu::restore`] does, but keeps the journal.
+    pub(crate) fn restore_state<R: Read>(&mut self, input: &mut R) -> Result<(), SnapshotError> {
+        let mut header = [0; 14];
+        input.read_exact(&mut header)?;
+        if header[0..4] != MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(SnapshotError::Version { found: version });
+        }
+        let len = u32::from_le_bytes([header[6], header[7], header[8], header[9]]);
+        let checksum = u32::from_le_bytes([header[10], header[11], header[12], header[13]]);
+
+        // The length is not trusted until the checksum matches,
+        // so the payload only grows as far as the data goes.
+        let mut payload = Vec::new();
+        input.take(len as u64).read_to_end(&mut payload)?;
+        if payload.len() != len as usize {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        if crc32(&payload) != checksum {
+            return Err(SnapshotError::Checksum);
+        }
+
+        self.restore_payload(&mut &payload[..]).map_err(|err| match err.kind() {
+            io::ErrorKind::Unsupported => SnapshotError::Io(err),
+            _ => SnapshotError::Corrupt,
+        })
+    }
+
+    fn save_payload(&self, out: &mut dyn Write) -> io::Result<()> {
+        let variant = match self.variant {
+            Variant::I8080 => 0,
+            Variant::I8085 => 1,
+            Variant::Z80 => 2,
+        };
+        let undocumented = match self.undocumented {
+            Undocumented::Hardware => 0,
+            Undocumented::Nop => 1,
+            Undocumented::Trap => 2,
+        };
+        out.write_all(&[variant, undocumented])?;
+        write_registers(out, &self.reg)?;
+        out.write_all(&[self.inte as u8, self.ei_delay as u8, self.halted as u8])?;
+        let pending = self.pending.map(|opcode| opcode.encode()).unwrap_or_default();
+        out.write_all(&[pending.len() as u8])?;
+        out.write_all(&pending)?;
+        out.write_all(&self.cycles.to_le_bytes())?;
+        self.i8085.save(out)?;
+        self.z80.save(out)?;
+        self.mem.save(out)?;
+        self.io.save(out)
+    }
+
+    /// Decodes the whole payload before changing any register, so that a damaged
+    /// payload, or a bus that cannot restore its state, leaves them untouched.
+    fn restore_payload(&mut self, input: &mut &[u8]) -> io::Result<()> {
+        let variant = match read_u8(input)? {
+            0 => Variant::I8080,
+            1 => Variant::I8085,
+            2 => Variant::Z80,
+            _ => return Err(invalid()),
+        };
+        let undocumented = match read_u8(input)? {
+            0 => Undocumented::Hardware,
+            1 => Undocumented::Nop,
+            2 => Undocumented::Trap,
+            _ => return Err(invalid()),
+        };
+        let reg = read_registers(input)?;
+        let inte = read_bool(input)?;
+        let ei_delay = read_bool(input)?;
+        let halted = read_bool(input)?;
+        let mut pending = vec![0; read_u8(input)? as usize];
+        input.read_exact(&mut pending)?;
+        let pending = match pending.is_empty() {
+            true => None,
+            false => Some(decode_with(variant, &pending).map_err(|_| invalid())?.0),
+        };
+        let mut cycles = [0; 8];
+        input.read_exact(&mut cycles)?;
+        let mut i8085 = self.i8085.clone();
+        i8085.restore(input)?;
+        let mut z80 = self.z80.clone();
+        z80.restore(input)?;
+        self.mem.restore(input)?;
+        self.io.restore(input)?;
+        if !input.is_empty() {
+            return Err(invalid());
+        }
+
+        self.variant = variant;
+        self.undocumented = undocumented;
+        self.reg = reg;
+        self.inte = inte;
+        self.ei_delay = ei_delay;
+        self.halted = halted;
+        self.pending = pending;
+        self.cycles = u64::from_le_bytes(cycles);
+        self.i8085 = i8085;
+        self.z80 = z80;
+        Ok(())
+    }
+}
+
+pub(crate) fn write_registers(out: &mut dyn Write, reg: &Registers) -> io::Result<()> {
+    out.write_all(&[reg.a, reg.b, reg.c, reg.d, reg.e, reg.h, reg.l])?;
+    write_flags(out, &reg.flags)?;
+    out.write_all(&reg.sp.to_le_bytes())?;
+    out.write_all(&reg.pc.to_le_bytes())
+}
+
+pub(crate) fn read_registers(input: &mut dyn Read) -> io::Result<Registers> {
+    let mut bytes = [0; 7];
+    input.read_exact(&mut bytes)?;
+    let [a, b, c, d, e, h, l] = bytes;
+    let flags = read_flags(input)?;
+    let sp = read_u16(input)?;
+    let pc = read_u16(input)?;
+    Ok(Registers { a, b, c, d, e, h, l, flags, sp, pc })
+}
+
+/// Writes every flag, including the ones that
+/// do not fit in the processor status word.
+pub(crate) fn write_flags(out: &mut dyn Write, flags: &Flags) -> io::Result<()> {
+    out.write_all(&[pack_flags(flags)])
+}
+
+pub(crate) fn read_flags(input: &mut dyn Read) -> io::Result<Flags> {
+    Ok(unpack_flags(read_u8(input)?))
+}
+
+/// Packs every flag into a byte laid out like the processor status word.
+pub(crate) fn pack_flags(flags: &Flags) -> u8 {
+    let mut value = u8::from(*flags) & !Flags::OVERFLOW;
+    value |= Flags::OVERFLOW * (flags.overflow() as u8);
+    value |= Flags::UNDERFLOW * (flags.underflow() as u8);
+    // Bit 3 is always reset in the processor status word.
+    value |= 0b0000_1000 * (flags.subtract() as u8);
+    value
+}
+
+pub(crate) fn unpack_flags(value: u8) -> Flags {
+    let mut flags = Flags::from(value);
+    flags.set_overflow(value & Flags::OVERFLOW != 0);
+    flags.set_underflow(value & Flags::UNDERFLOW != 0);
+    flags.set_subtract(value & 0b0000_1000 != 0);
+    flags
+}
+
+pub(crate) fn read_u8(input: &mut dyn Read) -> io::Result<u8> {
+    let mut byte = [0];
+    input.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+pub(crate) fn read_u16(input: &mut dyn Read) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    input.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+pub(crate) fn read_bool(input: &mut dyn Read) -> io::Result<bool> {
+    match read_u8(input)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(invalid()),
+    }
+}
+
+pub(crate) fn invalid() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid save state")
+}
+
+/// CRC-32 as used by zlib and PNG.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::z80::InterruptMode;
+    use crate::Memory;
+
+    fn cpu(program: &[u8]) -> Cpu {
+        Cpu::new(Memory::from_reader(&mut BufReader::new(program)).unwrap())
+    }
+
+    #[test]
+    fn restore_puts_back_what_save_wrote() {
+        // LD IX,1234H; EXX; LD B,5; IM 2; LD A,3; LD I,A; EI; DEC B; JR -3
+        let program = [
+            0xdd, 0x21, 0x34, 0x12, 0xd9, 0x06, 0x05, 0xed, 0x5e,
+            0x3e, 0x03, 0xed, 0x47, 0xfb, 0x05, 0x18, 0xfd,
+        ];
+        let mut cpu = cpu(&program);
+        cpu.set_variant(Variant::Z80);
+        for _ in 0..8 {
+            cpu.step().unwrap();
+        }
+        let mut snapshot = Vec::new();
+        cpu.save(&mut snapshot).unwrap();
+        let (reg, cycles) = (*cpu.reg(), cpu.cycles());
+
+        for _ in 0..5 {
+            cpu.step().unwrap();
+        }
+        cpu.mem_mut()[0x8000] = 0x55;
+        cpu.z80_mut().ix = 0;
+        cpu.set_variant(Variant::I8080);
+        assert_ne!(*cpu.reg(), reg);
+
+        cpu.restore(&mut &snapshot[..]).unwrap();
+        assert_eq!(cpu.variant(), Variant::Z80);
+        assert_eq!((*cpu.reg(), cpu.cycles(), cpu.mem()[0x8000]), (reg, cycles, 0));
+        let z80 = cpu.z80();
+        assert_eq!((z80.ix, z80.i, z80.alt.b, z80.im), (0x1234, 3, 0, InterruptMode::Mode2));
+        assert_eq!(cpu.reg().b, 4);
+    }
+
+    #[test]
+    fn damaged_snapshots_leave_the_machine_untouched() {
+        let mut cpu = cpu(&[0x3e, 0x42]);
+        let mut snapshot = Vec::new();
+        cpu.save(&mut snapshot).unwrap();
+        cpu.step().unwrap();
+
+        let mut damaged = snapshot.clone();
+        damaged[0] = b'Z';
+        assert!(matches!(cpu.restore(&mut &damaged[..]), Err(SnapshotError::NotASnapshot)));
+
+        let mut damaged = snapshot.clone();
+        damaged[4] = VERSION as u8 + 1;
+        let err = cpu.restore(&mut &damaged[..]);
+        assert!(matches!(err, Err(SnapshotError::Version { found }) if found == VERSION + 1));
+
+        let mut damaged = snapshot.clone();
+        *damaged.last_mut().unwrap() ^= 1;
+        assert!(matches!(cpu.restore(&mut &damaged[..]), Err(SnapshotError::Checksum)));
+
+        // A length far beyond the data must not be allocated up front.
+        let mut damaged = snapshot[..14].to_vec();
+        damaged[6..10].copy_from_slice(&u32::MAX.to_le_bytes());
+        match cpu.restore(&mut &damaged[..]) {
+            Err(SnapshotError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof),
+            other => panic!("{:?}", other),
+        }
+
+        assert_eq!((cpu.reg().a, cpu.pc()), (0x42, 2));
+    }
+
+    /// Writes the length and checksum of a modified payload into the header.
+    fn seal(snapshot: &mut [u8]) {
+        let len = snapshot.len() as u32 - 14;
+        let checksum = crc32(&snapshot[14..]);
+        snapshot[6..10].copy_from_slice(&len.to_le_bytes());
+        snapshot[10..14].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    #[test]
+    fn invalid_payloads_leave_the_machine_untouched() {
+        let mut cpu = cpu(&[0x3e, 0x42]);
+        let mut snapshot = Vec::new();
+        cpu.save(&mut snapshot).unwrap();
+        cpu.step().unwrap();
+        let reg = *cpu.reg();
+
+        let mut damaged = snapshot.clone();
+        damaged[14] = 9;
+        seal(&mut damaged);
+        assert!(matches!(cpu.restore(&mut &damaged[..]), Err(SnapshotError::Corrupt)));
+
+        // A flag byte that is neither 0 nor 1, after the registers.
+        let mut damaged = snapshot.clone();
+        damaged[14 + 2 + 12] = 2;
+        seal(&mut damaged);
+        assert!(matches!(cpu.restore(&mut &damaged[..]), Err(SnapshotError::Corrupt)));
+
+        let mut damaged = snapshot.clone();
+        damaged.push(0);
+        seal(&mut damaged);
+        assert!(matches!(cpu.restore(&mut &damaged[..]), Err(SnapshotError::Corrupt)));
+
+        assert_eq!((*cpu.reg(), cpu.cycles(), cpu.variant()), (reg, 7, Variant::I8080));
+        cpu.restore(&mut &snapshot[..]).unwrap();
+        assert_eq!((cpu.reg().a, cpu.pc()), (0, 0));
+    }
+
+    struct Stateless;
+
+    impl MemoryBus for Stateless {
+        fn read(&mut self, _adr: u16) -> u8 {
+            0
+        }
+
+        fn write(&mut self, _adr: u16, _value: u8) {}
+    }
+
+    #[test]
+    fn buses_must_opt_into_save_states() {
+        let mut cpu = Cpu::new(Stateless);
+        let err = cpu.save(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+        let mut saved = self::cpu(&[]);
+        saved.set_variant(Variant::Z80);
+        saved.reg_mut().set_bc(0x1234);
+        let mut snapshot = Vec::new();
+        saved.save(&mut snapshot).unwrap();
+
+        cpu.reg_mut().a = 0x42;
+        let reg = *cpu.reg();
+        match cpu.restore(&mut &snapshot[..]) {
+            Err(SnapshotError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::Unsupported),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!((*cpu.reg(), cpu.variant()), (reg, Variant::I8080));
+    }
+}
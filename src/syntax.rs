@@ -0,0 +1,133 @@
+//! Intel assembler syntax for instructions.
+
+use crate::{Opcode, Variant};
+
+/// How numbers are written by [`Opcode::display`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Hex {
+    /// Intel style, `1234H`, with a leading zero if the number
+    /// would otherwise start with a letter, as in `0FFH`.
+    #[default]
+    Suffix,
+    /// C style, `0x1234`.
+    Prefix,
+}
+
+/// Options for writing instructions with [`Opcode::display`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub hex: Hex,
+    /// Write mnemonics, registers and hex digits in lower case.
+    pub lowercase: bool,
+}
+
+/// An instruction written in Intel syntax, returned by [`Opcode::display`].
+pub struct Syntax {
+    opcode: Opcode,
+    style: Style,
+}
+
+impl Opcode {
+    /// Writes the instruction in Intel syntax with the given style.
+    ///
+    /// The undocumented 8080 opcodes are written as `DB` directives, since an
+    /// assembler would encode their mnemonics as the documented opcodes.
+    /// Z80 only instructions are written in Zilog syntax, as by `Debug`.
+    pub fn display(&self, style: Style) -> Syntax {
+        Syntax { opcode: *self, style }
+    }
+}
+
+impl Syntax {
+    fn hex(&self, value: u16, digits: usize) -> String {
+        match self.style.hex {
+            Hex::Prefix => format!("0x{:01$X}", value, digits),
+            Hex::Suffix => {
+                let digits = format!("{:01$X}", value, digits);
+                if digits.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                    format!("0{}H", digits)
+                } else {
+                    format!("{}H", digits)
+                }
+            },
+        }
+    }
+
+    fn text(&self) -> String {
+        let opcode = self.opcode;
+        let bytes = opcode.encode();
+        let variant = if opcode.supported_by(Variant::I8080) { Variant::I8080 } else { Variant::I8085 };
+        let metadata = match opcode.metadata(variant) {
+            Some(metadata) if !(metadata.undocumented && variant == Variant::I8080) => metadata,
+            Some(_) => {
+                let bytes: Vec<String> = bytes.iter().map(|&byte| self.hex(byte as u16, 2)).collect();
+                return format!("DB {}", bytes.join(","));
+            },
+            None => return format!("{:?}", opcode),
+        };
+
+        // The operand template ends with the placeholder for immediate data, if any.
+        let (regs, operand) = metadata.operands.rsplit_once(',').unwrap_or(("", metadata.operands));
+        let operand = match operand {
+            "d8" => self.hex(bytes[1] as u16, 2),
+            "d16" | "a16" => self.hex(u16::from_le_bytes([bytes[1], bytes[2]]), 4),
+            _ => operand.to_string(),
+        };
+        match (regs, operand.as_str()) {
+            (_, "") => metadata.mnemonic.to_string(),
+            ("", _) => format!("{} {}", metadata.mnemonic, operand),
+            _ => format!("{} {},{}", metadata.mnemonic, regs, operand),
+        }
+    }
+}
+
+impl std::fmt::Display for Syntax {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.style.lowercase {
+            f.write_str(&self.text().to_lowercase())
+        } else {
+            f.write_str(&self.text())
+        }
+    }
+}
+
+/// Writes the instruction in Intel syntax with the default [`Style`].
+impl std::fmt::Display for Opcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.display(Style::default()).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instructions_are_written_in_intel_syntax() {
+        assert_eq!(Opcode::LXI_BC(0x34, 0x12).to_string(), "LXI B,1234H");
+        assert_eq!(Opcode::MOV_AM.to_string(), "MOV A,M");
+        assert_eq!(Opcode::MVI_A(0xff).to_string(), "MVI A,0FFH");
+        assert_eq!(Opcode::JMP(0xc000).to_string(), "JMP 0C000H");
+        assert_eq!(Opcode::RST_7.to_string(), "RST 7");
+        assert_eq!(Opcode::PUSH_PSW.to_string(), "PUSH PSW");
+        assert_eq!(Opcode::XCHG.to_string(), "XCHG");
+        assert_eq!(Opcode::RIM.to_string(), "RIM");
+        assert_eq!(Opcode::LDHI(0x10).to_string(), "LDHI 10H");
+    }
+
+    #[test]
+    fn the_style_selects_hex_notation_and_case() {
+        let c = Style { hex: Hex::Prefix, lowercase: false };
+        assert_eq!(Opcode::LXI_SP(0x00, 0xf0).display(c).to_string(), "LXI SP,0xF000");
+        let lower = Style { hex: Hex::Suffix, lowercase: true };
+        assert_eq!(Opcode::CPI(0xab).display(lower).to_string(), "cpi 0abh");
+        let both = Style { hex: Hex::Prefix, lowercase: true };
+        assert_eq!(Opcode::IN(0x1f).display(both).to_string(), "in 0x1f");
+    }
+
+    #[test]
+    fn undocumented_opcodes_are_written_as_data() {
+        assert_eq!(Opcode::NOP_08.to_string(), "DB 08H");
+        assert_eq!(Opcode::JMP_CB(0x1234).to_string(), "DB 0CBH,34H,12H");
+    }
+}
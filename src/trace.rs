@@ -0,0 +1,169 @@
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+
+use crate::{Observer, Opcode, Registers, Step, Variant};
+
+/// An observer that writes one line of text per executed instruction.
+///
+/// Each line starts with the program counter, the register pairs and the
+/// number of states elapsed before the instruction, in the format used
+/// by many other 8080 emulators, followed by the bytes of the instruction
+/// and its disassembly, each after a tab:
+///
+/// ```text
+/// PC: 0100, AF: 0002, BC: 0000, DE: 0000, HL: 0000, SP: 0000, CYC: 0    (C3 AB 01)    JMP 01ABH
+/// ```
+///
+/// so that the traces of two emulators can be compared with `diff`, after
+/// cutting the lines at the first tab if the other one prints less.
+/// States are counted from when the trace was registered.
+///
+/// Writing stops at the first error, which is returned by
+/// [`TextTrace::finish`].
+pub struct TextTrace<W: Write> {
+    out: W,
+    /// The first error, after which nothing more is written.
+    error: Option<io::Error>,
+    range: RangeInclusive<u16>,
+    variant: Variant,
+    cycles: u64,
+    /// Registers from before the instruction being executed.
+    before: Registers,
+}
+
+impl<W: Write> TextTrace<W> {
+    /// Creates a trace of a processor of the given variant,
+    /// which decides how the flags are packed in AF.
+    pub fn new(out: W, variant: Variant) -> Self {
+        let before = Registers::default();
+        TextTrace { out, error: None, range: 0..=0xffff, variant, cycles: 0, before }
+    }
+
+    /// Only writes the instructions whose address is in the range.
+    pub fn set_range(&mut self, range: RangeInclusive<u16>) {
+        self.range = range;
+    }
+
+    /// Flushes the trace and returns the writer, or
+    /// the first error met while writing the trace.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write + 'static> Observer for TextTrace<W> {
+    fn before_instruction(&mut self, _address: u16, _opcode: Opcode, reg: &Registers) {
+        self.before = *reg;
+    }
+
+    fn after_instruction(&mut self, step: &Step, _reg: &Registers) {
+        let cycles = self.cycles;
+        self.cycles += step.states as u64;
+        if !self.range.contains(&step.address) || self.error.is_some() {
+            return;
+        }
+
+        let (reg, variant) = (&self.before, self.variant);
+        let result = write_line(&mut self.out, step.address, reg, variant, cycles, step.bytes(), step.opcode);
+        self.error = result.err();
+    }
+}
+
+/// Writes one line of a text trace, for the instruction at `address`
+/// executed with the registers `reg` on `variant`, after `cycles` states.
+pub(crate) fn write_line(
+    out: &mut dyn Write,
+    address: u16,
+    reg: &Registers,
+    variant: Variant,
+    cycles: u64,
+    bytes: &[u8],
+    opcode: Opcode,
+) -> io::Result<()> {
+    let bytes: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+    writeln!(
+        out,
+        "PC: {:04X}, AF: {:04X}, BC: {:04X}, DE: {:04X}, HL: {:04X}, SP: {:04X}, CYC: {}\t({})\t{}",
+        address,
+        reg.psw(variant),
+        reg.bc(),
+        reg.de(),
+        reg.hl(),
+        reg.sp,
+        cycles,
+        bytes.join(" "),
+        opcode,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::{Cpu, Memory};
+
+    // LXI SP,0100H; MVI A,0FFH; INR A; JMP 0000H
+    const PROGRAM: [u8; 9] = [0x31, 0x00, 0x01, 0x3e, 0xff, 0x3c, 0xc3, 0x00, 0x00];
+
+    fn run<W: Write + 'static>(trace: TextTrace<W>, steps: usize) -> io::Result<W> {
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(&PROGRAM[..])).unwrap());
+        cpu.set_observer(trace);
+        for _ in 0..steps {
+            cpu.step().unwrap();
+        }
+        let observer: Box<dyn Any> = cpu.take_observer().unwrap();
+        observer.downcast::<TextTrace<W>>().unwrap().finish()
+    }
+
+    #[test]
+    fn one_line_per_instruction() {
+        let out = run(TextTrace::new(Vec::new(), Variant::I8080), 4).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().collect::<Vec<_>>(), [
+            "PC: 0000, AF: 0002, BC: 0000, DE: 0000, HL: 0000, SP: 0000, CYC: 0\t(31 00 01)\tLXI SP,0100H",
+            "PC: 0003, AF: 0002, BC: 0000, DE: 0000, HL: 0000, SP: 0100, CYC: 10\t(3E FF)\tMVI A,0FFH",
+            "PC: 0005, AF: FF02, BC: 0000, DE: 0000, HL: 0000, SP: 0100, CYC: 17\t(3C)\tINR A",
+            "PC: 0006, AF: 0056, BC: 0000, DE: 0000, HL: 0000, SP: 0100, CYC: 22\t(C3 00 00)\tJMP 0000H",
+        ]);
+    }
+
+    #[test]
+    fn only_the_range_is_written() {
+        let mut trace = TextTrace::new(Vec::new(), Variant::I8080);
+        trace.set_range(3..=5);
+        let out = run(trace, 8).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let addresses: Vec<_> = out.lines().map(|line| &line[4..8]).collect();
+        assert_eq!(addresses, ["0003", "0005", "0003", "0005"]);
+    }
+
+    /// Accepts a given number of bytes, then fails.
+    struct Full(usize);
+
+    impl Write for Full {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.0 == 0 {
+                return Err(io::Error::new(io::ErrorKind::StorageFull, "full"));
+            }
+            let len = buf.len().min(self.0);
+            self.0 -= len;
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn the_first_error_is_returned() {
+        let err = run(TextTrace::new(Full(100), Variant::I8080), 4).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+    }
+}
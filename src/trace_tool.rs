@@ -0,0 +1,241 @@
+mod gzip;
+
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter, Write};
+
+use intel8080::{TraceEvent, TraceReader, TraceRecord, TraceWriter};
+
+const USAGE: &str = "\
+usage: trace-tool COMMAND TRACE [OPTIONS]
+
+commands:
+    text TRACE [--from N] [--to N]      print instructions N to M as a text trace
+    slice TRACE OUT [--from N] [--to N] copy instructions N to M to a new trace
+    search TRACE [--pc ADDR] [--write ADDR] [--in PORT] [--out PORT] [--opcode MNEMONIC]
+                                        print the instructions matching every option
+    stats TRACE                         print statistics about the trace
+
+Instructions are numbered from 0, in decimal. Addresses and ports are
+hexadecimal. Traces ending in .gz are (de)compressed with gzip, - is
+stdin or stdout.";
+
+/// Parses a number written in hexadecimal, with or without a `0x` prefix.
+fn hex(arg: Option<String>) -> u16 {
+    let arg = arg.unwrap_or_default();
+    let digits = arg.strip_prefix("0x").unwrap_or(&arg);
+    u16::from_str_radix(digits, 16).unwrap_or_else(|_| usage(&format!("invalid address: {:?}", arg)))
+}
+
+fn port(arg: Option<String>) -> u8 {
+    let value = hex(arg);
+    u8::try_from(value).unwrap_or_else(|_| usage(&format!("invalid port: {:#x}", value)))
+}
+
+fn index(arg: Option<String>) -> u64 {
+    let arg = arg.unwrap_or_default();
+    arg.parse().unwrap_or_else(|_| usage(&format!("invalid instruction number: {:?}", arg)))
+}
+
+fn usage(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+/// Criteria of the search command, all of which a record must match.
+#[derive(Default)]
+struct Search {
+    pc: Option<u16>,
+    write: Option<u16>,
+    input: Option<u8>,
+    output: Option<u8>,
+    opcode: Option<String>,
+}
+
+impl Search {
+    fn matches(&self, record: &TraceRecord) -> bool {
+        let mut write = self.write.is_none();
+        let mut input = self.input.is_none();
+        let mut output = self.output.is_none();
+        for event in &record.events {
+            match *event {
+                TraceEvent::Write { adr, .. } => write |= self.write == Some(adr),
+                TraceEvent::In { port, .. } => input |= self.input == Some(port),
+                TraceEvent::Out { port, .. } => output |= self.output == Some(port),
+                TraceEvent::Halt { .. } => {},
+            }
+        }
+        write
+            && input
+            && output
+            && self.pc.is_none_or(|pc| record.address == pc)
+            && self.opcode.as_ref().is_none_or(|opcode| mnemonic(record).eq_ignore_ascii_case(opcode))
+    }
+}
+
+fn mnemonic(record: &TraceRecord) -> String {
+    let text = record.opcode.to_string();
+    text.split_whitespace().next().unwrap_or_default().to_owned()
+}
+
+#[derive(Default)]
+struct Stats {
+    instructions: u64,
+    states: u64,
+    interrupts: u64,
+    halts: u64,
+    writes: u64,
+    inputs: u64,
+    outputs: u64,
+    mnemonics: HashMap<String, u64>,
+    addresses: HashMap<u16, u64>,
+    ports: HashMap<(&'static str, u8), u64>,
+}
+
+impl Stats {
+    fn add(&mut self, record: &TraceRecord) {
+        self.instructions += 1;
+        self.states += record.states as u64;
+        self.interrupts += record.interrupt as u64;
+        *self.mnemonics.entry(mnemonic(record)).or_default() += 1;
+        *self.addresses.entry(record.address).or_default() += 1;
+        for event in &record.events {
+            match *event {
+                TraceEvent::Write { .. } => self.writes += 1,
+                TraceEvent::In { port, .. } => {
+                    self.inputs += 1;
+                    *self.ports.entry(("IN", port)).or_default() += 1;
+                },
+                TraceEvent::Out { port, .. } => {
+                    self.outputs += 1;
+                    *self.ports.entry(("OUT", port)).or_default() += 1;
+                },
+                TraceEvent::Halt { .. } => self.halts += 1,
+            }
+        }
+    }
+
+    fn print(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(out, "instructions: {}", self.instructions)?;
+        writeln!(out, "states: {}", self.states)?;
+        writeln!(out, "interrupts: {}", self.interrupts)?;
+        writeln!(out, "halts: {}", self.halts)?;
+        writeln!(out, "memory writes: {}", self.writes)?;
+        writeln!(out, "port inputs: {}", self.inputs)?;
+        writeln!(out, "port outputs: {}", self.outputs)?;
+        writeln!(out, "distinct addresses: {}", self.addresses.len())?;
+
+        let percent = |count: u64| 100.0 * count as f64 / self.instructions.max(1) as f64;
+        writeln!(out, "\nmost executed instructions:")?;
+        for (mnemonic, count) in top(&self.mnemonics) {
+            writeln!(out, "    {:<6} {:>14} {:>6.2}%", mnemonic, count, percent(count))?;
+        }
+        writeln!(out, "\nmost executed addresses:")?;
+        for (address, count) in top(&self.addresses) {
+            writeln!(out, "    {:04X}   {:>14} {:>6.2}%", address, count, percent(count))?;
+        }
+        if !self.ports.is_empty() {
+            writeln!(out, "\nports:")?;
+            let mut ports: Vec<_> = self.ports.iter().collect();
+            ports.sort_by_key(|&(&(direction, port), _)| (port, direction));
+            for ((direction, port), count) in ports {
+                writeln!(out, "    {:<3} {:02X} {:>14}", direction, port, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The ten largest counts, largest first.
+fn top<K: Clone + Ord>(counts: &HashMap<K, u64>) -> Vec<(K, u64)> {
+    let mut counts: Vec<(K, u64)> = counts.iter().map(|(key, &count)| (key.clone(), count)).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(10);
+    counts
+}
+
+fn main() -> std::io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().unwrap_or_else(|| usage("missing command"));
+    if !["text", "slice", "search", "stats"].contains(&command.as_str()) {
+        usage(&format!("unknown command: {}", command));
+    }
+    let path = args.next().unwrap_or_else(|| usage("missing trace"));
+    let mut output = None;
+    if command == "slice" {
+        output = Some(args.next().unwrap_or_else(|| usage("missing output trace")));
+    }
+    let mut from = 0;
+    let mut to = u64::MAX;
+    let mut search = Search::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = index(args.next()),
+            "--to" => to = index(args.next()),
+            "--pc" => search.pc = Some(hex(args.next())),
+            "--write" => search.write = Some(hex(args.next())),
+            "--in" => search.input = Some(port(args.next())),
+            "--out" => search.output = Some(port(args.next())),
+            "--opcode" => search.opcode = Some(args.next().unwrap_or_else(|| usage("missing mnemonic"))),
+            _ => usage(&format!("unknown option: {}", arg)),
+        }
+    }
+
+    let (input, gunzip) = gzip::open(&path)?;
+    let mut records = TraceReader::new(BufReader::new(input))?;
+    let variant = records.variant();
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    let mut slice = None;
+    let mut gzip = None;
+    let mut stats = Stats::default();
+    let mut stopped = false;
+
+    for record in records.by_ref() {
+        let record = record?;
+        if record.index > to {
+            stopped = true;
+            break;
+        }
+        match command.as_str() {
+            "text" if record.index >= from => record.write_text(&mut stdout)?,
+            "slice" if record.index >= from => {
+                if slice.is_none() {
+                    let (out, child) = gzip::create(output.as_deref().unwrap())?;
+                    let out = BufWriter::new(out);
+                    slice = Some(TraceWriter::with_start(out, variant, record.index, record.cycles)?);
+                    gzip = child;
+                }
+                slice.as_mut().unwrap().write(&record)?;
+            },
+            "search" if search.matches(&record) => {
+                write!(stdout, "{}\t", record.index)?;
+                record.write_text(&mut stdout)?;
+            },
+            "stats" => stats.add(&record),
+            _ => {},
+        }
+    }
+
+    if command == "stats" {
+        stats.print(&mut stdout)?;
+    }
+    stdout.flush()?;
+    match slice {
+        Some(slice) => drop(slice.finish()?),
+        None if command == "slice" => {
+            eprintln!("no instructions to copy");
+            std::process::exit(1);
+        },
+        None => {},
+    }
+    if let Some(gzip) = gzip {
+        gzip::wait(gzip)?;
+    }
+    drop(records);
+    match gunzip {
+        // Stopping early closes the pipe, which makes gzip fail.
+        Some(mut gunzip) if stopped => drop(gunzip.wait()?),
+        Some(gunzip) => gzip::wait(gunzip)?,
+        None => {},
+    }
+    Ok(())
+}
@@ -0,0 +1,1158 @@
+//! The Zilog Z80 extensions to the 8080 instruction set.
+//!
+//! The Z80 executes every documented 8080 instruction, so those are
+//! decoded as a regular [`crate::Opcode`] and executed by the 8080 core,
+//! after which the flags are adjusted to the Z80 semantics. Only the
+//! instructions the 8080 does not have are described here.
+
+use std::io::{self, Read, Write};
+
+use crate::snapshot::{
+    invalid, read_bool, read_flags, read_registers, read_u16, read_u8, write_flags, write_registers,
+};
+use crate::{Cpu, Flags, IoBus, MemoryBus, Reg8, RegPair, Registers, Variant};
+
+/// The index register selected by a DD or FD prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Index {
+    IX,
+    IY,
+}
+
+/// The rotate and shift operations of the CB group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Shift {
+    RLC,
+    RRC,
+    RL,
+    RR,
+    SLA,
+    SRA,
+    /// Undocumented: shifts left and sets bit 0.
+    SLL,
+    SRL,
+}
+
+/// The bit manipulation instructions of the CB group.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum BitOp {
+    Shift(Shift, Reg8),
+    BIT(u8, Reg8),
+    RES(u8, Reg8),
+    SET(u8, Reg8),
+}
+
+impl BitOp {
+    fn read(byte: u8) -> BitOp {
+        let bit = (byte >> 3) & 0b111;
+        let reg = Reg8::from_bits(byte);
+        match byte >> 6 {
+            0 => {
+                let shift = [
+                    Shift::RLC,
+                    Shift::RRC,
+                    Shift::RL,
+                    Shift::RR,
+                    Shift::SLA,
+                    Shift::SRA,
+                    Shift::SLL,
+                    Shift::SRL,
+                ];
+                BitOp::Shift(shift[bit as usize], reg)
+            },
+            1 => BitOp::BIT(bit, reg),
+            2 => BitOp::RES(bit, reg),
+            _ => BitOp::SET(bit, reg),
+        }
+    }
+
+    fn byte(&self) -> u8 {
+        match *self {
+            BitOp::Shift(shift, reg) => (shift as u8) << 3 | reg.bits(),
+            BitOp::BIT(bit, reg) => 0x40 | (bit & 0b111) << 3 | reg.bits(),
+            BitOp::RES(bit, reg) => 0x80 | (bit & 0b111) << 3 | reg.bits(),
+            BitOp::SET(bit, reg) => 0xc0 | (bit & 0b111) << 3 | reg.bits(),
+        }
+    }
+
+    fn reg(&self) -> Reg8 {
+        match *self {
+            BitOp::Shift(_, reg) | BitOp::BIT(_, reg) | BitOp::RES(_, reg) | BitOp::SET(_, reg) => reg,
+        }
+    }
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>, operand: &str) -> std::fmt::Result {
+        match self {
+            BitOp::Shift(shift, _) => write!(f, "{:?} {}", shift, operand),
+            BitOp::BIT(bit, _) => write!(f, "BIT {},{}", bit, operand),
+            BitOp::RES(bit, _) => write!(f, "RES {},{}", bit, operand),
+            BitOp::SET(bit, _) => write!(f, "SET {},{}", bit, operand),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InterruptMode {
+    /// The interrupting device places an instruction on the data bus,
+    /// as on the 8080.
+    #[default]
+    Mode0,
+    /// Every interrupt executes RST 7.
+    Mode1,
+    /// The interrupting device places the low byte of an address on the
+    /// data bus. The processor calls the address stored in the table
+    /// at that location, with register I as the high byte.
+    Mode2,
+}
+
+/// The instructions only the Z80 has.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum Opcode {
+    EX_AF,
+    EXX,
+    DJNZ(i8),
+    JR(i8),
+    JR_NZ(i8),
+    JR_Z(i8),
+    JR_NC(i8),
+    JR_C(i8),
+
+    // CB prefix
+    CB(BitOp),
+
+    // ED prefix
+    /// `IN r,(C)`. With [`Reg8::M`] only the flags are affected.
+    IN_C(Reg8),
+    /// `OUT (C),r`. With [`Reg8::M`] zero is output.
+    OUT_C(Reg8),
+    SBC_HL(RegPair),
+    ADC_HL(RegPair),
+    LD_NN_RR(u16, RegPair),
+    LD_RR_NN(RegPair, u16),
+    NEG,
+    RETN,
+    RETI,
+    IM(InterruptMode),
+    LD_I_A,
+    LD_R_A,
+    LD_A_I,
+    LD_A_R,
+    RRD,
+    RLD,
+    LDI,
+    LDD,
+    LDIR,
+    LDDR,
+    CPI,
+    CPD,
+    CPIR,
+    CPDR,
+    INI,
+    IND,
+    INIR,
+    INDR,
+    OUTI,
+    OUTD,
+    OTIR,
+    OTDR,
+    /// An ED prefixed byte without an instruction, which does nothing.
+    NOP_ED(u8),
+
+    // DD and FD prefixes
+    /// An unprefixed instruction executed with the index register in place
+    /// of H and L, or of the memory location at the index register plus
+    /// the displacement if it addresses memory through H and L.
+    /// Holds the bytes of the unprefixed instruction.
+    Indexed(Index, i8, [u8; 3]),
+    /// A CB prefixed instruction operating on the memory location at the
+    /// index register plus the displacement. Unless the operand is
+    /// [`Reg8::M`], the result is also copied into that register.
+    IndexedCB(Index, i8, BitOp),
+    /// A DD or FD prefix followed by another prefix, which makes it act as a NOP.
+    NONI(Index),
+}
+
+impl Opcode {
+    /// Decodes the Z80 instructions starting with `byte`, or returns `None`
+    /// if the 8080 instruction with that opcode should be decoded instead.
+    pub(crate) fn read(byte: u8, next: &mut impl FnMut() -> u8) -> Option<Opcode> {
+        let opcode = match byte {
+            0x08 => Opcode::EX_AF,
+            0x10 => Opcode::DJNZ(next() as i8),
+            0x18 => Opcode::JR(next() as i8),
+            0x20 => Opcode::JR_NZ(next() as i8),
+            0x28 => Opcode::JR_Z(next() as i8),
+            0x30 => Opcode::JR_NC(next() as i8),
+            0x38 => Opcode::JR_C(next() as i8),
+            0xd9 => Opcode::EXX,
+            0xcb => Opcode::CB(BitOp::read(next())),
+            0xed => Opcode::read_ed(next),
+            0xdd => Opcode::read_indexed(Index::IX, next),
+            0xfd => Opcode::read_indexed(Index::IY, next),
+            _ => return None,
+        };
+        Some(opcode)
+    }
+
+    fn read_ed(next: &mut impl FnMut() -> u8) -> Opcode {
+        fn word(next: &mut impl FnMut() -> u8) -> u16 {
+            let rl = next();
+            let rh = next();
+            ((rh as u16) << 8) | (rl as u16)
+        }
+
+        let byte = next();
+        let y = (byte >> 3) & 0b111;
+        let pair = RegPair::from_bits(y >> 1);
+        match (byte >> 6, byte & 0b111) {
+            (1, 0) => Opcode::IN_C(Reg8::from_bits(y)),
+            (1, 1) => Opcode::OUT_C(Reg8::from_bits(y)),
+            (1, 2) if y & 1 == 0 => Opcode::SBC_HL(pair),
+            (1, 2) => Opcode::ADC_HL(pair),
+            (1, 3) if y & 1 == 0 => Opcode::LD_NN_RR(word(next), pair),
+            (1, 3) => Opcode::LD_RR_NN(pair, word(next)),
+            (1, 4) => Opcode::NEG,
+            (1, 5) if y == 1 => Opcode::RETI,
+            (1, 5) => Opcode::RETN,
+            (1, 6) => match y & 0b11 {
+                2 => Opcode::IM(InterruptMode::Mode1),
+                3 => Opcode::IM(InterruptMode::Mode2),
+                _ => Opcode::IM(InterruptMode::Mode0),
+            },
+            (1, 7) => match y {
+                0 => Opcode::LD_I_A,
+                1 => Opcode::LD_R_A,
+                2 => Opcode::LD_A_I,
+                3 => Opcode::LD_A_R,
+                4 => Opcode::RRD,
+                5 => Opcode::RLD,
+                _ => Opcode::NOP_ED(byte),
+            },
+            (2, z) if y >= 4 && z <= 3 => {
+                let block = [
+                    [Opcode::LDI, Opcode::CPI, Opcode::INI, Opcode::OUTI],
+                    [Opcode::LDD, Opcode::CPD, Opcode::IND, Opcode::OUTD],
+                    [Opcode::LDIR, Opcode::CPIR, Opcode::INIR, Opcode::OTIR],
+                    [Opcode::LDDR, Opcode::CPDR, Opcode::INDR, Opcode::OTDR],
+                ];
+                block[y as usize - 4][z as usize]
+            },
+            _ => Opcode::NOP_ED(byte),
+        }
+    }
+
+    fn read_indexed(index: Index, next: &mut dyn FnMut() -> u8) -> Opcode {
+        let byte = next();
+        match byte {
+            0xcb => {
+                let d = next() as i8;
+                Opcode::IndexedCB(index, d, BitOp::read(next()))
+            },
+            0xdd | 0xed | 0xfd => Opcode::NONI(index),
+            _ => {
+                let d = if addresses_memory(byte) { next() as i8 } else { 0 };
+                let mut bytes = [0; 3];
+                let mut len = 0;
+                crate::Opcode::read(Variant::Z80, || {
+                    let value = if len == 0 { byte } else { next() };
+                    bytes[len] = value;
+                    len += 1;
+                    value
+                });
+                Opcode::Indexed(index, d, bytes)
+            },
+        }
+    }
+
+    /// The first byte of the instruction, which is the prefix
+    /// unless the 8080 leaves the opcode undocumented.
+    pub(crate) fn byte(&self) -> u8 {
+        match self {
+            Opcode::EX_AF => 0x08,
+            Opcode::EXX => 0xd9,
+            Opcode::DJNZ(_) => 0x10,
+            Opcode::JR(_) => 0x18,
+            Opcode::JR_NZ(_) => 0x20,
+            Opcode::JR_Z(_) => 0x28,
+            Opcode::JR_NC(_) => 0x30,
+            Opcode::JR_C(_) => 0x38,
+            Opcode::CB(_) => 0xcb,
+            Opcode::Indexed(index, ..) | Opcode::IndexedCB(index, ..) | Opcode::NONI(index) => match index {
+                Index::IX => 0xdd,
+                Index::IY => 0xfd,
+            },
+            _ => 0xed,
+        }
+    }
+
+    /// Encodes the instruction, with 16-bit operands in little-endian order.
+    /// A [`Opcode::NONI`] is encoded as its prefix alone, since the prefix
+    /// that follows it belongs to the next instruction.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![self.byte()];
+        match *self {
+            Opcode::EX_AF | Opcode::EXX | Opcode::NONI(_) => {},
+            Opcode::DJNZ(e) | Opcode::JR(e) => bytes.push(e as u8),
+            Opcode::JR_NZ(e) | Opcode::JR_Z(e) | Opcode::JR_NC(e) | Opcode::JR_C(e) => bytes.push(e as u8),
+            Opcode::CB(op) => bytes.push(op.byte()),
+            Opcode::Indexed(_, d, base) => {
+                let len = crate::decode_with(Variant::Z80, &base).map_or(1, |(_, len)| len);
+                bytes.push(base[0]);
+                if addresses_memory(base[0]) {
+                    bytes.push(d as u8);
+                }
+                bytes.extend(&base[1..len]);
+            },
+            Opcode::IndexedCB(_, d, op) => bytes.extend([0xcb, d as u8, op.byte()]),
+            Opcode::IN_C(r) => bytes.push(0x40 | r.bits() << 3),
+            Opcode::OUT_C(r) => bytes.push(0x41 | r.bits() << 3),
+            Opcode::SBC_HL(rp) => bytes.push(0x42 | rp.bits() << 4),
+            Opcode::ADC_HL(rp) => bytes.push(0x4a | rp.bits() << 4),
+            Opcode::LD_NN_RR(adr, rp) => {
+                bytes.push(0x43 | rp.bits() << 4);
+                bytes.extend(adr.to_le_bytes());
+            },
+            Opcode::LD_RR_NN(rp, adr) => {
+                bytes.push(0x4b | rp.bits() << 4);
+                bytes.extend(adr.to_le_bytes());
+            },
+            Opcode::NEG => bytes.push(0x44),
+            Opcode::RETN => bytes.push(0x45),
+            Opcode::RETI => bytes.push(0x4d),
+            Opcode::IM(InterruptMode::Mode0) => bytes.push(0x46),
+            Opcode::IM(InterruptMode::Mode1) => bytes.push(0x56),
+            Opcode::IM(InterruptMode::Mode2) => bytes.push(0x5e),
+            Opcode::LD_I_A => bytes.push(0x47),
+            Opcode::LD_R_A => bytes.push(0x4f),
+            Opcode::LD_A_I => bytes.push(0x57),
+            Opcode::LD_A_R => bytes.push(0x5f),
+            Opcode::RRD => bytes.push(0x67),
+            Opcode::RLD => bytes.push(0x6f),
+            Opcode::LDI => bytes.push(0xa0),
+            Opcode::CPI => bytes.push(0xa1),
+            Opcode::INI => bytes.push(0xa2),
+            Opcode::OUTI => bytes.push(0xa3),
+            Opcode::LDD => bytes.push(0xa8),
+            Opcode::CPD => bytes.push(0xa9),
+            Opcode::IND => bytes.push(0xaa),
+            Opcode::OUTD => bytes.push(0xab),
+            Opcode::LDIR => bytes.push(0xb0),
+            Opcode::CPIR => bytes.push(0xb1),
+            Opcode::INIR => bytes.push(0xb2),
+            Opcode::OTIR => bytes.push(0xb3),
+            Opcode::LDDR => bytes.push(0xb8),
+            Opcode::CPDR => bytes.push(0xb9),
+            Opcode::INDR => bytes.push(0xba),
+            Opcode::OTDR => bytes.push(0xbb),
+            Opcode::NOP_ED(byte) => bytes.push(byte),
+        }
+        bytes
+    }
+
+    /// Number of opcode fetches, each of which increments register R.
+    fn fetches(&self) -> u8 {
+        match self {
+            Opcode::EX_AF | Opcode::EXX | Opcode::NONI(_) => 1,
+            Opcode::DJNZ(_) | Opcode::JR(_) => 1,
+            Opcode::JR_NZ(_) | Opcode::JR_Z(_) | Opcode::JR_NC(_) | Opcode::JR_C(_) => 1,
+            _ => 2,
+        }
+    }
+
+    /// Number of states (clock periods) the instruction takes to execute.
+    /// For conditional and repeating instructions this is the duration
+    /// when the condition is not met, or when the last repetition is done.
+    pub fn states(&self) -> u32 {
+        use Opcode::*;
+        match self {
+            EX_AF | EXX | NONI(_) => 4,
+            DJNZ(_) => 8,
+            JR(_) => 12,
+            JR_NZ(_) | JR_Z(_) | JR_NC(_) | JR_C(_) => 7,
+
+            CB(BitOp::BIT(_, Reg8::M)) => 12,
+            CB(op) if op.reg() == Reg8::M => 15,
+            CB(_) => 8,
+
+            IN_C(_) | OUT_C(_) => 12,
+            SBC_HL(_) | ADC_HL(_) => 15,
+            LD_NN_RR(..) | LD_RR_NN(..) => 20,
+            NEG | IM(_) | NOP_ED(_) => 8,
+            RETN | RETI => 14,
+            LD_I_A | LD_R_A | LD_A_I | LD_A_R => 9,
+            RRD | RLD => 18,
+            LDI | LDD | LDIR | LDDR | CPI | CPD | CPIR | CPDR => 16,
+            INI | IND | INIR | INDR | OUTI | OUTD | OTIR | OTDR => 16,
+
+            Indexed(_, _, bytes) => base(bytes).states(Variant::Z80) + indexed_states(bytes),
+            IndexedCB(_, _, BitOp::BIT(..)) => 20,
+            IndexedCB(..) => 23,
+        }
+    }
+
+    /// Number of states the instruction takes when its condition is met,
+    /// or when it repeats. Equal to [`Opcode::states`] otherwise.
+    pub fn states_taken(&self) -> u32 {
+        use Opcode::*;
+        match self {
+            DJNZ(_) | JR_NZ(_) | JR_Z(_) | JR_NC(_) | JR_C(_) => self.states() + 5,
+            LDIR | LDDR | CPIR | CPDR | INIR | INDR | OTIR | OTDR => self.states() + 5,
+            Indexed(_, _, bytes) => base(bytes).states_taken(Variant::Z80) + indexed_states(bytes),
+            _ => self.states(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Opcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pair = |pair: &RegPair| format!("{:?}", pair);
+        match self {
+            Opcode::EX_AF => f.write_str("EX AF,AF'"),
+            Opcode::EXX => f.write_str("EXX"),
+            Opcode::DJNZ(e) => f.write_fmt(format_args!("DJNZ {:+}", e)),
+            Opcode::JR(e) => f.write_fmt(format_args!("JR {:+}", e)),
+            Opcode::JR_NZ(e) => f.write_fmt(format_args!("JR NZ,{:+}", e)),
+            Opcode::JR_Z(e) => f.write_fmt(format_args!("JR Z,{:+}", e)),
+            Opcode::JR_NC(e) => f.write_fmt(format_args!("JR NC,{:+}", e)),
+            Opcode::JR_C(e) => f.write_fmt(format_args!("JR C,{:+}", e)),
+            Opcode::CB(op) => op.fmt(f, name(op.reg())),
+            Opcode::IN_C(Reg8::M) => f.write_str("IN F,(C)"),
+            Opcode::IN_C(reg) => f.write_fmt(format_args!("IN {},(C)", name(*reg))),
+            Opcode::OUT_C(Reg8::M) => f.write_str("OUT (C),0"),
+            Opcode::OUT_C(reg) => f.write_fmt(format_args!("OUT (C),{}", name(*reg))),
+            Opcode::SBC_HL(p) => f.write_fmt(format_args!("SBC HL,{}", pair(p))),
+            Opcode::ADC_HL(p) => f.write_fmt(format_args!("ADC HL,{}", pair(p))),
+            Opcode::LD_NN_RR(adr, p) => f.write_fmt(format_args!("LD ({:#06x}),{}", adr, pair(p))),
+            Opcode::LD_RR_NN(p, adr) => f.write_fmt(format_args!("LD {},({:#06x})", pair(p), adr)),
+            Opcode::NEG => f.write_str("NEG"),
+            Opcode::RETN => f.write_str("RETN"),
+            Opcode::RETI => f.write_str("RETI"),
+            Opcode::IM(InterruptMode::Mode0) => f.write_str("IM 0"),
+            Opcode::IM(InterruptMode::Mode1) => f.write_str("IM 1"),
+            Opcode::IM(InterruptMode::Mode2) => f.write_str("IM 2"),
+            Opcode::LD_I_A => f.write_str("LD I,A"),
+            Opcode::LD_R_A => f.write_str("LD R,A"),
+            Opcode::LD_A_I => f.write_str("LD A,I"),
+            Opcode::LD_A_R => f.write_str("LD A,R"),
+            Opcode::RRD => f.write_str("RRD"),
+            Opcode::RLD => f.write_str("RLD"),
+            Opcode::LDI => f.write_str("LDI"),
+            Opcode::LDD => f.write_str("LDD"),
+            Opcode::LDIR => f.write_str("LDIR"),
+            Opcode::LDDR => f.write_str("LDDR"),
+            Opcode::CPI => f.write_str("CPI"),
+            Opcode::CPD => f.write_str("CPD"),
+            Opcode::CPIR => f.write_str("CPIR"),
+            Opcode::CPDR => f.write_str("CPDR"),
+            Opcode::INI => f.write_str("INI"),
+            Opcode::IND => f.write_str("IND"),
+            Opcode::INIR => f.write_str("INIR"),
+            Opcode::INDR => f.write_str("INDR"),
+            Opcode::OUTI => f.write_str("OUTI"),
+            Opcode::OUTD => f.write_str("OUTD"),
+            Opcode::OTIR => f.write_str("OTIR"),
+            Opcode::OTDR => f.write_str("OTDR"),
+            Opcode::NOP_ED(byte) => f.write_fmt(format_args!("*NOP ED {:#04x}", byte)),
+            Opcode::Indexed(index, d, bytes) => {
+                let base = base(bytes);
+                if addresses_memory(bytes[0]) {
+                    f.write_fmt(format_args!("{:?} [{:?}{:+}]", base, index, d))
+                } else {
+                    f.write_fmt(format_args!("{:?} [{:?}]", base, index))
+                }
+            },
+            Opcode::IndexedCB(index, d, op) => {
+                op.fmt(f, &format!("({:?}{:+})", index, d))?;
+                match op {
+                    BitOp::BIT(..) => Ok(()),
+                    _ if op.reg() == Reg8::M => Ok(()),
+                    _ => f.write_fmt(format_args!(",{}", name(op.reg()))),
+                }
+            },
+            Opcode::NONI(index) => f.write_fmt(format_args!("NONI {:?}", index)),
+        }
+    }
+}
+
+/// Registers and interrupt state that only exist on the Z80.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct State {
+    pub ix: u16,
+    pub iy: u16,
+    /// Interrupt vector register, the high byte of the table used in mode 2.
+    pub i: u8,
+    /// Memory refresh register, incremented by every opcode fetch.
+    pub r: u8,
+    /// The alternate register set, swapped in by EXX.
+    pub alt: Registers,
+    /// The alternate accumulator and flags, swapped in by `EX AF,AF'`.
+    pub alt_a: u8,
+    pub alt_flags: Flags,
+    pub im: InterruptMode,
+    /// Second interrupt enable flip-flop, which holds the interrupt
+    /// enable status while a non-maskable interrupt is serviced.
+    pub iff2: bool,
+    pub(crate) nmi: bool,
+    pub(crate) vector: u8,
+}
+
+impl State {
+    pub(crate) fn reset(&mut self) {
+        self.i = 0;
+        self.r = 0;
+        self.im = InterruptMode::Mode0;
+        self.iff2 = false;
+        self.nmi = false;
+    }
+
+    pub(crate) fn save(&self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(&self.ix.to_le_bytes())?;
+        out.write_all(&self.iy.to_le_bytes())?;
+        out.write_all(&[self.i, self.r])?;
+        write_registers(out, &self.alt)?;
+        out.write_all(&[self.alt_a])?;
+        write_flags(out, &self.alt_flags)?;
+        let im = match self.im {
+            InterruptMode::Mode0 => 0,
+            InterruptMode::Mode1 => 1,
+            InterruptMode::Mode2 => 2,
+        };
+        out.write_all(&[im, self.iff2 as u8, self.nmi as u8, self.vector])
+    }
+
+    pub(crate) fn restore(&mut self, input: &mut dyn Read) -> io::Result<()> {
+        self.ix = read_u16(input)?;
+        self.iy = read_u16(input)?;
+        self.i = read_u8(input)?;
+        self.r = read_u8(input)?;
+        self.alt = read_registers(input)?;
+        self.alt_a = read_u8(input)?;
+        self.alt_flags = read_flags(input)?;
+        self.im = match read_u8(input)? {
+            0 => InterruptMode::Mode0,
+            1 => InterruptMode::Mode1,
+            2 => InterruptMode::Mode2,
+            _ => return Err(invalid()),
+        };
+        self.iff2 = read_bool(input)?;
+        self.nmi = read_bool(input)?;
+        self.vector = read_u8(input)?;
+        Ok(())
+    }
+
+    fn index(&self, index: Index) -> u16 {
+        match index {
+            Index::IX => self.ix,
+            Index::IY => self.iy,
+        }
+    }
+
+    fn set_index(&mut self, index: Index, value: u16) {
+        match index {
+            Index::IX => self.ix = value,
+            Index::IY => self.iy = value,
+        }
+    }
+}
+
+/// The Zilog name of an 8-bit operand.
+fn name(reg: Reg8) -> &'static str {
+    ["B", "C", "D", "E", "H", "L", "(HL)", "A"][reg as usize]
+}
+
+/// Whether the unprefixed instruction addresses memory through H and L,
+/// which a DD or FD prefix turns into the index register plus a displacement.
+fn addresses_memory(byte: u8) -> bool {
+    match byte {
+        0x34..=0x36 => true,
+        0x76 => false,
+        0x40..=0x7f => byte & 0b111 == 6 || byte & 0b11_1000 == 0b11_0000,
+        0x80..=0xbf => byte & 0b111 == 6,
+        _ => false,
+    }
+}
+
+fn base(bytes: &[u8; 3]) -> crate::Opcode {
+    let mut bytes = bytes.iter();
+    crate::Opcode::read(Variant::Z80, || *bytes.next().unwrap_or(&0))
+}
+
+/// States added to the unprefixed instruction by the prefix and displacement.
+fn indexed_states(bytes: &[u8; 3]) -> u32 {
+    match bytes[0] {
+        0x36 => 9,
+        byte if addresses_memory(byte) => 12,
+        _ => 4,
+    }
+}
+
+/// Executes a single decoded instruction on the Z80 and returns the
+/// number of states it took, or `None` if the instruction is not supported.
+pub(crate) fn emulate<M: MemoryBus, I: IoBus>(cpu: &mut Cpu<M, I>, opcode: crate::Opcode) -> Option<u32> {
+    let fetches = match opcode {
+        crate::Opcode::Z80(op) => op.fetches(),
+        _ => 1,
+    };
+    let r = cpu.z80.r;
+    cpu.z80.r = (r & 0x80) | (r.wrapping_add(fetches) & 0x7f);
+
+    run(cpu, opcode)
+}
+
+fn run<M: MemoryBus, I: IoBus>(cpu: &mut Cpu<M, I>, opcode: crate::Opcode) -> Option<u32> {
+    use crate::Opcode::*;
+    match opcode {
+        Z80(op) => return execute(cpu, op),
+
+        // DAA also corrects the accumulator after a subtraction.
+        DAA => {
+            daa(&mut cpu.reg.a, &mut cpu.reg.flags);
+            return Some(opcode.states(Variant::Z80));
+        },
+
+        // ADD HL,rp
+        DAD_BC | DAD_DE | DAD_HL | DAD_SP => {
+            let rp = match opcode {
+                DAD_BC => RegPair::BC,
+                DAD_DE => RegPair::DE,
+                DAD_HL => RegPair::HL,
+                _ => RegPair::SP,
+            };
+            let hl = cpu.reg.m();
+            let value = cpu.get16(rp);
+            let (res, cy) = hl.overflowing_add(value);
+            cpu.set16(RegPair::HL, res);
+            cpu.reg.flags.set_carry(cy);
+            cpu.reg.flags.set_carry_aux((hl ^ value ^ res) & 0x1000 != 0);
+            cpu.reg.flags.set_subtract(false);
+            return Some(opcode.states(Variant::Z80));
+        },
+        _ => {},
+    }
+
+    let states = crate::emulate(cpu, opcode)?;
+
+    // The Z80 reports overflow instead of parity after arithmetic, a borrow
+    // instead of a carry in H after subtraction, and sets H and N differently
+    // for the other instructions it shares with the 8080.
+    let flags = &mut cpu.reg.flags;
+    match opcode {
+        ADD_B | ADD_C | ADD_D | ADD_E | ADD_H | ADD_L | ADD_M | ADD_A | ADI(_)
+        | ADC_B | ADC_C | ADC_D | ADC_E | ADC_H | ADC_L | ADC_M | ADC_A | ACI(_)
+        | INR_B | INR_C | INR_D | INR_E | INR_H | INR_L | INR_M | INR_A => {
+            flags.set_parity(flags.overflow());
+            flags.set_subtract(false);
+        },
+        SUB_B | SUB_C | SUB_D | SUB_E | SUB_H | SUB_L | SUB_M | SUB_A | SUI(_)
+        | SBB_B | SBB_C | SBB_D | SBB_E | SBB_H | SBB_L | SBB_M | SBB_A | SBI(_)
+        | CMP_B | CMP_C | CMP_D | CMP_E | CMP_H | CMP_L | CMP_M | CMP_A | CPI(_)
+        | DCR_B | DCR_C | DCR_D | DCR_E | DCR_H | DCR_L | DCR_M | DCR_A => {
+            flags.set_parity(flags.overflow());
+            flags.set_subtract(true);
+            flags.set_carry_aux(!flags.carry_aux());
+        },
+        ANA_B | ANA_C | ANA_D | ANA_E | ANA_H | ANA_L | ANA_M | ANA_A | ANI(_) => {
+            flags.set_carry_aux(true);
+            flags.set_subtract(false);
+        },
+        XRA_B | XRA_C | XRA_D | XRA_E | XRA_H | XRA_L | XRA_M | XRA_A | XRI(_)
+        | ORA_B | ORA_C | ORA_D | ORA_E | ORA_H | ORA_L | ORA_M | ORA_A | ORI(_)
+        | RLC | RRC | RAL | RAR | STC => {
+            flags.set_carry_aux(false);
+            flags.set_subtract(false);
+        },
+        CMA => {
+            flags.set_carry_aux(true);
+            flags.set_subtract(true);
+        },
+        CMC => {
+            flags.set_carry_aux(!flags.carry());
+            flags.set_subtract(false);
+        },
+        EI | DI => cpu.z80.iff2 = cpu.inte,
+        _ => {},
+    }
+
+    Some(states)
+}
+
+fn daa(a: &mut u8, flags: &mut Flags) {
+    let mut adjust = 0;
+    let mut carry = flags.carry();
+    if flags.carry_aux() || *a & 0x0f > 9 {
+        adjust |= 0x06;
+    }
+    if carry || *a > 0x99 {
+        adjust |= 0x60;
+        carry = true;
+    }
+
+    let res = if flags.subtract() {
+        flags.set_carry_aux(flags.carry_aux() && *a & 0x0f < 6);
+        a.wrapping_sub(adjust)
+    } else {
+        flags.set_carry_aux(*a & 0x0f > 9);
+        a.wrapping_add(adjust)
+    };
+    flags.upd(res, Some(carry));
+    *a = res;
+}
+
+fn shift(flags: &mut Flags, shift: Shift, value: u8) -> u8 {
+    let cy = flags.carry() as u8;
+    let (res, carry) = match shift {
+        Shift::RLC => (value.rotate_left(1), value & 0x80 != 0),
+        Shift::RRC => (value.rotate_right(1), value & 0x01 != 0),
+        Shift::RL => ((value << 1) | cy, value & 0x80 != 0),
+        Shift::RR => ((value >> 1) | (cy << 7), value & 0x01 != 0),
+        Shift::SLA => (value << 1, value & 0x80 != 0),
+        Shift::SRA => ((value >> 1) | (value & 0x80), value & 0x01 != 0),
+        Shift::SLL => ((value << 1) | 1, value & 0x80 != 0),
+        Shift::SRL => (value >> 1, value & 0x01 != 0),
+    };
+    flags.upd(res, Some(carry));
+    flags.set_carry_aux(false);
+    flags.set_subtract(false);
+    res
+}
+
+/// Applies a bit manipulation to `value`, returning the result to be
+/// written back, or `None` for BIT, which only affects the flags.
+fn bit_op(flags: &mut Flags, op: BitOp, value: u8) -> Option<u8> {
+    match op {
+        BitOp::Shift(s, _) => Some(shift(flags, s, value)),
+        BitOp::BIT(bit, _) => {
+            let set = value & (1 << bit) != 0;
+            flags.set_zero(!set);
+            flags.set_parity(!set);
+            flags.set_sign(bit == 7 && set);
+            flags.set_carry_aux(true);
+            flags.set_subtract(false);
+            None
+        },
+        BitOp::RES(bit, _) => Some(value & !(1 << bit)),
+        BitOp::SET(bit, _) => Some(value | (1 << bit)),
+    }
+}
+
+fn jump_relative<M: MemoryBus, I: IoBus>(cpu: &mut Cpu<M, I>, e: i8, cond: bool) -> bool {
+    if cond {
+        cpu.reg.pc = cpu.reg.pc.wrapping_add(e as u16);
+    }
+    cond
+}
+
+fn execute<M: MemoryBus, I: IoBus>(cpu: &mut Cpu<M, I>, opcode: Opcode) -> Option<u32> {
+    // Conditional and repeating instructions take longer when they jump back.
+    let mut taken = false;
+
+    match opcode {
+        Opcode::EX_AF => {
+            std::mem::swap(&mut cpu.reg.a, &mut cpu.z80.alt_a);
+            std::mem::swap(&mut cpu.reg.flags, &mut cpu.z80.alt_flags);
+        },
+        Opcode::EXX => {
+            let alt = &mut cpu.z80.alt;
+            std::mem::swap(&mut cpu.reg.b, &mut alt.b);
+            std::mem::swap(&mut cpu.reg.c, &mut alt.c);
+            std::mem::swap(&mut cpu.reg.d, &mut alt.d);
+            std::mem::swap(&mut cpu.reg.e, &mut alt.e);
+            std::mem::swap(&mut cpu.reg.h, &mut alt.h);
+            std::mem::swap(&mut cpu.reg.l, &mut alt.l);
+        },
+        Opcode::DJNZ(e) => {
+            cpu.reg.b = cpu.reg.b.wrapping_sub(1);
+            taken = jump_relative(cpu, e, cpu.reg.b != 0);
+        },
+        Opcode::JR(e) => { jump_relative(cpu, e, true); },
+        Opcode::JR_NZ(e) => taken = jump_relative(cpu, e, !cpu.reg.flags.zero()),
+        Opcode::JR_Z(e)  => taken = jump_relative(cpu, e, cpu.reg.flags.zero()),
+        Opcode::JR_NC(e) => taken = jump_relative(cpu, e, !cpu.reg.flags.carry()),
+        Opcode::JR_C(e)  => taken = jump_relative(cpu, e, cpu.reg.flags.carry()),
+
+        Opcode::CB(op) => {
+            let value = cpu.get8(op.reg());
+            if let Some(res) = bit_op(&mut cpu.reg.flags, op, value) {
+                cpu.set8(op.reg(), res);
+            }
+        },
+
+        Opcode::IN_C(r) => {
+            let value = cpu.input(cpu.reg.c);
+            cpu.reg.flags.upd(value, None);
+            cpu.reg.flags.set_carry_aux(false);
+            cpu.reg.flags.set_subtract(false);
+            if r != Reg8::M {
+                cpu.set8(r, value);
+            }
+        },
+        Opcode::OUT_C(r) => {
+            let value = if r == Reg8::M { 0 } else { cpu.get8(r) };
+            cpu.output(cpu.reg.c, value);
+        },
+        Opcode::SBC_HL(rp) | Opcode::ADC_HL(rp) => {
+            let subtract = matches!(opcode, Opcode::SBC_HL(_));
+            let hl = cpu.reg.m() as u32;
+            let value = cpu.get16(rp) as u32;
+            let cy = cpu.reg.flags.carry() as u32;
+            let res = if subtract {
+                hl.wrapping_sub(value).wrapping_sub(cy)
+            } else {
+                hl + value + cy
+            };
+            let overflow = if subtract {
+                (hl ^ value) & (hl ^ res) & 0x8000
+            } else {
+                (hl ^ res) & (value ^ res) & 0x8000
+            };
+            cpu.set16(RegPair::HL, res as u16);
+            cpu.reg.flags.set_sign(res & 0x8000 != 0);
+            cpu.reg.flags.set_zero(res as u16 == 0);
+            cpu.reg.flags.set_carry_aux((hl ^ value ^ res) & 0x1000 != 0);
+            cpu.reg.flags.set_parity(overflow != 0);
+            cpu.reg.flags.set_overflow(overflow != 0);
+            cpu.reg.flags.set_subtract(subtract);
+            cpu.reg.flags.set_carry(res & 0x1_0000 != 0);
+        },
+        Opcode::LD_NN_RR(adr, rp) => {
+            let value = cpu.get16(rp);
+            cpu.write(adr, value as u8);
+            cpu.write(adr.wrapping_add(1), (value >> 8) as u8);
+        },
+        Opcode::LD_RR_NN(rp, adr) => {
+            let rl = cpu.read(adr);
+            let rh = cpu.read(adr.wrapping_add(1));
+            cpu.set16(rp, ((rh as u16) << 8) | (rl as u16));
+        },
+        Opcode::NEG => {
+            cpu.reg.a = crate::sub(0, cpu.reg.a, &mut cpu.reg.flags);
+            cpu.reg.flags.set_parity(cpu.reg.flags.overflow());
+            cpu.reg.flags.set_subtract(true);
+            cpu.reg.flags.set_carry_aux(!cpu.reg.flags.carry_aux());
+        },
+        Opcode::RETN | Opcode::RETI => {
+            cpu.ret(true);
+            cpu.inte = cpu.z80.iff2;
+        },
+        Opcode::IM(mode) => cpu.z80.im = mode,
+        Opcode::LD_I_A => cpu.z80.i = cpu.reg.a,
+        Opcode::LD_R_A => cpu.z80.r = cpu.reg.a,
+        Opcode::LD_A_I | Opcode::LD_A_R => {
+            cpu.reg.a = if opcode == Opcode::LD_A_I { cpu.z80.i } else { cpu.z80.r };
+            cpu.reg.flags.upd(cpu.reg.a, None);
+            cpu.reg.flags.set_carry_aux(false);
+            cpu.reg.flags.set_subtract(false);
+            cpu.reg.flags.set_parity(cpu.z80.iff2);
+        },
+        Opcode::RRD | Opcode::RLD => {
+            let m = cpu.read(cpu.reg.m());
+            let a = cpu.reg.a;
+            let (m, a) = if opcode == Opcode::RRD {
+                ((a << 4) | (m >> 4), (a & 0xf0) | (m & 0x0f))
+            } else {
+                ((m << 4) | (a & 0x0f), (a & 0xf0) | (m >> 4))
+            };
+            cpu.write(cpu.reg.m(), m);
+            cpu.reg.a = a;
+            cpu.reg.flags.upd(a, None);
+            cpu.reg.flags.set_carry_aux(false);
+            cpu.reg.flags.set_subtract(false);
+        },
+        Opcode::LDI | Opcode::LDD | Opcode::LDIR | Opcode::LDDR => {
+            let step = if matches!(opcode, Opcode::LDI | Opcode::LDIR) { 1 } else { 0xffff };
+            let value = cpu.read(cpu.reg.m());
+            cpu.write(cpu.reg.de(), value);
+            cpu.set16(RegPair::HL, cpu.reg.m().wrapping_add(step));
+            cpu.set16(RegPair::DE, cpu.reg.de().wrapping_add(step));
+            cpu.set16(RegPair::BC, cpu.reg.bc().wrapping_sub(1));
+            cpu.reg.flags.set_parity(cpu.reg.bc() != 0);
+            cpu.reg.flags.set_carry_aux(false);
+            cpu.reg.flags.set_subtract(false);
+            if matches!(opcode, Opcode::LDIR | Opcode::LDDR) && cpu.reg.bc() != 0 {
+                cpu.reg.pc = cpu.reg.pc.wrapping_sub(2);
+                taken = true;
+            }
+        },
+        Opcode::CPI | Opcode::CPD | Opcode::CPIR | Opcode::CPDR => {
+            let step = if matches!(opcode, Opcode::CPI | Opcode::CPIR) { 1 } else { 0xffff };
+            let value = cpu.read(cpu.reg.m());
+            let carry = cpu.reg.flags.carry();
+            let res = crate::sub(cpu.reg.a, value, &mut cpu.reg.flags);
+            cpu.set16(RegPair::HL, cpu.reg.m().wrapping_add(step));
+            cpu.set16(RegPair::BC, cpu.reg.bc().wrapping_sub(1));
+            cpu.reg.flags.set_carry(carry);
+            cpu.reg.flags.set_parity(cpu.reg.bc() != 0);
+            cpu.reg.flags.set_subtract(true);
+            cpu.reg.flags.set_carry_aux(!cpu.reg.flags.carry_aux());
+            if matches!(opcode, Opcode::CPIR | Opcode::CPDR) && cpu.reg.bc() != 0 && res != 0 {
+                cpu.reg.pc = cpu.reg.pc.wrapping_sub(2);
+                taken = true;
+            }
+        },
+        Opcode::INI | Opcode::IND | Opcode::INIR | Opcode::INDR => {
+            let step = if matches!(opcode, Opcode::INI | Opcode::INIR) { 1 } else { 0xffff };
+            let value = cpu.input(cpu.reg.c);
+            cpu.write(cpu.reg.m(), value);
+            cpu.set16(RegPair::HL, cpu.reg.m().wrapping_add(step));
+            cpu.reg.b = cpu.reg.b.wrapping_sub(1);
+            cpu.reg.flags.set_zero(cpu.reg.b == 0);
+            cpu.reg.flags.set_subtract(true);
+            if matches!(opcode, Opcode::INIR | Opcode::INDR) && cpu.reg.b != 0 {
+                cpu.reg.pc = cpu.reg.pc.wrapping_sub(2);
+                taken = true;
+            }
+        },
+        Opcode::OUTI | Opcode::OUTD | Opcode::OTIR | Opcode::OTDR => {
+            let step = if matches!(opcode, Opcode::OUTI | Opcode::OTIR) { 1 } else { 0xffff };
+            let value = cpu.read(cpu.reg.m());
+            cpu.reg.b = cpu.reg.b.wrapping_sub(1);
+            cpu.output(cpu.reg.c, value);
+            cpu.set16(RegPair::HL, cpu.reg.m().wrapping_add(step));
+            cpu.reg.flags.set_zero(cpu.reg.b == 0);
+            cpu.reg.flags.set_subtract(true);
+            if matches!(opcode, Opcode::OTIR | Opcode::OTDR) && cpu.reg.b != 0 {
+                cpu.reg.pc = cpu.reg.pc.wrapping_sub(2);
+                taken = true;
+            }
+        },
+        Opcode::NOP_ED(_) | Opcode::NONI(_) => {},
+
+        Opcode::Indexed(index, d, bytes) => return indexed(cpu, index, d, &bytes),
+        Opcode::IndexedCB(index, d, op) => {
+            let adr = cpu.z80.index(index).wrapping_add(d as u16);
+            let value = cpu.read(adr);
+            if let Some(res) = bit_op(&mut cpu.reg.flags, op, value) {
+                cpu.write(adr, res);
+                if op.reg() != Reg8::M {
+                    cpu.set8(op.reg(), res);
+                }
+            }
+        },
+    }
+
+    if taken {
+        Some(opcode.states_taken())
+    } else {
+        Some(opcode.states())
+    }
+}
+
+/// Executes an unprefixed instruction with the index register in place of
+/// H and L, by temporarily loading it into H and L.
+fn indexed<M: MemoryBus, I: IoBus>(
+    cpu: &mut Cpu<M, I>,
+    index: Index,
+    d: i8,
+    bytes: &[u8; 3],
+) -> Option<u32> {
+    use crate::Opcode::*;
+    let base = base(bytes);
+    let hl = cpu.reg.m();
+    let ix = cpu.z80.index(index);
+    let adr = ix.wrapping_add(d as u16);
+
+    let states = match base {
+        // The prefix does not affect instructions that exchange with D and E.
+        XCHG | Z80(Opcode::EXX) => run(cpu, base)?,
+
+        // H and L are still meant when they are moved to or from memory.
+        MOV_HM => {
+            cpu.reg.h = cpu.read(adr);
+            base.states(Variant::Z80)
+        },
+        MOV_LM => {
+            cpu.reg.l = cpu.read(adr);
+            base.states(Variant::Z80)
+        },
+        MOV_MH => {
+            cpu.write(adr, cpu.reg.h);
+            base.states(Variant::Z80)
+        },
+        MOV_ML => {
+            cpu.write(adr, cpu.reg.l);
+            base.states(Variant::Z80)
+        },
+
+        _ if addresses_memory(bytes[0]) => {
+            cpu.set16(RegPair::HL, adr);
+            let states = run(cpu, base);
+            cpu.set16(RegPair::HL, hl);
+            states?
+        },
+        _ => {
+            cpu.set16(RegPair::HL, ix);
+            let states = run(cpu, base);
+            cpu.z80.set_index(index, cpu.reg.m());
+            cpu.set16(RegPair::HL, hl);
+            states?
+        },
+    };
+
+    Some(states + indexed_states(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::Memory;
+
+    fn cpu(program: &[u8]) -> Cpu {
+        let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(program)).unwrap());
+        cpu.set_variant(Variant::Z80);
+        cpu
+    }
+
+    #[test]
+    fn exx_and_ex_af_swap_in_the_alternate_registers() {
+        // LD BC,1234H; EXX; LD BC,5678H; EXX; LD A,11H; EX AF,AF'; LD A,22H; EX AF,AF'
+        let mut cpu = cpu(&[0x01, 0x34, 0x12, 0xd9, 0x01, 0x78, 0x56, 0xd9, 0x3e, 0x11, 0x08, 0x3e, 0x22, 0x08]);
+        for _ in 0..4 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.reg().bc(), 0x1234);
+        assert_eq!(cpu.z80().alt.bc(), 0x5678);
+
+        for _ in 0..4 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.reg().a, 0x11);
+        assert_eq!(cpu.z80().alt_a, 0x22);
+    }
+
+    #[test]
+    fn relative_jumps_take_longer_when_taken() {
+        // LD B,3; loop: INC A; DJNZ loop; JR $
+        let mut cpu = cpu(&[0x06, 0x03, 0x3c, 0x10, 0xfd, 0x18, 0xfe]);
+        cpu.step().unwrap();
+        for taken in [true, true, false] {
+            cpu.step().unwrap();
+            let step = cpu.step().unwrap();
+            assert_eq!(step.states, if taken { 13 } else { 8 });
+        }
+        assert_eq!((cpu.reg().a, cpu.reg().b), (3, 0));
+
+        assert_eq!(cpu.step().unwrap().states, 12);
+        assert_eq!(cpu.pc(), 5);
+    }
+
+    #[test]
+    fn ldir_repeats_until_bc_is_zero() {
+        // LD HL,0100H; LD DE,0200H; LD BC,3; LDIR
+        let mut program = vec![0x21, 0x00, 0x01, 0x11, 0x00, 0x02, 0x01, 0x03, 0x00, 0xed, 0xb0];
+        program.resize(0x100, 0);
+        program.extend([1, 2, 3]);
+        let mut cpu = cpu(&program);
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.step().unwrap().states, 21);
+        assert_eq!(cpu.step().unwrap().states, 21);
+        assert_eq!(cpu.step().unwrap().states, 16);
+
+        assert_eq!(cpu.pc(), 11);
+        assert_eq!((cpu.reg().bc(), cpu.reg().de(), cpu.reg().m()), (0, 0x203, 0x103));
+        assert_eq!((cpu.mem()[0x200], cpu.mem()[0x201], cpu.mem()[0x202]), (1, 2, 3));
+        assert!(!cpu.flags().parity());
+    }
+
+    #[test]
+    fn indexed_instructions_address_memory_relative_to_ix() {
+        // LD IX,0100H; LD (IX+2),55H; LD A,(IX+2); SET 1,(IX+2); INC IX
+        let mut cpu = cpu(&[
+            0xdd, 0x21, 0x00, 0x01, 0xdd, 0x36, 0x02, 0x55, 0xdd, 0x7e, 0x02, 0xdd, 0xcb, 0x02, 0xce, 0xdd, 0x23,
+        ]);
+        cpu.reg_mut().h = 0x80;
+        assert_eq!(cpu.step().unwrap().states, 14);
+        assert_eq!(cpu.z80().ix, 0x100);
+        assert_eq!(cpu.step().unwrap().states, 19);
+        assert_eq!(cpu.mem()[0x102], 0x55);
+        assert_eq!(cpu.step().unwrap().states, 19);
+        assert_eq!(cpu.reg().a, 0x55);
+        assert_eq!(cpu.step().unwrap().states, 23);
+        assert_eq!(cpu.mem()[0x102], 0x57);
+        cpu.step().unwrap();
+        assert_eq!(cpu.z80().ix, 0x101);
+        assert_eq!(cpu.reg().m(), 0x8000);
+    }
+
+    #[test]
+    fn arithmetic_reports_overflow_and_subtraction() {
+        // LD HL,7FFFH; LD BC,1; ADC HL,BC; LD A,1; NEG; ADD A,A
+        let mut cpu = cpu(&[0x21, 0xff, 0x7f, 0x01, 0x01, 0x00, 0xed, 0x4a, 0x3e, 0x01, 0xed, 0x44, 0x87]);
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.reg().m(), 0x8000);
+        assert!(cpu.flags().parity() && cpu.flags().carry_aux() && !cpu.flags().carry());
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().a, 0xff);
+        assert!(cpu.flags().carry() && cpu.flags().subtract());
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg().a, 0xfe);
+        assert!(cpu.flags().carry() && !cpu.flags().subtract() && !cpu.flags().parity());
+    }
+
+    #[test]
+    fn mode_2_interrupts_call_through_the_vector_table() {
+        // LD SP,0300H; IM 2; LD A,1; LD I,A; EI; NOP
+        let mut program = vec![0x31, 0x00, 0x03, 0xed, 0x5e, 0x3e, 0x01, 0xed, 0x47, 0xfb, 0x00];
+        program.resize(0x110, 0);
+        program.extend([0x34, 0x12]);
+        let mut cpu = cpu(&program);
+        for _ in 0..5 {
+            cpu.step().unwrap();
+        }
+        cpu.set_interrupt_vector(0x10);
+        cpu.interrupt(crate::Opcode::RST_7);
+
+        // The instruction after EI runs before the interrupt.
+        cpu.step().unwrap();
+        let step = cpu.step().unwrap();
+        assert!(step.interrupt);
+        assert_eq!(cpu.pc(), 0x1234);
+        assert_eq!(cpu.sp(), 0x2fe);
+        assert!(!cpu.interrupts_enabled());
+    }
+
+    #[test]
+    fn retn_restores_the_interrupt_state_after_an_nmi() {
+        // LD SP,0100H; EI; NOP ... 0066: RETN
+        let mut program = vec![0x31, 0x00, 0x01, 0xfb, 0x00];
+        program.resize(0x66, 0);
+        program.extend([0xed, 0x45]);
+        let mut cpu = cpu(&program);
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+        cpu.nmi();
+        assert!(cpu.step().unwrap().interrupt);
+        assert_eq!(cpu.pc(), 0x66);
+        assert!(!cpu.interrupts_enabled());
+        assert!(cpu.z80().iff2);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.pc(), 5);
+        assert!(cpu.interrupts_enabled());
+    }
+
+    #[test]
+    fn the_refresh_register_counts_opcode_fetches() {
+        // NOP; LD B,(IX+0); SET 0,(IX+0); LD R,A
+        let mut cpu = cpu(&[0x00, 0xdd, 0x46, 0x00, 0xdd, 0xcb, 0x00, 0xc6, 0xed, 0x4f]);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.z80().r, 5);
+        cpu.reg_mut().a = 0x80;
+        cpu.step().unwrap();
+        assert_eq!(cpu.z80().r, 0x80);
+    }
+}
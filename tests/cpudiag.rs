@@ -0,0 +1,51 @@
+use std::io::BufReader;
+
+use intel8080::{Cpu, Memory, StepError, Variant};
+
+/// Runs the Microcosm Associates CPU diagnostic, which is assembled to run
+/// under CP/M at 0x100, and returns what it printed through the BDOS.
+fn run(variant: Variant) -> String {
+    let mut program = vec![0; 0x100];
+    program.extend(std::fs::read("roms/cpudiag").unwrap());
+    let mut cpu = Cpu::new(Memory::from_reader(&mut BufReader::new(&program[..])).unwrap());
+    cpu.set_variant(variant);
+
+    // Warm boot halts, and the BDOS entry point returns straight away.
+    cpu.mem_mut()[0x0000] = 0x76;
+    cpu.mem_mut()[0x0005] = 0xc9;
+    cpu.set_pc(0x100);
+
+    let mut output = String::new();
+    loop {
+        if cpu.pc() == 0x0005 {
+            match cpu.reg().c {
+                2 => output.push(cpu.reg().e as char),
+                9 => {
+                    let mut adr = cpu.reg().de();
+                    while cpu.mem()[adr] != b'$' {
+                        output.push(cpu.mem()[adr] as char);
+                        adr += 1;
+                    }
+                },
+                _ => {},
+            }
+        }
+        match cpu.step() {
+            Ok(_) => {},
+            Err(StepError::Halted { .. }) => return output,
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
+
+#[test]
+fn cpudiag_passes_on_the_8080() {
+    let output = run(Variant::I8080);
+    assert!(output.ends_with(" CPU IS OPERATIONAL"), "{:?}", output);
+}
+
+#[test]
+fn cpudiag_passes_on_the_8085() {
+    let output = run(Variant::I8085);
+    assert!(output.ends_with(" CPU IS OPERATIONAL"), "{:?}", output);
+}